@@ -0,0 +1,150 @@
+//! Fetching and decoding of vault-owned accounts.
+//!
+//! [`AccountFetcher`] abstracts over where account data comes from: a
+//! validator through `RpcClient` (feature `rpc`), an in-process bank through
+//! `LiteSVM` (feature `litesvm`), or anything else that can return raw bytes.
+
+use anchor_lang::{prelude::*, AccountDeserialize};
+#[cfg(feature = "zero-copy")]
+use anchor_lang::ZeroCopy;
+use collateral_vault_interface::{
+    pda, CollateralVault, IntegratorPosition, IntegratorStats, MintConfig, MintStats,
+    OracleConfig, PortfolioVault, SubAccount, VaultAuthority, ID,
+};
+#[cfg(feature = "zero-copy")]
+use collateral_vault_interface::{CollateralVaultZc, VaultAuthorityZc};
+
+use crate::{ClientError, Result};
+
+/// Owner and data of an on-chain account
+pub struct RawAccount {
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+/// Source of account data
+pub trait AccountFetcher {
+    /// `None` when the account does not exist
+    fn fetch_raw(&self, address: &Pubkey) -> Result<Option<RawAccount>>;
+}
+
+#[cfg(feature = "rpc")]
+impl AccountFetcher for solana_rpc_client::rpc_client::RpcClient {
+    fn fetch_raw(&self, address: &Pubkey) -> Result<Option<RawAccount>> {
+        let response = self
+            .get_account_with_commitment(address, self.commitment())
+            .map_err(|err| ClientError::Rpc(Box::new(err)))?;
+        
+        Ok(response.value.map(|account| RawAccount {
+            owner: account.owner,
+            data: account.data,
+        }))
+    }
+}
+
+#[cfg(feature = "litesvm")]
+impl AccountFetcher for litesvm::LiteSVM {
+    fn fetch_raw(&self, address: &Pubkey) -> Result<Option<RawAccount>> {
+        Ok(self.get_account(address).map(|account| RawAccount {
+            owner: account.owner,
+            data: account.data,
+        }))
+    }
+}
+
+fn fetch_owned(fetcher: &impl AccountFetcher, address: &Pubkey) -> Result<Vec<u8>> {
+    let account = fetcher
+        .fetch_raw(address)?
+        .ok_or(ClientError::AccountNotFound(*address))?;
+    if account.owner != ID {
+        return Err(ClientError::InvalidOwner(*address));
+    }
+    
+    Ok(account.data)
+}
+
+/// Fetch and Borsh-decode any account of the program
+pub fn fetch<T: AccountDeserialize>(fetcher: &impl AccountFetcher, address: &Pubkey) -> Result<T> {
+    let data = fetch_owned(fetcher, address)?;
+    T::try_deserialize(&mut data.as_slice()).map_err(|err| ClientError::Decode(*address, err))
+}
+
+/// Fetch and decode a zero-copy account of the program
+#[cfg(feature = "zero-copy")]
+pub fn fetch_zero_copy<T: ZeroCopy>(fetcher: &impl AccountFetcher, address: &Pubkey) -> Result<T> {
+    let data = fetch_owned(fetcher, address)?;
+    let body = data
+        .strip_prefix(T::DISCRIMINATOR)
+        .filter(|body| body.len() >= std::mem::size_of::<T>())
+        .ok_or_else(|| {
+            ClientError::Decode(*address, ErrorCode::AccountDiscriminatorMismatch.into())
+        })?;
+    
+    Ok(bytemuck::pod_read_unaligned(&body[..std::mem::size_of::<T>()]))
+}
+
+pub fn fetch_vault(fetcher: &impl AccountFetcher, owner: &Pubkey) -> Result<CollateralVault> {
+    fetch(fetcher, &pda::vault(owner).0)
+}
+
+pub fn fetch_vault_authority(fetcher: &impl AccountFetcher) -> Result<VaultAuthority> {
+    fetch(fetcher, &pda::vault_authority().0)
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn fetch_vault_zc(fetcher: &impl AccountFetcher, owner: &Pubkey) -> Result<CollateralVaultZc> {
+    fetch_zero_copy(fetcher, &pda::vault_zc(owner).0)
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn fetch_vault_authority_zc(fetcher: &impl AccountFetcher) -> Result<VaultAuthorityZc> {
+    fetch_zero_copy(fetcher, &pda::vault_authority_zc().0)
+}
+
+pub fn fetch_sub_account(
+    fetcher: &impl AccountFetcher,
+    owner: &Pubkey,
+    id: u8,
+) -> Result<SubAccount> {
+    fetch(fetcher, &pda::sub_account(&pda::vault(owner).0, id).0)
+}
+
+pub fn fetch_portfolio(fetcher: &impl AccountFetcher, owner: &Pubkey) -> Result<PortfolioVault> {
+    fetch(fetcher, &pda::portfolio(owner).0)
+}
+
+pub fn fetch_mint_config(fetcher: &impl AccountFetcher, token_mint: &Pubkey) -> Result<MintConfig> {
+    fetch(fetcher, &pda::mint_config(token_mint).0)
+}
+
+pub fn fetch_mint_stats(fetcher: &impl AccountFetcher, token_mint: &Pubkey) -> Result<MintStats> {
+    fetch(fetcher, &pda::mint_stats(token_mint).0)
+}
+
+pub fn fetch_oracle_config(
+    fetcher: &impl AccountFetcher,
+    token_mint: &Pubkey,
+) -> Result<OracleConfig> {
+    fetch(fetcher, &pda::oracle_config(token_mint).0)
+}
+
+pub fn fetch_integrator_stats(
+    fetcher: &impl AccountFetcher,
+    program: &Pubkey,
+) -> Result<IntegratorStats> {
+    fetch(fetcher, &pda::integrator_stats(program).0)
+}
+
+/// Locks `program` holds in `holder`, a vault, sub-account or portfolio
+pub fn fetch_integrator_position(
+    fetcher: &impl AccountFetcher,
+    program: &Pubkey,
+    holder: &Pubkey,
+) -> Result<IntegratorPosition> {
+    fetch(fetcher, &pda::integrator_position(program, holder).0)
+}
+
+/// Decode the return data of a view instruction (`get_vault_balances` etc.)
+pub fn decode_return_data<T: AnchorDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_from_slice(data).map_err(ClientError::ReturnData)
+}
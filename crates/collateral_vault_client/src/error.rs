@@ -0,0 +1,126 @@
+//! Client errors and mapping of on-chain error codes back to [`VaultError`].
+
+use anchor_lang::{
+    error::ERROR_CODE_OFFSET, prelude::Pubkey, solana_program::instruction::error::InstructionError,
+};
+use collateral_vault_interface::VaultError;
+use solana_transaction_error::TransactionError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("account {0} is not owned by the collateral vault program")]
+    InvalidOwner(Pubkey),
+    #[error("failed to decode account {0}: {1}")]
+    Decode(Pubkey, anchor_lang::error::Error),
+    #[error("failed to decode return data: {0}")]
+    ReturnData(std::io::Error),
+    #[cfg(feature = "rpc")]
+    #[error(transparent)]
+    Rpc(Box<solana_rpc_client_api::client_error::Error>),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// Every `VaultError` variant, in declaration order
+const VAULT_ERRORS: [VaultError; 51] = [
+    VaultError::InvalidAmount,
+    VaultError::InsufficientAvailableBalance,
+    VaultError::InsufficientLockedBalance,
+    VaultError::Unauthorized,
+    VaultError::UnauthorizedProgram,
+    VaultError::Overflow,
+    VaultError::Underflow,
+    VaultError::InvalidTokenMint,
+    VaultError::TooManyPrograms,
+    VaultError::ProgramAlreadyAuthorized,
+    VaultError::VaultNotEmpty,
+    VaultError::HasLockedCollateral,
+    VaultError::PooledVault,
+    VaultError::NotPooledVault,
+    VaultError::SameVault,
+    VaultError::MissingCustodyPool,
+    VaultError::PoolVaultSetMismatch,
+    VaultError::CustodyBalanceMismatch,
+    VaultError::HasSubAccountBalance,
+    VaultError::TooManyMints,
+    VaultError::MintAlreadyAdded,
+    VaultError::MintNotInPortfolio,
+    VaultError::InvalidTokenAccount,
+    VaultError::InvalidBasket,
+    VaultError::InvalidOracleConfig,
+    VaultError::InvalidOracle,
+    VaultError::InvalidOraclePrice,
+    VaultError::StaleOraclePrice,
+    VaultError::OracleConfidenceTooWide,
+    VaultError::MintNotApproved,
+    VaultError::FreezeAuthorityNotAllowed,
+    VaultError::ExtensionNotAllowed,
+    VaultError::DepositLimitExceeded,
+    VaultError::PermanentDelegateNotAllowed,
+    VaultError::NonTransferableNotAllowed,
+    VaultError::DefaultAccountStateFrozen,
+    VaultError::TransferHookNotAllowed,
+    VaultError::TransferFeeNotAllowed,
+    VaultError::PausableNotAllowed,
+    VaultError::ExtensionDenied,
+    VaultError::DepositCapExceeded,
+    VaultError::IntegratorCapExceeded,
+    VaultError::InvalidVaultLayout,
+    VaultError::InvalidSubAccount,
+    VaultError::BalanceSplitMismatch,
+    VaultError::CustodyShortfall,
+    VaultError::LifetimeBalanceMismatch,
+    VaultError::ProgramNotAuthorized,
+    VaultError::InvalidAuthorityLayout,
+    VaultError::AuthorityNotMigrated,
+    VaultError::LockNotHeld,
+];
+
+/// `VaultError` for a custom program error code (`6000 + variant index`)
+pub fn vault_error(code: u32) -> Option<VaultError> {
+    let index = code.checked_sub(ERROR_CODE_OFFSET)?;
+    VAULT_ERRORS.get(index as usize).copied()
+}
+
+/// `VaultError` behind a failed transaction.
+///
+/// Custom codes are not tagged with the program that raised them, so this is
+/// only meaningful when the failing instruction targets the vault program.
+pub fn transaction_vault_error(err: &TransactionError) -> Option<VaultError> {
+    match err {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => vault_error(*code),
+        _ => None,
+    }
+}
+
+/// `VaultError` behind a failed RPC `send_transaction` or simulation
+#[cfg(feature = "rpc")]
+pub fn rpc_vault_error(err: &solana_rpc_client_api::client_error::Error) -> Option<VaultError> {
+    err.get_transaction_error()
+        .as_ref()
+        .and_then(transaction_vault_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn vault_errors_match_program_codes() {
+        for (index, &error) in VAULT_ERRORS.iter().enumerate() {
+            let Error::AnchorError(anchor_error) = Error::from(error) else {
+                panic!("{error} is not an Anchor error");
+            };
+            let code = anchor_error.error_code_number;
+            assert_eq!(error as u32 + ERROR_CODE_OFFSET, code, "{error}");
+            assert_eq!(code, ERROR_CODE_OFFSET + index as u32, "{error} is out of order");
+            assert_eq!(vault_error(code).map(|e| e as u32), Some(error as u32));
+        }
+        assert!(vault_error(ERROR_CODE_OFFSET + VAULT_ERRORS.len() as u32).is_none());
+        assert!(vault_error(ERROR_CODE_OFFSET - 1).is_none());
+    }
+}
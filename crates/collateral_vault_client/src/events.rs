@@ -0,0 +1,125 @@
+//! Decoding of program events from transaction logs and event CPIs.
+//!
+//! `emit!` writes each event as a `Program data: <base64>` log line holding
+//! the event discriminator followed by its Borsh encoding. Lines are only
+//! attributed to the vault program while it is the innermost invoked program,
+//! so events logged by other programs in the same transaction are skipped.
+//!
+//! `emit_cpi!` instead invokes the program itself with the same bytes behind
+//! Anchor's event instruction tag, which survives log truncation.
+
+use anchor_lang::{event::EVENT_IX_TAG_LE, prelude::*, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use collateral_vault_interface::{
+    AuthorityInitialized, AuthorityMigrated, CustodyPoolInitialized, CustodyPoolVerified,
+    DeniedExtensionsUpdated, DepositEvent, IntegratorCapUpdated, LockEvent, MintConfigUpdated,
+    OracleConfigUpdated, PortfolioDepositEvent, PortfolioLockEvent, PortfolioMintAdded,
+    PortfolioUnlockEvent, PortfolioVaultClosed, PortfolioVaultInitialized, PortfolioWithdrawEvent,
+    ProgramAuthorized, ProgramDeauthorized, SubAccountAllocated, SubAccountClosed,
+    SubAccountCreated, SubAccountLockEvent, SubAccountMoved, SubAccountReleased,
+    SubAccountTransferEvent, SubAccountUnlockEvent, TransferEvent, UnlockEvent, VaultClosed,
+    VaultInitialized, VaultMigrated, WithdrawEvent, ID,
+};
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+macro_rules! vault_events {
+    ($($event:ident),* $(,)?) => {
+        /// Any event emitted by the vault program
+        pub enum VaultEvent {
+            $($event($event),)*
+        }
+
+        impl VaultEvent {
+            /// Event struct name, e.g. `"DepositEvent"`
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$event(_) => stringify!($event),)*
+                }
+            }
+        }
+
+        /// Decode discriminator-prefixed event data; `None` for unknown events
+        pub fn decode_event(data: &[u8]) -> Option<VaultEvent> {
+            let (discriminator, mut payload) = (data.get(..8)?, &data[8..]);
+            $(
+                if discriminator == $event::DISCRIMINATOR {
+                    return $event::deserialize(&mut payload).ok().map(VaultEvent::$event);
+                }
+            )*
+            None
+        }
+    };
+}
+
+vault_events!(
+    VaultInitialized,
+    DepositEvent,
+    WithdrawEvent,
+    LockEvent,
+    UnlockEvent,
+    TransferEvent,
+    VaultClosed,
+    VaultMigrated,
+    AuthorityInitialized,
+    AuthorityMigrated,
+    ProgramAuthorized,
+    ProgramDeauthorized,
+    CustodyPoolInitialized,
+    CustodyPoolVerified,
+    SubAccountCreated,
+    SubAccountAllocated,
+    SubAccountReleased,
+    SubAccountMoved,
+    SubAccountLockEvent,
+    SubAccountUnlockEvent,
+    SubAccountTransferEvent,
+    SubAccountClosed,
+    PortfolioVaultInitialized,
+    PortfolioVaultClosed,
+    PortfolioMintAdded,
+    PortfolioDepositEvent,
+    PortfolioWithdrawEvent,
+    PortfolioLockEvent,
+    PortfolioUnlockEvent,
+    OracleConfigUpdated,
+    MintConfigUpdated,
+    DeniedExtensionsUpdated,
+    IntegratorCapUpdated,
+);
+
+/// Events the vault program logged, in emission order.
+///
+/// Lines that fail to decode (other programs' data, truncated logs) are skipped.
+pub fn events_from_logs<S: AsRef<str>>(logs: &[S]) -> Vec<VaultEvent> {
+    let program = ID.to_string();
+    let mut invoked: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    
+    for log in logs {
+        let log = log.as_ref();
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+            if invoked.last() == Some(&program.as_str()) {
+                events.extend(STANDARD.decode(data).ok().and_then(|data| decode_event(&data)));
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            // "Program <id> invoke [n]" / "Program <id> success" / "Program <id> failed: ..."
+            let mut words = rest.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(id), Some("invoke")) => invoked.push(id),
+                (Some(id), Some("success" | "failed:")) if invoked.last() == Some(&id) => {
+                    invoked.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    
+    events
+}
+
+/// Decode the instruction data of an `emit_cpi!` self-invocation;
+/// `None` for any other instruction
+pub fn decode_event_cpi(data: &[u8]) -> Option<VaultEvent> {
+    decode_event(data.strip_prefix(EVENT_IX_TAG_LE)?)
+}
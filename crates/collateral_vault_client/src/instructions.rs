@@ -0,0 +1,1166 @@
+//! Instruction builders for every collateral vault entrypoint.
+//!
+//! Builders derive all PDAs and associated token accounts from the owners,
+//! mints and ids they are given. For mints with the TransferHook extension,
+//! append the hook's extra account metas to `Instruction::accounts` of the
+//! token-moving instructions (deposit, withdraw and transfers).
+
+use anchor_lang::{
+    prelude::*, solana_program::instruction::Instruction, system_program, InstructionData,
+};
+use anchor_spl::associated_token;
+use collateral_vault::{accounts, instruction as args};
+use collateral_vault_interface::{
+    pda, BasketLeg, BasketWeight, MintConfigParams, OracleConfigParams, ID,
+};
+
+/// Token account of a vault-owned PDA (vault, zero-copy vault, portfolio or custody pool)
+pub fn vault_token_account(
+    owner_pda: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    associated_token::get_associated_token_address_with_program_id(
+        owner_pda,
+        token_mint,
+        token_program,
+    )
+}
+
+/// Associated token account of a wallet
+pub fn user_token_account(wallet: &Pubkey, token_mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    associated_token::get_associated_token_address_with_program_id(
+        wallet,
+        token_mint,
+        token_program,
+    )
+}
+
+/// Accounts struct of an instruction that emits events, plus the
+/// `event_authority` and `program` accounts `#[event_cpi]` adds under `event-cpi`
+macro_rules! emitting {
+    ($accounts:ident { $($field:ident $(: $value:expr)?),* $(,)? }) => {
+        accounts::$accounts {
+            $($field $(: $value)?,)*
+            #[cfg(feature = "event-cpi")]
+            event_authority: pda::event_authority().0,
+            #[cfg(feature = "event-cpi")]
+            program: ID,
+        }
+    };
+}
+
+fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn readonly(addresses: impl IntoIterator<Item = Pubkey>) -> impl Iterator<Item = AccountMeta> {
+    addresses
+        .into_iter()
+        .map(|address| AccountMeta::new_readonly(address, false))
+}
+
+// ============ AUTHORITY ============
+
+pub fn initialize_authority(admin: &Pubkey, authorized_programs: Vec<Pubkey>) -> Instruction {
+    instruction(
+        emitting!(InitializeAuthority {
+            admin: *admin,
+            vault_authority: pda::vault_authority().0,
+            system_program: system_program::ID,
+        }),
+        args::InitializeAuthority {
+            authorized_programs,
+        },
+    )
+}
+
+fn update_authority(admin: &Pubkey) -> accounts::UpdateAuthority {
+    emitting!(UpdateAuthority {
+        admin: *admin,
+        vault_authority: pda::vault_authority().0,
+    })
+}
+
+pub fn add_authorized_program(admin: &Pubkey, program: Pubkey) -> Instruction {
+    instruction(
+        update_authority(admin),
+        args::AddAuthorizedProgram { program },
+    )
+}
+
+pub fn remove_authorized_program(admin: &Pubkey, program: Pubkey) -> Instruction {
+    instruction(
+        update_authority(admin),
+        args::RemoveAuthorizedProgram { program },
+    )
+}
+
+pub fn set_denied_extensions(admin: &Pubkey, denied_extensions: u64) -> Instruction {
+    instruction(
+        update_authority(admin),
+        args::SetDeniedExtensions { denied_extensions },
+    )
+}
+
+pub fn initialize_mint_config(
+    admin: &Pubkey,
+    token_mint: &Pubkey,
+    params: MintConfigParams,
+) -> Instruction {
+    instruction(
+        emitting!(InitializeMintConfig {
+            admin: *admin,
+            vault_authority: pda::vault_authority().0,
+            token_mint: *token_mint,
+            mint_config: pda::mint_config(token_mint).0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            system_program: system_program::ID,
+        }),
+        args::InitializeMintConfig { params },
+    )
+}
+
+pub fn update_mint_config(
+    admin: &Pubkey,
+    token_mint: &Pubkey,
+    params: MintConfigParams,
+) -> Instruction {
+    instruction(
+        emitting!(UpdateMintConfig {
+            admin: *admin,
+            vault_authority: pda::vault_authority().0,
+            mint_config: pda::mint_config(token_mint).0,
+        }),
+        args::UpdateMintConfig { params },
+    )
+}
+
+pub fn initialize_integrator_stats(
+    admin: &Pubkey,
+    program: Pubkey,
+    max_locked: u64,
+) -> Instruction {
+    instruction(
+        emitting!(InitializeIntegratorStats {
+            admin: *admin,
+            vault_authority: pda::vault_authority().0,
+            integrator_stats: pda::integrator_stats(&program).0,
+            system_program: system_program::ID,
+        }),
+        args::InitializeIntegratorStats {
+            program,
+            max_locked,
+        },
+    )
+}
+
+pub fn set_integrator_cap(admin: &Pubkey, program: &Pubkey, max_locked: u64) -> Instruction {
+    instruction(
+        emitting!(SetIntegratorCap {
+            admin: *admin,
+            vault_authority: pda::vault_authority().0,
+            integrator_stats: pda::integrator_stats(program).0,
+        }),
+        args::SetIntegratorCap { max_locked },
+    )
+}
+
+pub fn initialize_oracle_config(
+    admin: &Pubkey,
+    token_mint: &Pubkey,
+    params: OracleConfigParams,
+) -> Instruction {
+    instruction(
+        emitting!(InitializeOracleConfig {
+            admin: *admin,
+            vault_authority: pda::vault_authority().0,
+            token_mint: *token_mint,
+            oracle_config: pda::oracle_config(token_mint).0,
+            system_program: system_program::ID,
+        }),
+        args::InitializeOracleConfig { params },
+    )
+}
+
+pub fn update_oracle_config(
+    admin: &Pubkey,
+    token_mint: &Pubkey,
+    params: OracleConfigParams,
+) -> Instruction {
+    instruction(
+        emitting!(UpdateOracleConfig {
+            admin: *admin,
+            vault_authority: pda::vault_authority().0,
+            oracle_config: pda::oracle_config(token_mint).0,
+        }),
+        args::UpdateOracleConfig { params },
+    )
+}
+
+// ============ VAULTS ============
+
+pub fn initialize_vault(user: &Pubkey, token_mint: &Pubkey, token_program: &Pubkey) -> Instruction {
+    let vault = pda::vault(user).0;
+    instruction(
+        emitting!(InitializeVault {
+            user: *user,
+            token_mint: *token_mint,
+            vault_authority: pda::vault_authority().0,
+            mint_config: pda::mint_config(token_mint).0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            vault,
+            user_token_account: user_token_account(user, token_mint, token_program),
+            vault_token_account: vault_token_account(&vault, token_mint, token_program),
+            token_program: *token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }),
+        args::InitializeVault {},
+    )
+}
+
+pub fn deposit(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault(user).0;
+    instruction(
+        emitting!(Deposit {
+            user: *user,
+            vault,
+            token_mint: *token_mint,
+            mint_config: pda::mint_config(token_mint).0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            user_token_account: user_token_account(user, token_mint, token_program),
+            vault_token_account: vault_token_account(&vault, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::Deposit { amount },
+    )
+}
+
+pub fn withdraw(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault(user).0;
+    instruction(
+        emitting!(Withdraw {
+            user: *user,
+            vault,
+            token_mint: *token_mint,
+            mint_stats: pda::mint_stats(token_mint).0,
+            user_token_account: user_token_account(user, token_mint, token_program),
+            vault_token_account: vault_token_account(&vault, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::Withdraw { amount },
+    )
+}
+
+/// `pooled` selects the custody pool's token account and passes the pool itself
+pub fn close_vault(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    pooled: bool,
+) -> Instruction {
+    let vault = pda::vault(user).0;
+    let custody_pool = pda::custody_pool(token_mint).0;
+    let token_account_owner = if pooled { custody_pool } else { vault };
+
+    instruction(
+        emitting!(CloseVault {
+            user: *user,
+            vault,
+            vault_token_account: vault_token_account(
+                &token_account_owner,
+                token_mint,
+                token_program,
+            ),
+            mint_stats: pda::mint_stats(token_mint).0,
+            custody_pool: pooled.then_some(custody_pool),
+        }),
+        args::CloseVault {},
+    )
+}
+
+pub fn migrate_vault(payer: &Pubkey, owner: &Pubkey) -> Instruction {
+    instruction(
+        emitting!(MigrateVault {
+            payer: *payer,
+            owner: *owner,
+            vault: pda::vault(owner).0,
+            system_program: system_program::ID,
+        }),
+        args::MigrateVault {},
+    )
+}
+
+pub fn migrate_authority(payer: &Pubkey) -> Instruction {
+    instruction(
+        emitting!(MigrateAuthority {
+            payer: *payer,
+            vault_authority: pda::vault_authority().0,
+            system_program: system_program::ID,
+        }),
+        args::MigrateAuthority {},
+    )
+}
+
+/// `pooled` checks against the custody pool's token account, as in [`close_vault`]
+pub fn assert_invariants(
+    owner: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    pooled: bool,
+) -> Instruction {
+    let vault = pda::vault(owner).0;
+    let token_account_owner = if pooled {
+        pda::custody_pool(token_mint).0
+    } else {
+        vault
+    };
+
+    instruction(
+        accounts::AssertInvariants {
+            vault,
+            vault_token_account: vault_token_account(
+                &token_account_owner,
+                token_mint,
+                token_program,
+            ),
+        },
+        args::AssertInvariants {},
+    )
+}
+
+// ============ INTEGRATOR OPERATIONS ============
+
+pub fn lock_collateral(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    token_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault(owner).0;
+    instruction(
+        emitting!(LockCollateral {
+            signer: *signer,
+            vault,
+            vault_authority: pda::vault_authority().0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            integrator_position: pda::integrator_position(signer, &vault).0,
+            system_program: system_program::ID,
+        }),
+        args::LockCollateral { amount },
+    )
+}
+
+pub fn unlock_collateral(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    token_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault(owner).0;
+    instruction(
+        emitting!(UnlockCollateral {
+            signer: *signer,
+            vault,
+            vault_authority: pda::vault_authority().0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            integrator_position: pda::integrator_position(signer, &vault).0,
+            system_program: system_program::ID,
+        }),
+        args::UnlockCollateral { amount },
+    )
+}
+
+pub fn lock_value(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    token_mint: &Pubkey,
+    oracle: &Pubkey,
+    value: u64,
+) -> Instruction {
+    let vault = pda::vault(owner).0;
+    instruction(
+        emitting!(LockValue {
+            signer: *signer,
+            vault,
+            vault_authority: pda::vault_authority().0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            integrator_position: pda::integrator_position(signer, &vault).0,
+            system_program: system_program::ID,
+            token_mint: *token_mint,
+            oracle_config: pda::oracle_config(token_mint).0,
+            oracle: *oracle,
+        }),
+        args::LockValue { value },
+    )
+}
+
+pub fn transfer_collateral(
+    signer: &Pubkey,
+    from_owner: &Pubkey,
+    to_owner: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let from_vault = pda::vault(from_owner).0;
+    let to_vault = pda::vault(to_owner).0;
+    instruction(
+        emitting!(TransferCollateral {
+            signer: *signer,
+            from_vault,
+            to_vault,
+            vault_authority: pda::vault_authority().0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            system_program: system_program::ID,
+            token_mint: *token_mint,
+            from_vault_token_account: vault_token_account(&from_vault, token_mint, token_program),
+            to_vault_token_account: vault_token_account(&to_vault, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::TransferCollateral { amount },
+    )
+}
+
+pub fn get_collateral_value(owner: &Pubkey, token_mint: &Pubkey, oracle: &Pubkey) -> Instruction {
+    instruction(
+        accounts::GetCollateralValue {
+            vault: pda::vault(owner).0,
+            token_mint: *token_mint,
+            oracle_config: pda::oracle_config(token_mint).0,
+            oracle: *oracle,
+        },
+        args::GetCollateralValue {},
+    )
+}
+
+// ============ POOLED CUSTODY ============
+
+pub fn initialize_custody_pool(
+    admin: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let custody_pool = pda::custody_pool(token_mint).0;
+    instruction(
+        emitting!(InitializeCustodyPool {
+            admin: *admin,
+            vault_authority: pda::vault_authority().0,
+            token_mint: *token_mint,
+            custody_pool,
+            custody_token_account: vault_token_account(&custody_pool, token_mint, token_program),
+            token_program: *token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }),
+        args::InitializeCustodyPool {},
+    )
+}
+
+pub fn initialize_pooled_vault(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    instruction(
+        emitting!(InitializePooledVault {
+            user: *user,
+            token_mint: *token_mint,
+            vault_authority: pda::vault_authority().0,
+            mint_config: pda::mint_config(token_mint).0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            custody_pool: pda::custody_pool(token_mint).0,
+            vault: pda::vault(user).0,
+            token_program: *token_program,
+            system_program: system_program::ID,
+        }),
+        args::InitializePooledVault {},
+    )
+}
+
+pub fn deposit_pooled(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let custody_pool = pda::custody_pool(token_mint).0;
+    instruction(
+        emitting!(DepositPooled {
+            user: *user,
+            vault: pda::vault(user).0,
+            custody_pool,
+            token_mint: *token_mint,
+            mint_config: pda::mint_config(token_mint).0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            user_token_account: user_token_account(user, token_mint, token_program),
+            custody_token_account: vault_token_account(&custody_pool, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::DepositPooled { amount },
+    )
+}
+
+pub fn withdraw_pooled(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let custody_pool = pda::custody_pool(token_mint).0;
+    instruction(
+        emitting!(WithdrawPooled {
+            user: *user,
+            vault: pda::vault(user).0,
+            custody_pool,
+            token_mint: *token_mint,
+            mint_stats: pda::mint_stats(token_mint).0,
+            user_token_account: user_token_account(user, token_mint, token_program),
+            custody_token_account: vault_token_account(&custody_pool, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::WithdrawPooled { amount },
+    )
+}
+
+pub fn transfer_collateral_internal(
+    signer: &Pubkey,
+    from_owner: &Pubkey,
+    to_owner: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        emitting!(TransferCollateralInternal {
+            signer: *signer,
+            from_vault: pda::vault(from_owner).0,
+            to_vault: pda::vault(to_owner).0,
+            vault_authority: pda::vault_authority().0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            system_program: system_program::ID,
+        }),
+        args::TransferCollateralInternal { amount },
+    )
+}
+
+/// `vault_owners` must cover every pooled vault of the mint
+pub fn verify_custody_pool(
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    vault_owners: &[Pubkey],
+) -> Instruction {
+    let custody_pool = pda::custody_pool(token_mint).0;
+    let mut ix = instruction(
+        emitting!(VerifyCustodyPool {
+            custody_pool,
+            custody_token_account: vault_token_account(&custody_pool, token_mint, token_program),
+        }),
+        args::VerifyCustodyPool {},
+    );
+    ix.accounts.extend(readonly(
+        vault_owners.iter().map(|owner| pda::vault(owner).0),
+    ));
+    ix
+}
+
+// ============ SUB-ACCOUNTS ============
+
+pub fn create_sub_account(user: &Pubkey, id: u8) -> Instruction {
+    let vault = pda::vault(user).0;
+    instruction(
+        emitting!(CreateSubAccount {
+            user: *user,
+            vault,
+            sub_account: pda::sub_account(&vault, id).0,
+            system_program: system_program::ID,
+        }),
+        args::CreateSubAccount { id },
+    )
+}
+
+fn manage_sub_account(user: &Pubkey, id: u8) -> accounts::ManageSubAccount {
+    let vault = pda::vault(user).0;
+    emitting!(ManageSubAccount {
+        user: *user,
+        vault,
+        sub_account: pda::sub_account(&vault, id).0,
+    })
+}
+
+pub fn allocate_to_sub_account(user: &Pubkey, id: u8, amount: u64) -> Instruction {
+    instruction(
+        manage_sub_account(user, id),
+        args::AllocateToSubAccount { amount },
+    )
+}
+
+pub fn release_from_sub_account(user: &Pubkey, id: u8, amount: u64) -> Instruction {
+    instruction(
+        manage_sub_account(user, id),
+        args::ReleaseFromSubAccount { amount },
+    )
+}
+
+pub fn move_between_sub_accounts(
+    user: &Pubkey,
+    from_id: u8,
+    to_id: u8,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault(user).0;
+    instruction(
+        emitting!(MoveBetweenSubAccounts {
+            user: *user,
+            vault,
+            from_sub_account: pda::sub_account(&vault, from_id).0,
+            to_sub_account: pda::sub_account(&vault, to_id).0,
+        }),
+        args::MoveBetweenSubAccounts { amount },
+    )
+}
+
+pub fn lock_sub_account_collateral(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    id: u8,
+    token_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault(owner).0;
+    let sub_account = pda::sub_account(&vault, id).0;
+    instruction(
+        emitting!(LockSubAccountCollateral {
+            signer: *signer,
+            vault,
+            sub_account,
+            vault_authority: pda::vault_authority().0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            integrator_position: pda::integrator_position(signer, &sub_account).0,
+            system_program: system_program::ID,
+        }),
+        args::LockSubAccountCollateral { amount },
+    )
+}
+
+pub fn unlock_sub_account_collateral(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    id: u8,
+    token_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault(owner).0;
+    let sub_account = pda::sub_account(&vault, id).0;
+    instruction(
+        emitting!(UnlockSubAccountCollateral {
+            signer: *signer,
+            vault,
+            sub_account,
+            vault_authority: pda::vault_authority().0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            integrator_position: pda::integrator_position(signer, &sub_account).0,
+            system_program: system_program::ID,
+        }),
+        args::UnlockSubAccountCollateral { amount },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_sub_account_collateral(
+    signer: &Pubkey,
+    from_owner: &Pubkey,
+    from_id: u8,
+    to_owner: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let from_vault = pda::vault(from_owner).0;
+    let to_vault = pda::vault(to_owner).0;
+    instruction(
+        emitting!(TransferSubAccountCollateral {
+            signer: *signer,
+            from_vault,
+            from_sub_account: pda::sub_account(&from_vault, from_id).0,
+            to_vault,
+            vault_authority: pda::vault_authority().0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            system_program: system_program::ID,
+            token_mint: *token_mint,
+            from_vault_token_account: vault_token_account(&from_vault, token_mint, token_program),
+            to_vault_token_account: vault_token_account(&to_vault, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::TransferSubAccountCollateral { amount },
+    )
+}
+
+pub fn close_sub_account(user: &Pubkey, id: u8) -> Instruction {
+    let vault = pda::vault(user).0;
+    instruction(
+        emitting!(CloseSubAccount {
+            user: *user,
+            vault,
+            sub_account: pda::sub_account(&vault, id).0,
+        }),
+        args::CloseSubAccount {},
+    )
+}
+
+// ============ PORTFOLIO ============
+
+pub fn initialize_portfolio_vault(user: &Pubkey) -> Instruction {
+    instruction(
+        emitting!(InitializePortfolioVault {
+            user: *user,
+            portfolio_vault: pda::portfolio(user).0,
+            system_program: system_program::ID,
+        }),
+        args::InitializePortfolioVault {},
+    )
+}
+
+pub fn add_portfolio_mint(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let portfolio_vault = pda::portfolio(user).0;
+    instruction(
+        emitting!(AddPortfolioMint {
+            user: *user,
+            portfolio_vault,
+            token_mint: *token_mint,
+            vault_authority: pda::vault_authority().0,
+            mint_config: pda::mint_config(token_mint).0,
+            vault_token_account: vault_token_account(&portfolio_vault, token_mint, token_program),
+            token_program: *token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }),
+        args::AddPortfolioMint {},
+    )
+}
+
+pub fn deposit_portfolio(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let portfolio_vault = pda::portfolio(user).0;
+    instruction(
+        emitting!(DepositPortfolio {
+            user: *user,
+            portfolio_vault,
+            token_mint: *token_mint,
+            mint_config: pda::mint_config(token_mint).0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            user_token_account: user_token_account(user, token_mint, token_program),
+            vault_token_account: vault_token_account(&portfolio_vault, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::DepositPortfolio { amount },
+    )
+}
+
+pub fn withdraw_portfolio(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let portfolio_vault = pda::portfolio(user).0;
+    instruction(
+        emitting!(WithdrawPortfolio {
+            user: *user,
+            portfolio_vault,
+            token_mint: *token_mint,
+            mint_stats: pda::mint_stats(token_mint).0,
+            user_token_account: user_token_account(user, token_mint, token_program),
+            vault_token_account: vault_token_account(&portfolio_vault, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::WithdrawPortfolio { amount },
+    )
+}
+
+fn lock_portfolio(signer: &Pubkey, owner: &Pubkey) -> accounts::LockPortfolioCollateral {
+    let portfolio_vault = pda::portfolio(owner).0;
+    emitting!(LockPortfolioCollateral {
+        signer: *signer,
+        portfolio_vault,
+        vault_authority: pda::vault_authority().0,
+        integrator_stats: pda::integrator_stats(signer).0,
+        integrator_position: pda::integrator_position(signer, &portfolio_vault).0,
+        system_program: system_program::ID,
+    })
+}
+
+fn unlock_portfolio(signer: &Pubkey, owner: &Pubkey) -> accounts::UnlockPortfolioCollateral {
+    let portfolio_vault = pda::portfolio(owner).0;
+    emitting!(UnlockPortfolioCollateral {
+        signer: *signer,
+        portfolio_vault,
+        vault_authority: pda::vault_authority().0,
+        integrator_stats: pda::integrator_stats(signer).0,
+        integrator_position: pda::integrator_position(signer, &portfolio_vault).0,
+        system_program: system_program::ID,
+    })
+}
+
+/// `MintStats` of each leg's mint, passed as remaining accounts in leg order
+fn leg_mint_stats<'a>(mints: impl IntoIterator<Item = &'a Pubkey>) -> Vec<AccountMeta> {
+    mints
+        .into_iter()
+        .map(|mint| AccountMeta::new(pda::mint_stats(mint).0, false))
+        .collect()
+}
+
+pub fn lock_portfolio_collateral(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    token_mint: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut ix = instruction(
+        lock_portfolio(signer, owner),
+        args::LockPortfolioCollateral { token_mint, amount },
+    );
+    ix.accounts.extend(leg_mint_stats([&token_mint]));
+    ix
+}
+
+pub fn unlock_portfolio_collateral(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    token_mint: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut ix = instruction(
+        unlock_portfolio(signer, owner),
+        args::UnlockPortfolioCollateral { token_mint, amount },
+    );
+    ix.accounts.extend(leg_mint_stats([&token_mint]));
+    ix
+}
+
+pub fn lock_portfolio_basket(signer: &Pubkey, owner: &Pubkey, legs: Vec<BasketLeg>) -> Instruction {
+    let mint_stats = leg_mint_stats(legs.iter().map(|leg| &leg.mint));
+    let mut ix = instruction(
+        lock_portfolio(signer, owner),
+        args::LockPortfolioBasket { legs },
+    );
+    ix.accounts.extend(mint_stats);
+    ix
+}
+
+pub fn unlock_portfolio_basket(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    legs: Vec<BasketLeg>,
+) -> Instruction {
+    let mint_stats = leg_mint_stats(legs.iter().map(|leg| &leg.mint));
+    let mut ix = instruction(
+        unlock_portfolio(signer, owner),
+        args::UnlockPortfolioBasket { legs },
+    );
+    ix.accounts.extend(mint_stats);
+    ix
+}
+
+/// Value-weighted basket lock; each leg is a weight and the mint's price feed
+pub fn lock_portfolio_value(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    value: u64,
+    legs: &[(BasketWeight, Pubkey)],
+) -> Instruction {
+    let mut ix = instruction(
+        lock_portfolio(signer, owner),
+        args::LockPortfolioValue {
+            value,
+            legs: legs.iter().map(|(leg, _)| leg.clone()).collect(),
+        },
+    );
+    for (leg, oracle) in legs {
+        ix.accounts
+            .extend(readonly([leg.mint, pda::oracle_config(&leg.mint).0, *oracle]));
+        ix.accounts.extend(leg_mint_stats([&leg.mint]));
+    }
+    ix
+}
+
+pub fn close_portfolio_vault(user: &Pubkey) -> Instruction {
+    instruction(
+        emitting!(ClosePortfolioVault {
+            user: *user,
+            portfolio_vault: pda::portfolio(user).0,
+        }),
+        args::ClosePortfolioVault {},
+    )
+}
+
+// ============ MOCK ORACLE ============
+
+#[cfg(feature = "mock-oracle")]
+pub fn initialize_mock_price_feed(authority: &Pubkey, price_feed: &Pubkey) -> Instruction {
+    instruction(
+        accounts::InitializeMockPriceFeed {
+            authority: *authority,
+            price_feed: *price_feed,
+            system_program: system_program::ID,
+        },
+        args::InitializeMockPriceFeed {},
+    )
+}
+
+#[cfg(feature = "mock-oracle")]
+pub fn set_mock_price(
+    authority: &Pubkey,
+    price_feed: &Pubkey,
+    price: i64,
+    conf: u64,
+    expo: i32,
+    publish_time: Option<i64>,
+) -> Instruction {
+    instruction(
+        accounts::SetMockPrice {
+            authority: *authority,
+            price_feed: *price_feed,
+        },
+        args::SetMockPrice {
+            price,
+            conf,
+            expo,
+            publish_time,
+        },
+    )
+}
+
+// ============ ZERO-COPY (EXPERIMENTAL) ============
+
+#[cfg(feature = "zero-copy")]
+pub fn initialize_authority_zc(admin: &Pubkey, authorized_programs: Vec<Pubkey>) -> Instruction {
+    instruction(
+        emitting!(InitializeAuthorityZc {
+            admin: *admin,
+            vault_authority: pda::vault_authority_zc().0,
+            system_program: system_program::ID,
+        }),
+        args::InitializeAuthorityZc {
+            authorized_programs,
+        },
+    )
+}
+
+#[cfg(feature = "zero-copy")]
+fn update_authority_zc(admin: &Pubkey) -> accounts::UpdateAuthorityZc {
+    emitting!(UpdateAuthorityZc {
+        admin: *admin,
+        vault_authority: pda::vault_authority_zc().0,
+    })
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn add_authorized_program_zc(admin: &Pubkey, program: Pubkey) -> Instruction {
+    instruction(
+        update_authority_zc(admin),
+        args::AddAuthorizedProgramZc { program },
+    )
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn remove_authorized_program_zc(admin: &Pubkey, program: Pubkey) -> Instruction {
+    instruction(
+        update_authority_zc(admin),
+        args::RemoveAuthorizedProgramZc { program },
+    )
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn initialize_vault_zc(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let vault = pda::vault_zc(user).0;
+    instruction(
+        emitting!(InitializeVaultZc {
+            user: *user,
+            token_mint: *token_mint,
+            vault_authority: pda::vault_authority_zc().0,
+            mint_config: pda::mint_config(token_mint).0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            vault,
+            vault_token_account: vault_token_account(&vault, token_mint, token_program),
+            token_program: *token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }),
+        args::InitializeVaultZc {},
+    )
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn deposit_zc(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault_zc(user).0;
+    instruction(
+        emitting!(DepositZc {
+            user: *user,
+            vault,
+            token_mint: *token_mint,
+            mint_config: pda::mint_config(token_mint).0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            user_token_account: user_token_account(user, token_mint, token_program),
+            vault_token_account: vault_token_account(&vault, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::DepositZc { amount },
+    )
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn lock_collateral_zc(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    token_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault_zc(owner).0;
+    instruction(
+        emitting!(LockCollateralZc {
+            signer: *signer,
+            vault,
+            vault_authority: pda::vault_authority_zc().0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            integrator_position: pda::integrator_position(signer, &vault).0,
+            system_program: system_program::ID,
+        }),
+        args::LockCollateralZc { amount },
+    )
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn unlock_collateral_zc(
+    signer: &Pubkey,
+    owner: &Pubkey,
+    token_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault_zc(owner).0;
+    instruction(
+        emitting!(UnlockCollateralZc {
+            signer: *signer,
+            vault,
+            vault_authority: pda::vault_authority_zc().0,
+            mint_stats: pda::mint_stats(token_mint).0,
+            integrator_stats: pda::integrator_stats(signer).0,
+            integrator_position: pda::integrator_position(signer, &vault).0,
+            system_program: system_program::ID,
+        }),
+        args::UnlockCollateralZc { amount },
+    )
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn withdraw_zc(
+    user: &Pubkey,
+    token_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let vault = pda::vault_zc(user).0;
+    instruction(
+        emitting!(WithdrawZc {
+            user: *user,
+            vault,
+            token_mint: *token_mint,
+            mint_stats: pda::mint_stats(token_mint).0,
+            user_token_account: user_token_account(user, token_mint, token_program),
+            vault_token_account: vault_token_account(&vault, token_mint, token_program),
+            token_program: *token_program,
+        }),
+        args::WithdrawZc { amount },
+    )
+}
+
+#[cfg(feature = "zero-copy")]
+pub fn close_vault_zc(user: &Pubkey, token_mint: &Pubkey) -> Instruction {
+    instruction(
+        emitting!(CloseVaultZc {
+            user: *user,
+            vault: pda::vault_zc(user).0,
+            mint_stats: pda::mint_stats(token_mint).0,
+        }),
+        args::CloseVaultZc {},
+    )
+}
+
+// ============ VIEWS ============
+
+pub fn get_vault_balances(owner: &Pubkey) -> Instruction {
+    instruction(
+        accounts::GetVaultBalances {
+            vault: pda::vault(owner).0,
+        },
+        args::GetVaultBalances {},
+    )
+}
+
+pub fn get_lock_positions(owner: &Pubkey, sub_account_ids: &[u8]) -> Instruction {
+    let vault = pda::vault(owner).0;
+    let mut ix = instruction(
+        accounts::GetVaultBalances { vault },
+        args::GetLockPositions {},
+    );
+    ix.accounts.extend(readonly(
+        sub_account_ids
+            .iter()
+            .map(|id| pda::sub_account(&vault, *id).0),
+    ));
+    ix
+}
+
+pub fn get_authority_config() -> Instruction {
+    instruction(
+        accounts::GetAuthorityConfig {
+            vault_authority: pda::vault_authority().0,
+        },
+        args::GetAuthorityConfig {},
+    )
+}
+
+pub fn get_mint_stats(token_mint: &Pubkey) -> Instruction {
+    instruction(
+        accounts::GetMintStats {
+            mint_stats: pda::mint_stats(token_mint).0,
+        },
+        args::GetMintStats {},
+    )
+}
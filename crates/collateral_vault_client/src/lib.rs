@@ -0,0 +1,26 @@
+//! Off-chain client for the collateral vault program.
+//!
+//! - [`instructions`]: builders for every entrypoint, deriving all PDAs and ATAs
+//! - [`accounts`]: fetching and decoding through an [`AccountFetcher`]
+//! - [`error`]: client errors and `VaultError` code mapping
+//! - [`events`]: decoding of program events from logs and event CPIs
+//!
+//! Enable `rpc` to fetch from a validator with `RpcClient`, or `litesvm` to
+//! fetch from an in-process `LiteSVM` bank.
+
+pub mod accounts;
+pub mod error;
+pub mod events;
+pub mod instructions;
+
+pub use accounts::{AccountFetcher, RawAccount};
+pub use collateral_vault_interface::{
+    pda, BasketLeg, BasketWeight, CollateralVault, IntegratorPosition, IntegratorStats,
+    MintConfig, MintConfigParams, MintStats, OracleConfig, OracleConfigParams, PortfolioVault,
+    SubAccount, VaultAuthority, VaultError, ID,
+};
+pub use error::{transaction_vault_error, vault_error, ClientError, Result};
+pub use events::{decode_event, decode_event_cpi, events_from_logs, VaultEvent};
+
+#[cfg(feature = "rpc")]
+pub use error::rpc_vault_error;
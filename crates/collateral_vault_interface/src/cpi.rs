@@ -0,0 +1,1243 @@
+//! Typed CPI wrappers for every instruction of the vault program.
+//!
+//! Each wrapper takes the accounts of one instruction (plus the vault program
+//! itself), checks the program id and every PDA it can derive without reading
+//! account data, and signs with `signer_seeds` — the seeds of the calling
+//! program's PDA: the integrator PDA registered in
+//! `VaultAuthority::authorized_programs` for locks and transfers, or a PDA
+//! acting as vault owner or admin elsewhere (pass `&[]` when the signer is a
+//! transaction signer). The program still enforces all constraints; the checks
+//! here only turn a wrong account into an early, readable error.
+//!
+//! Wrappers of instructions that move tokens take `transfer_hook_accounts`,
+//! forwarded as remaining accounts for mints with the TransferHook extension;
+//! pass an empty `Vec` otherwise.
+//!
+//! Lock, unlock and transfer wrappers take the signer's `integrator_stats` and
+//! `system_program`, and locks and unlocks also the signer's
+//! `integrator_position` in the vault, sub-account or portfolio. The program
+//! creates both on first use, paid by the signer, which must be writable and
+//! hold enough lamports for their rent.
+//!
+//! With the `event-cpi` feature (matching a program built with it) each wrapper
+//! also takes the program's `event_authority`; `vault_program` doubles as the
+//! `program` account the event self-CPI needs.
+
+use anchor_lang::prelude::*;
+use collateral_vault::cpi::accounts;
+
+use crate::{
+    pda, AuthorityConfig, BasketLeg, BasketWeight, CollateralValue, LockPositions,
+    MintConfigParams, MintStatsView, OracleConfigParams, VaultBalances, ID,
+};
+
+fn check_program(vault_program: &AccountInfo) -> Result<()> {
+    require_keys_eq!(vault_program.key(), ID, ErrorCode::InvalidProgramId);
+    Ok(())
+}
+
+fn check_address(account: &AccountInfo, expected: Pubkey) -> Result<()> {
+    require_keys_eq!(account.key(), expected, ErrorCode::ConstraintSeeds);
+    Ok(())
+}
+
+/// Declares the accounts of one or more instructions and their wrappers.
+///
+/// Each field is an `AccountInfo` (`: Option` for an optional account), checked
+/// against the PDA after `=` when there is one; every field's key is in scope
+/// there. `vault_program` comes first and, for `#[event_cpi]` instructions,
+/// `event_authority` last. `=> Target, ...` names the `cpi::accounts` structs of
+/// the program the accounts convert into. Each `pub fn` checks the accounts and
+/// calls the instruction of the same name with its arguments, `with` naming a
+/// `Vec` of remaining accounts taken before `signer_seeds`.
+macro_rules! vault_cpi {
+    (@type) => { AccountInfo<'info> };
+    (@type Option) => { Option<AccountInfo<'info>> };
+    (
+        @struct [$($event:ident)?] [$($cfg:tt)*]
+        $(#[doc = $doc:literal])*
+        $name:ident {
+            $(
+                $(#[doc = $field_doc:literal])*
+                $field:ident $(: $kind:ident)? $(= $address:expr)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[doc = $doc])*
+        $($cfg)*
+        pub struct $name<'info> {
+            pub vault_program: AccountInfo<'info>,
+            $(
+                $(#[doc = $field_doc])*
+                pub $field: vault_cpi!(@type $($kind)?),
+            )*
+            $(
+                /// `pda::event_authority`, with `event-cpi`
+                #[cfg(feature = "event-cpi")]
+                pub $event: AccountInfo<'info>,
+            )?
+        }
+
+        $($cfg)*
+        impl<'info> $name<'info> {
+            /// Checks the program id and every PDA derivable from the account keys
+            fn check(&self) -> Result<()> {
+                #[allow(unused_variables)]
+                let Self { $($field,)* .. } = self;
+                check_program(&self.vault_program)?;
+                $($(check_address($field, $address.0)?;)?)*
+                $(
+                    #[cfg(feature = "event-cpi")]
+                    check_address(&self.$event, pda::event_authority().0)?;
+                )?
+                Ok(())
+            }
+
+            /// Context for the instruction taking `T`, signed with `signer_seeds`
+            fn context<'a, 'b, 'c, T>(
+                self,
+                signer_seeds: &'a [&'b [&'c [u8]]],
+            ) -> CpiContext<'a, 'b, 'c, 'info, T>
+            where
+                T: From<Self> + ToAccountMetas + ToAccountInfos<'info>,
+            {
+                CpiContext::new_with_signer(self.vault_program.clone(), T::from(self), signer_seeds)
+            }
+        }
+    };
+    (@into $event:tt $cfg:tt $name:ident [] $fields:tt) => {};
+    (
+        @into $event:tt $cfg:tt $name:ident [$target:ident $($rest:ident)*] $fields:tt
+    ) => {
+        vault_cpi!(@from $event $cfg $name $target $fields);
+        vault_cpi!(@into $event $cfg $name [$($rest)*] $fields);
+    };
+    (
+        @from [$($event:ident)?] [$($cfg:tt)*] $name:ident $target:ident {
+            $(
+                $(#[doc = $field_doc:literal])*
+                $field:ident $(: $kind:ident)? $(= $address:expr)?
+            ),* $(,)?
+        }
+    ) => {
+        $($cfg)*
+        impl<'info> From<$name<'info>> for accounts::$target<'info> {
+            fn from(accounts: $name<'info>) -> Self {
+                Self {
+                    $($field: accounts.$field,)*
+                    $(
+                        #[cfg(feature = "event-cpi")]
+                        $event: accounts.$event,
+                        #[cfg(feature = "event-cpi")]
+                        program: accounts.vault_program,
+                    )?
+                }
+            }
+        }
+    };
+    (
+        @fn [$($cfg:tt)*] $name:ident
+        $(#[doc = $doc:literal])*
+        $fn:ident($($arg:ident: $arg_ty:ty),*)
+    ) => {
+        $(#[doc = $doc])*
+        $($cfg)*
+        pub fn $fn(accounts: $name<'_>, $($arg: $arg_ty,)* signer_seeds: &[&[&[u8]]]) -> Result<()> {
+            accounts.check()?;
+            collateral_vault::cpi::$fn(accounts.context(signer_seeds), $($arg),*)
+        }
+    };
+    (
+        @fn [$($cfg:tt)*] $name:ident
+        $(#[doc = $doc:literal])*
+        $fn:ident($($arg:ident: $arg_ty:ty),*) $remaining:ident
+    ) => {
+        $(#[doc = $doc])*
+        $($cfg)*
+        pub fn $fn<'info>(
+            accounts: $name<'info>,
+            $($arg: $arg_ty,)*
+            $remaining: Vec<AccountInfo<'info>>,
+            signer_seeds: &[&[&[u8]]],
+        ) -> Result<()> {
+            accounts.check()?;
+            collateral_vault::cpi::$fn(
+                accounts.context(signer_seeds).with_remaining_accounts($remaining),
+                $($arg),*
+            )
+        }
+    };
+    (
+        @build $event:tt $cfg:tt
+        $(#[doc = $doc:literal])*
+        pub struct $name:ident => $($target:ident),+ { $($fields:tt)* }
+        $(
+            $(#[doc = $fn_doc:literal])*
+            pub fn $fn:ident($($arg:ident: $arg_ty:ty),* $(,)?) $(with $remaining:ident)?;
+        )*
+    ) => {
+        vault_cpi!(@struct $event $cfg $(#[doc = $doc])* $name { $($fields)* });
+        vault_cpi!(@into $event $cfg $name [$($target)+] { $($fields)* });
+        $(vault_cpi!(@fn $cfg $name $(#[doc = $fn_doc])* $fn($($arg: $arg_ty),*) $($remaining)?);)*
+    };
+    (
+        $(#[doc = $doc:literal])*
+        $(#[cfg($cfg:meta)])?
+        #[event_cpi]
+        pub struct $($rest:tt)*
+    ) => {
+        vault_cpi!(@build [event_authority] [$(#[cfg($cfg)])?] $(#[doc = $doc])* pub struct $($rest)*);
+    };
+    (
+        $(#[doc = $doc:literal])*
+        $(#[cfg($cfg:meta)])?
+        pub struct $($rest:tt)*
+    ) => {
+        vault_cpi!(@build [] [$(#[cfg($cfg)])?] $(#[doc = $doc])* pub struct $($rest)*);
+    };
+}
+
+// ============ VAULTS ============
+
+vault_cpi! {
+    /// Accounts of `initialize_vault`
+    #[event_cpi]
+    pub struct InitializeVault => InitializeVault {
+        user,
+        token_mint,
+        vault_authority = pda::vault_authority(),
+        mint_config = pda::mint_config(token_mint.key),
+        mint_stats = pda::mint_stats(token_mint.key),
+        vault = pda::vault(user.key),
+        user_token_account,
+        vault_token_account,
+        token_program,
+        associated_token_program,
+        system_program,
+    }
+
+    pub fn initialize_vault();
+}
+
+vault_cpi! {
+    /// Accounts of `deposit`
+    #[event_cpi]
+    pub struct Deposit => Deposit {
+        user,
+        vault = pda::vault(user.key),
+        token_mint,
+        mint_config = pda::mint_config(token_mint.key),
+        mint_stats = pda::mint_stats(token_mint.key),
+        user_token_account,
+        vault_token_account,
+        token_program,
+    }
+
+    pub fn deposit(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of `withdraw`
+    #[event_cpi]
+    pub struct Withdraw => Withdraw {
+        user,
+        vault = pda::vault(user.key),
+        token_mint,
+        mint_stats = pda::mint_stats(token_mint.key),
+        user_token_account,
+        vault_token_account,
+        token_program,
+    }
+
+    pub fn withdraw(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of `close_vault`
+    #[event_cpi]
+    pub struct CloseVault => CloseVault {
+        user,
+        vault = pda::vault(user.key),
+        vault_token_account,
+        mint_stats,
+        /// Required when closing a pooled vault
+        custody_pool: Option,
+    }
+
+    pub fn close_vault();
+}
+
+vault_cpi! {
+    /// Accounts of `migrate_vault`
+    #[event_cpi]
+    pub struct MigrateVault => MigrateVault {
+        payer,
+        owner,
+        vault = pda::vault(owner.key),
+        system_program,
+    }
+
+    pub fn migrate_vault();
+}
+
+vault_cpi! {
+    /// Accounts of `migrate_authority`
+    #[event_cpi]
+    pub struct MigrateAuthority => MigrateAuthority {
+        payer,
+        vault_authority = pda::vault_authority(),
+        system_program,
+    }
+
+    pub fn migrate_authority();
+}
+
+// ============ LOCKING ============
+
+vault_cpi! {
+    /// Accounts of `lock_collateral` / `unlock_collateral`
+    #[event_cpi]
+    pub struct LockCollateral => LockCollateral, UnlockCollateral {
+        signer,
+        vault,
+        vault_authority = pda::vault_authority(),
+        mint_stats,
+        integrator_stats = pda::integrator_stats(signer.key),
+        /// `pda::integrator_position` of `signer` in `vault`
+        integrator_position = pda::integrator_position(signer.key, vault.key),
+        /// Creates `integrator_stats` and `integrator_position` on first use, paid by `signer`
+        system_program,
+    }
+
+    pub fn lock_collateral(amount: u64);
+    pub fn unlock_collateral(amount: u64);
+}
+
+vault_cpi! {
+    /// Accounts of `lock_value`
+    #[event_cpi]
+    pub struct LockValue => LockValue {
+        signer,
+        vault,
+        vault_authority = pda::vault_authority(),
+        mint_stats = pda::mint_stats(token_mint.key),
+        integrator_stats = pda::integrator_stats(signer.key),
+        /// `pda::integrator_position` of `signer` in `vault`
+        integrator_position = pda::integrator_position(signer.key, vault.key),
+        /// Creates `integrator_stats` and `integrator_position` on first use, paid by `signer`
+        system_program,
+        token_mint,
+        oracle_config = pda::oracle_config(token_mint.key),
+        oracle,
+    }
+
+    pub fn lock_value(value: u64);
+}
+
+// ============ SETTLEMENT ============
+
+vault_cpi! {
+    /// Accounts of `transfer_collateral`
+    #[event_cpi]
+    pub struct TransferCollateral => TransferCollateral {
+        signer,
+        from_vault,
+        to_vault,
+        vault_authority = pda::vault_authority(),
+        integrator_stats = pda::integrator_stats(signer.key),
+        /// Creates `integrator_stats` on first use, paid by `signer`
+        system_program,
+        token_mint,
+        from_vault_token_account,
+        to_vault_token_account,
+        token_program,
+    }
+
+    /// `transfer_hook_accounts` are forwarded as remaining accounts for mints
+    /// with the TransferHook extension; pass an empty `Vec` otherwise.
+    pub fn transfer_collateral(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of `transfer_collateral_internal` (pooled vaults, ledger only)
+    #[event_cpi]
+    pub struct TransferCollateralInternal => TransferCollateralInternal {
+        signer,
+        from_vault,
+        to_vault,
+        vault_authority = pda::vault_authority(),
+        integrator_stats = pda::integrator_stats(signer.key),
+        /// Creates `integrator_stats` on first use, paid by `signer`
+        system_program,
+    }
+
+    pub fn transfer_collateral_internal(amount: u64);
+}
+
+// ============ POOLED CUSTODY ============
+
+vault_cpi! {
+    /// Accounts of `initialize_pooled_vault`
+    #[event_cpi]
+    pub struct InitializePooledVault => InitializePooledVault {
+        user,
+        token_mint,
+        vault_authority = pda::vault_authority(),
+        mint_config = pda::mint_config(token_mint.key),
+        mint_stats = pda::mint_stats(token_mint.key),
+        custody_pool = pda::custody_pool(token_mint.key),
+        vault = pda::vault(user.key),
+        token_program,
+        system_program,
+    }
+
+    pub fn initialize_pooled_vault();
+}
+
+vault_cpi! {
+    /// Accounts of `deposit_pooled`
+    #[event_cpi]
+    pub struct DepositPooled => DepositPooled {
+        user,
+        vault = pda::vault(user.key),
+        custody_pool,
+        token_mint,
+        mint_config = pda::mint_config(token_mint.key),
+        mint_stats = pda::mint_stats(token_mint.key),
+        user_token_account,
+        custody_token_account,
+        token_program,
+    }
+
+    pub fn deposit_pooled(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of `withdraw_pooled`
+    #[event_cpi]
+    pub struct WithdrawPooled => WithdrawPooled {
+        user,
+        vault = pda::vault(user.key),
+        custody_pool,
+        token_mint,
+        mint_stats = pda::mint_stats(token_mint.key),
+        user_token_account,
+        custody_token_account,
+        token_program,
+    }
+
+    pub fn withdraw_pooled(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of `verify_custody_pool`
+    #[event_cpi]
+    pub struct VerifyCustodyPool => VerifyCustodyPool {
+        custody_pool,
+        custody_token_account,
+    }
+}
+
+/// `vaults` must list every pooled vault of the mint
+pub fn verify_custody_pool<'info>(
+    accounts: VerifyCustodyPool<'info>,
+    vaults: Vec<AccountInfo<'info>>,
+) -> Result<()> {
+    accounts.check()?;
+    collateral_vault::cpi::verify_custody_pool(accounts.context(&[]).with_remaining_accounts(vaults))
+}
+
+// ============ SUB-ACCOUNTS ============
+
+vault_cpi! {
+    /// Accounts of `create_sub_account`
+    #[event_cpi]
+    pub struct CreateSubAccount => CreateSubAccount {
+        user,
+        vault = pda::vault(user.key),
+        sub_account,
+        system_program,
+    }
+}
+
+pub fn create_sub_account(
+    accounts: CreateSubAccount<'_>,
+    id: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    accounts.check()?;
+    check_address(&accounts.sub_account, pda::sub_account(accounts.vault.key, id).0)?;
+    collateral_vault::cpi::create_sub_account(accounts.context(signer_seeds), id)
+}
+
+vault_cpi! {
+    /// Accounts of `allocate_to_sub_account` / `release_from_sub_account`
+    #[event_cpi]
+    pub struct ManageSubAccount => ManageSubAccount {
+        user,
+        vault = pda::vault(user.key),
+        sub_account,
+    }
+
+    pub fn allocate_to_sub_account(amount: u64);
+    pub fn release_from_sub_account(amount: u64);
+}
+
+vault_cpi! {
+    /// Accounts of `move_between_sub_accounts`
+    #[event_cpi]
+    pub struct MoveBetweenSubAccounts => MoveBetweenSubAccounts {
+        user,
+        vault = pda::vault(user.key),
+        from_sub_account,
+        to_sub_account,
+    }
+
+    pub fn move_between_sub_accounts(amount: u64);
+}
+
+vault_cpi! {
+    /// Accounts of `lock_sub_account_collateral` / `unlock_sub_account_collateral`
+    #[event_cpi]
+    pub struct LockSubAccountCollateral => LockSubAccountCollateral, UnlockSubAccountCollateral {
+        signer,
+        /// Parent vault of `sub_account`, whose event sequence advances
+        vault,
+        sub_account,
+        vault_authority = pda::vault_authority(),
+        /// `pda::mint_stats` of the vault's mint
+        mint_stats,
+        integrator_stats = pda::integrator_stats(signer.key),
+        /// `pda::integrator_position` of `signer` in `sub_account`
+        integrator_position = pda::integrator_position(signer.key, sub_account.key),
+        /// Creates `integrator_stats` and `integrator_position` on first use, paid by `signer`
+        system_program,
+    }
+
+    pub fn lock_sub_account_collateral(amount: u64);
+    pub fn unlock_sub_account_collateral(amount: u64);
+}
+
+vault_cpi! {
+    /// Accounts of `transfer_sub_account_collateral`
+    #[event_cpi]
+    pub struct TransferSubAccountCollateral => TransferSubAccountCollateral {
+        signer,
+        from_vault,
+        from_sub_account,
+        to_vault,
+        vault_authority = pda::vault_authority(),
+        integrator_stats = pda::integrator_stats(signer.key),
+        /// Creates `integrator_stats` on first use, paid by `signer`
+        system_program,
+        token_mint,
+        from_vault_token_account,
+        to_vault_token_account,
+        token_program,
+    }
+
+    pub fn transfer_sub_account_collateral(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of `close_sub_account`
+    #[event_cpi]
+    pub struct CloseSubAccount => CloseSubAccount {
+        user,
+        vault = pda::vault(user.key),
+        sub_account,
+    }
+
+    pub fn close_sub_account();
+}
+
+// ============ PORTFOLIO ============
+
+vault_cpi! {
+    /// Accounts of `initialize_portfolio_vault`
+    #[event_cpi]
+    pub struct InitializePortfolioVault => InitializePortfolioVault {
+        user,
+        portfolio_vault = pda::portfolio(user.key),
+        system_program,
+    }
+
+    pub fn initialize_portfolio_vault();
+}
+
+vault_cpi! {
+    /// Accounts of `add_portfolio_mint`
+    #[event_cpi]
+    pub struct AddPortfolioMint => AddPortfolioMint {
+        user,
+        portfolio_vault = pda::portfolio(user.key),
+        token_mint,
+        vault_authority = pda::vault_authority(),
+        mint_config = pda::mint_config(token_mint.key),
+        vault_token_account,
+        token_program,
+        associated_token_program,
+        system_program,
+    }
+
+    pub fn add_portfolio_mint();
+}
+
+vault_cpi! {
+    /// Accounts of `deposit_portfolio`
+    #[event_cpi]
+    pub struct DepositPortfolio => DepositPortfolio {
+        user,
+        portfolio_vault = pda::portfolio(user.key),
+        token_mint,
+        mint_config = pda::mint_config(token_mint.key),
+        mint_stats = pda::mint_stats(token_mint.key),
+        user_token_account,
+        vault_token_account,
+        token_program,
+    }
+
+    pub fn deposit_portfolio(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of `withdraw_portfolio`
+    #[event_cpi]
+    pub struct WithdrawPortfolio => WithdrawPortfolio {
+        user,
+        portfolio_vault = pda::portfolio(user.key),
+        token_mint,
+        mint_stats = pda::mint_stats(token_mint.key),
+        user_token_account,
+        vault_token_account,
+        token_program,
+    }
+
+    pub fn withdraw_portfolio(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of the portfolio lock/unlock instructions (single mint, basket and value).
+    /// The `MintStats` of each mint involved is passed separately, in leg order
+    #[event_cpi]
+    pub struct LockPortfolioCollateral => LockPortfolioCollateral, UnlockPortfolioCollateral {
+        signer,
+        portfolio_vault,
+        vault_authority = pda::vault_authority(),
+        integrator_stats = pda::integrator_stats(signer.key),
+        /// `pda::integrator_position` of `signer` in `portfolio_vault`
+        integrator_position = pda::integrator_position(signer.key, portfolio_vault.key),
+        /// Creates `integrator_stats` and `integrator_position` on first use, paid by `signer`
+        system_program,
+    }
+}
+
+pub fn lock_portfolio_collateral<'info>(
+    accounts: LockPortfolioCollateral<'info>,
+    token_mint: Pubkey,
+    amount: u64,
+    mint_stats: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    accounts.check()?;
+    check_address(&mint_stats, pda::mint_stats(&token_mint).0)?;
+    collateral_vault::cpi::lock_portfolio_collateral(
+        accounts.context(signer_seeds).with_remaining_accounts(vec![mint_stats]),
+        token_mint,
+        amount,
+    )
+}
+
+pub fn unlock_portfolio_collateral<'info>(
+    accounts: LockPortfolioCollateral<'info>,
+    token_mint: Pubkey,
+    amount: u64,
+    mint_stats: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    accounts.check()?;
+    check_address(&mint_stats, pda::mint_stats(&token_mint).0)?;
+    collateral_vault::cpi::unlock_portfolio_collateral(
+        accounts.context(signer_seeds).with_remaining_accounts(vec![mint_stats]),
+        token_mint,
+        amount,
+    )
+}
+
+/// `mint_stats` holds the `MintStats` of each leg's mint, in leg order
+pub fn lock_portfolio_basket<'info>(
+    accounts: LockPortfolioCollateral<'info>,
+    legs: Vec<BasketLeg>,
+    mint_stats: Vec<AccountInfo<'info>>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    accounts.check()?;
+    for (leg, mint_stats) in legs.iter().zip(&mint_stats) {
+        check_address(mint_stats, pda::mint_stats(&leg.mint).0)?;
+    }
+    collateral_vault::cpi::lock_portfolio_basket(
+        accounts.context(signer_seeds).with_remaining_accounts(mint_stats),
+        legs,
+    )
+}
+
+/// `mint_stats` holds the `MintStats` of each leg's mint, in leg order
+pub fn unlock_portfolio_basket<'info>(
+    accounts: LockPortfolioCollateral<'info>,
+    legs: Vec<BasketLeg>,
+    mint_stats: Vec<AccountInfo<'info>>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    accounts.check()?;
+    for (leg, mint_stats) in legs.iter().zip(&mint_stats) {
+        check_address(mint_stats, pda::mint_stats(&leg.mint).0)?;
+    }
+    collateral_vault::cpi::unlock_portfolio_basket(
+        accounts.context(signer_seeds).with_remaining_accounts(mint_stats),
+        legs,
+    )
+}
+
+/// Lock `value` quote units split across mints by weight. `leg_accounts` holds
+/// `[token_mint, oracle_config, oracle, mint_stats]` per leg, in leg order; returns
+/// the token amount locked per mint, to pass back to [`unlock_portfolio_basket`]
+pub fn lock_portfolio_value<'info>(
+    accounts: LockPortfolioCollateral<'info>,
+    value: u64,
+    legs: Vec<BasketWeight>,
+    leg_accounts: Vec<AccountInfo<'info>>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<Vec<BasketLeg>> {
+    accounts.check()?;
+    for (leg, accounts) in legs.iter().zip(leg_accounts.chunks(4)) {
+        if let [token_mint, oracle_config, _, mint_stats] = accounts {
+            check_address(token_mint, leg.mint)?;
+            check_address(oracle_config, pda::oracle_config(&leg.mint).0)?;
+            check_address(mint_stats, pda::mint_stats(&leg.mint).0)?;
+        }
+    }
+    
+    let locked = collateral_vault::cpi::lock_portfolio_value(
+        accounts.context(signer_seeds).with_remaining_accounts(leg_accounts),
+        value,
+        legs,
+    )?;
+    
+    Ok(locked.get())
+}
+
+vault_cpi! {
+    /// Accounts of `close_portfolio_vault`
+    #[event_cpi]
+    pub struct ClosePortfolioVault => ClosePortfolioVault {
+        user,
+        portfolio_vault = pda::portfolio(user.key),
+    }
+
+    pub fn close_portfolio_vault();
+}
+
+// ============ VIEWS ============
+
+/// Read a vault's balances through `get_vault_balances` return data
+pub fn get_vault_balances<'info>(
+    vault_program: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+) -> Result<VaultBalances> {
+    check_program(&vault_program)?;
+    let balances = collateral_vault::cpi::get_vault_balances(CpiContext::new(
+        vault_program,
+        accounts::GetVaultBalances { vault },
+    ))?;
+    
+    Ok(balances.get())
+}
+
+/// Read a vault's lock positions; `sub_accounts` must belong to `vault`
+pub fn get_lock_positions<'info>(
+    vault_program: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    sub_accounts: Vec<AccountInfo<'info>>,
+) -> Result<LockPositions> {
+    check_program(&vault_program)?;
+    let positions = collateral_vault::cpi::get_lock_positions(
+        CpiContext::new(vault_program, accounts::GetVaultBalances { vault })
+            .with_remaining_accounts(sub_accounts),
+    )?;
+    
+    Ok(positions.get())
+}
+
+/// Read the admin and authorized programs through `get_authority_config`
+pub fn get_authority_config<'info>(
+    vault_program: AccountInfo<'info>,
+    vault_authority: AccountInfo<'info>,
+) -> Result<AuthorityConfig> {
+    check_program(&vault_program)?;
+    check_address(&vault_authority, pda::vault_authority().0)?;
+    let config = collateral_vault::cpi::get_authority_config(CpiContext::new(
+        vault_program,
+        accounts::GetAuthorityConfig { vault_authority },
+    ))?;
+    
+    Ok(config.get())
+}
+
+/// Read a mint's aggregate statistics through `get_mint_stats`
+pub fn get_mint_stats<'info>(
+    vault_program: AccountInfo<'info>,
+    mint_stats: AccountInfo<'info>,
+) -> Result<MintStatsView> {
+    check_program(&vault_program)?;
+    let stats = collateral_vault::cpi::get_mint_stats(CpiContext::new(
+        vault_program,
+        accounts::GetMintStats { mint_stats },
+    ))?;
+    
+    Ok(stats.get())
+}
+
+vault_cpi! {
+    /// Accounts of `get_collateral_value`
+    pub struct GetCollateralValue => GetCollateralValue {
+        vault,
+        token_mint,
+        oracle_config = pda::oracle_config(token_mint.key),
+        oracle,
+    }
+}
+
+/// Risk-adjusted value of a vault in quote units
+pub fn get_collateral_value(accounts: GetCollateralValue<'_>) -> Result<CollateralValue> {
+    accounts.check()?;
+    let value = collateral_vault::cpi::get_collateral_value(accounts.context(&[]))?;
+    
+    Ok(value.get())
+}
+
+vault_cpi! {
+    /// Accounts of `assert_invariants`
+    pub struct AssertInvariants => AssertInvariants {
+        vault,
+        vault_token_account,
+    }
+}
+
+pub fn assert_invariants(accounts: AssertInvariants<'_>) -> Result<()> {
+    accounts.check()?;
+    collateral_vault::cpi::assert_invariants(accounts.context(&[]))
+}
+
+// ============ ADMIN ============
+
+vault_cpi! {
+    /// Accounts of `initialize_authority`
+    #[event_cpi]
+    pub struct InitializeAuthority => InitializeAuthority {
+        admin,
+        vault_authority = pda::vault_authority(),
+        system_program,
+    }
+
+    pub fn initialize_authority(authorized_programs: Vec<Pubkey>);
+}
+
+vault_cpi! {
+    /// Accounts of `add_authorized_program` / `remove_authorized_program` /
+    /// `set_denied_extensions`
+    #[event_cpi]
+    pub struct UpdateAuthority => UpdateAuthority {
+        admin,
+        vault_authority = pda::vault_authority(),
+    }
+
+    pub fn add_authorized_program(program: Pubkey);
+    pub fn remove_authorized_program(program: Pubkey);
+    pub fn set_denied_extensions(denied_extensions: u64);
+}
+
+vault_cpi! {
+    /// Accounts of `initialize_mint_config`
+    #[event_cpi]
+    pub struct InitializeMintConfig => InitializeMintConfig {
+        admin,
+        vault_authority = pda::vault_authority(),
+        token_mint,
+        mint_config = pda::mint_config(token_mint.key),
+        mint_stats = pda::mint_stats(token_mint.key),
+        system_program,
+    }
+
+    pub fn initialize_mint_config(params: MintConfigParams);
+}
+
+vault_cpi! {
+    /// Accounts of `update_mint_config`
+    #[event_cpi]
+    pub struct UpdateMintConfig => UpdateMintConfig {
+        admin,
+        vault_authority = pda::vault_authority(),
+        mint_config,
+    }
+
+    pub fn update_mint_config(params: MintConfigParams);
+}
+
+vault_cpi! {
+    /// Accounts of `initialize_integrator_stats`
+    #[event_cpi]
+    pub struct InitializeIntegratorStats => InitializeIntegratorStats {
+        admin,
+        vault_authority = pda::vault_authority(),
+        integrator_stats,
+        system_program,
+    }
+}
+
+pub fn initialize_integrator_stats(
+    accounts: InitializeIntegratorStats<'_>,
+    program: Pubkey,
+    max_locked: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    accounts.check()?;
+    check_address(&accounts.integrator_stats, pda::integrator_stats(&program).0)?;
+    collateral_vault::cpi::initialize_integrator_stats(
+        accounts.context(signer_seeds),
+        program,
+        max_locked,
+    )
+}
+
+vault_cpi! {
+    /// Accounts of `set_integrator_cap`
+    #[event_cpi]
+    pub struct SetIntegratorCap => SetIntegratorCap {
+        admin,
+        vault_authority = pda::vault_authority(),
+        integrator_stats,
+    }
+
+    pub fn set_integrator_cap(max_locked: u64);
+}
+
+vault_cpi! {
+    /// Accounts of `initialize_oracle_config`
+    #[event_cpi]
+    pub struct InitializeOracleConfig => InitializeOracleConfig {
+        admin,
+        vault_authority = pda::vault_authority(),
+        token_mint,
+        oracle_config = pda::oracle_config(token_mint.key),
+        system_program,
+    }
+
+    pub fn initialize_oracle_config(params: OracleConfigParams);
+}
+
+vault_cpi! {
+    /// Accounts of `update_oracle_config`
+    #[event_cpi]
+    pub struct UpdateOracleConfig => UpdateOracleConfig {
+        admin,
+        vault_authority = pda::vault_authority(),
+        oracle_config,
+    }
+
+    pub fn update_oracle_config(params: OracleConfigParams);
+}
+
+vault_cpi! {
+    /// Accounts of `initialize_custody_pool`
+    #[event_cpi]
+    pub struct InitializeCustodyPool => InitializeCustodyPool {
+        admin,
+        vault_authority = pda::vault_authority(),
+        token_mint,
+        custody_pool = pda::custody_pool(token_mint.key),
+        custody_token_account,
+        token_program,
+        associated_token_program,
+        system_program,
+    }
+
+    pub fn initialize_custody_pool();
+}
+
+// ============ ZERO-COPY (EXPERIMENTAL) ============
+
+vault_cpi! {
+    /// Accounts of `lock_collateral_zc` / `unlock_collateral_zc`
+    #[cfg(feature = "zero-copy")]
+    #[event_cpi]
+    pub struct LockCollateralZc => LockCollateralZc, UnlockCollateralZc {
+        signer,
+        vault,
+        vault_authority = pda::vault_authority_zc(),
+        mint_stats,
+        integrator_stats = pda::integrator_stats(signer.key),
+        /// `pda::integrator_position` of `signer` in `vault`
+        integrator_position = pda::integrator_position(signer.key, vault.key),
+        /// Creates `integrator_stats` and `integrator_position` on first use, paid by `signer`
+        system_program,
+    }
+
+    pub fn lock_collateral_zc(amount: u64);
+    pub fn unlock_collateral_zc(amount: u64);
+}
+
+vault_cpi! {
+    /// Accounts of `initialize_authority_zc`
+    #[cfg(feature = "zero-copy")]
+    #[event_cpi]
+    pub struct InitializeAuthorityZc => InitializeAuthorityZc {
+        admin,
+        vault_authority = pda::vault_authority_zc(),
+        system_program,
+    }
+
+    pub fn initialize_authority_zc(authorized_programs: Vec<Pubkey>);
+}
+
+vault_cpi! {
+    /// Accounts of `add_authorized_program_zc` / `remove_authorized_program_zc`
+    #[cfg(feature = "zero-copy")]
+    #[event_cpi]
+    pub struct UpdateAuthorityZc => UpdateAuthorityZc {
+        admin,
+        vault_authority = pda::vault_authority_zc(),
+    }
+
+    pub fn add_authorized_program_zc(program: Pubkey);
+    pub fn remove_authorized_program_zc(program: Pubkey);
+}
+
+vault_cpi! {
+    /// Accounts of `initialize_vault_zc`
+    #[cfg(feature = "zero-copy")]
+    #[event_cpi]
+    pub struct InitializeVaultZc => InitializeVaultZc {
+        user,
+        token_mint,
+        vault_authority = pda::vault_authority_zc(),
+        mint_config = pda::mint_config(token_mint.key),
+        mint_stats = pda::mint_stats(token_mint.key),
+        vault = pda::vault_zc(user.key),
+        vault_token_account,
+        token_program,
+        associated_token_program,
+        system_program,
+    }
+
+    pub fn initialize_vault_zc();
+}
+
+vault_cpi! {
+    /// Accounts of `deposit_zc`
+    #[cfg(feature = "zero-copy")]
+    #[event_cpi]
+    pub struct DepositZc => DepositZc {
+        user,
+        vault = pda::vault_zc(user.key),
+        token_mint,
+        mint_config = pda::mint_config(token_mint.key),
+        mint_stats = pda::mint_stats(token_mint.key),
+        user_token_account,
+        vault_token_account,
+        token_program,
+    }
+
+    pub fn deposit_zc(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of `withdraw_zc`
+    #[cfg(feature = "zero-copy")]
+    #[event_cpi]
+    pub struct WithdrawZc => WithdrawZc {
+        user,
+        vault = pda::vault_zc(user.key),
+        token_mint,
+        mint_stats = pda::mint_stats(token_mint.key),
+        user_token_account,
+        vault_token_account,
+        token_program,
+    }
+
+    pub fn withdraw_zc(amount: u64) with transfer_hook_accounts;
+}
+
+vault_cpi! {
+    /// Accounts of `close_vault_zc`
+    #[cfg(feature = "zero-copy")]
+    #[event_cpi]
+    pub struct CloseVaultZc => CloseVaultZc {
+        user,
+        vault = pda::vault_zc(user.key),
+        mint_stats,
+    }
+
+    pub fn close_vault_zc();
+}
+
+// ============ MOCK ORACLE ============
+
+vault_cpi! {
+    /// Accounts of `initialize_mock_price_feed`
+    #[cfg(feature = "mock-oracle")]
+    pub struct InitializeMockPriceFeed => InitializeMockPriceFeed {
+        authority,
+        price_feed,
+        system_program,
+    }
+
+    pub fn initialize_mock_price_feed();
+}
+
+vault_cpi! {
+    /// Accounts of `set_mock_price`
+    #[cfg(feature = "mock-oracle")]
+    pub struct SetMockPrice => SetMockPrice {
+        authority,
+        price_feed,
+    }
+
+    pub fn set_mock_price(price: i64, conf: u64, expo: i32, publish_time: Option<i64>);
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::error::Error;
+
+    use super::*;
+
+    fn info(key: Pubkey) -> AccountInfo<'static> {
+        AccountInfo::new(
+            Box::leak(Box::new(key)),
+            false,
+            true,
+            Box::leak(Box::new(0)),
+            &mut [],
+            &ID,
+            false,
+            0,
+        )
+    }
+
+    fn error_code(result: Result<()>) -> u32 {
+        match result.unwrap_err() {
+            Error::AnchorError(error) => error.error_code_number,
+            Error::ProgramError(error) => panic!("unexpected {error}"),
+        }
+    }
+
+    fn keys(metas: Vec<AccountMeta>) -> Vec<Pubkey> {
+        metas.into_iter().map(|meta| meta.pubkey).collect()
+    }
+
+    fn deposit(user: Pubkey, token_mint: Pubkey) -> Deposit<'static> {
+        Deposit {
+            vault_program: info(ID),
+            user: info(user),
+            vault: info(pda::vault(&user).0),
+            token_mint: info(token_mint),
+            mint_config: info(pda::mint_config(&token_mint).0),
+            mint_stats: info(pda::mint_stats(&token_mint).0),
+            user_token_account: info(Pubkey::new_unique()),
+            vault_token_account: info(Pubkey::new_unique()),
+            token_program: info(Pubkey::new_unique()),
+            #[cfg(feature = "event-cpi")]
+            event_authority: info(pda::event_authority().0),
+        }
+    }
+
+    #[test]
+    fn checks_program_and_pdas() {
+        let (user, token_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(deposit(user, token_mint).check().is_ok());
+
+        let mut accounts = deposit(user, token_mint);
+        accounts.vault_program = info(Pubkey::new_unique());
+        assert_eq!(error_code(accounts.check()), ErrorCode::InvalidProgramId as u32);
+
+        let other = Pubkey::new_unique();
+        let mut accounts = deposit(user, token_mint);
+        accounts.vault = info(pda::vault(&other).0);
+        assert_eq!(error_code(accounts.check()), ErrorCode::ConstraintSeeds as u32);
+
+        let mut accounts = deposit(user, token_mint);
+        accounts.mint_stats = info(pda::mint_stats(&other).0);
+        assert_eq!(error_code(accounts.check()), ErrorCode::ConstraintSeeds as u32);
+
+        #[cfg(feature = "event-cpi")]
+        {
+            let mut accounts = deposit(user, token_mint);
+            accounts.event_authority = info(other);
+            assert_eq!(error_code(accounts.check()), ErrorCode::ConstraintSeeds as u32);
+        }
+    }
+
+    #[test]
+    fn shared_accounts_convert_into_each_instruction() {
+        let signer = Pubkey::new_unique();
+        let vault = pda::vault(&Pubkey::new_unique()).0;
+        let mint_stats = pda::mint_stats(&Pubkey::new_unique()).0;
+        let system_program = Pubkey::new_unique();
+        let lock = || LockCollateral {
+            vault_program: info(ID),
+            signer: info(signer),
+            vault: info(vault),
+            vault_authority: info(pda::vault_authority().0),
+            mint_stats: info(mint_stats),
+            integrator_stats: info(pda::integrator_stats(&signer).0),
+            integrator_position: info(pda::integrator_position(&signer, &vault).0),
+            system_program: info(system_program),
+            #[cfg(feature = "event-cpi")]
+            event_authority: info(pda::event_authority().0),
+        };
+        assert!(lock().check().is_ok());
+
+        #[allow(unused_mut)]
+        let mut expected = vec![
+            signer,
+            vault,
+            pda::vault_authority().0,
+            mint_stats,
+            pda::integrator_stats(&signer).0,
+            pda::integrator_position(&signer, &vault).0,
+            system_program,
+        ];
+        #[cfg(feature = "event-cpi")]
+        expected.extend([pda::event_authority().0, ID]);
+        assert_eq!(keys(accounts::LockCollateral::from(lock()).to_account_metas(None)), expected);
+        assert_eq!(keys(accounts::UnlockCollateral::from(lock()).to_account_metas(None)), expected);
+
+        // A position in another vault is rejected before the CPI
+        let mut accounts = lock();
+        accounts.integrator_position = info(pda::integrator_position(&signer, &mint_stats).0);
+        assert_eq!(error_code(accounts.check()), ErrorCode::ConstraintSeeds as u32);
+    }
+
+    #[test]
+    fn optional_accounts_pass_through() {
+        let user = Pubkey::new_unique();
+        let close = |custody_pool: Option<Pubkey>| CloseVault {
+            vault_program: info(ID),
+            user: info(user),
+            vault: info(pda::vault(&user).0),
+            vault_token_account: info(Pubkey::new_unique()),
+            mint_stats: info(Pubkey::new_unique()),
+            custody_pool: custody_pool.map(info),
+            #[cfg(feature = "event-cpi")]
+            event_authority: info(pda::event_authority().0),
+        };
+        assert!(close(None).check().is_ok());
+
+        let pool = Pubkey::new_unique();
+        let with_pool = keys(accounts::CloseVault::from(close(Some(pool))).to_account_metas(None));
+        assert_eq!(with_pool[4], pool);
+        // Anchor stands the program id in for a missing optional account
+        let without = keys(accounts::CloseVault::from(close(None)).to_account_metas(None));
+        assert_eq!(without[4], ID);
+    }
+}
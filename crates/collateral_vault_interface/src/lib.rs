@@ -0,0 +1,48 @@
+//! Integration surface of the collateral vault program for other on-chain
+//! programs.
+//!
+//! - [`pda`]: address derivation for every vault-owned account
+//! - [`cpi`]: typed wrappers for every instruction
+//! - [`raw`]: the Anchor-generated CPI module, covering every instruction
+//! - account, view, event and error types re-exported from the program
+
+pub mod cpi;
+pub mod pda;
+
+pub use collateral_vault::cpi as raw;
+pub use collateral_vault::program::CollateralVault as CollateralVaultProgram;
+pub use collateral_vault::{ID, ID_CONST};
+
+// Accounts and instruction arguments
+pub use collateral_vault::{
+    BasketLeg, BasketWeight, CollateralVault, CustodyPool, IntegratorPosition, IntegratorStats,
+    MintBalance, MintConfig, MintConfigParams, MintStats, OracleConfig, OracleConfigParams,
+    PortfolioVault, PositionLock, PythPriceMessage, PythPriceUpdate, PythVerificationLevel,
+    SubAccount, VaultAuthority, AUTHORITY_VERSION, DEFAULT_DENIED_EXTENSIONS, PYTH_RECEIVER_ID,
+    QUOTE_DECIMALS, VAULT_VERSION,
+};
+
+// Experimental zero-copy accounts, only in programs built with `zero-copy`
+#[cfg(feature = "zero-copy")]
+pub use collateral_vault::{CollateralVaultZc, VaultAuthorityZc, ZERO_COPY_VERSION};
+
+// Return data of the view instructions
+pub use collateral_vault::{
+    AuthorityConfig, CollateralValue, LockPositions, MintStatsView, OraclePrice, SubAccountLock,
+    VaultBalances, VIEW_VERSION,
+};
+
+// Events
+pub use collateral_vault::{
+    AuthorityInitialized, AuthorityMigrated, CustodyPoolInitialized, CustodyPoolVerified,
+    DeniedExtensionsUpdated, DepositEvent, IntegratorCapUpdated, LockEvent, MintConfigUpdated,
+    OracleConfigUpdated, PortfolioDepositEvent, PortfolioLockEvent, PortfolioMintAdded,
+    PortfolioUnlockEvent, PortfolioVaultClosed, PortfolioVaultInitialized, PortfolioWithdrawEvent,
+    ProgramAuthorized, ProgramDeauthorized, SubAccountAllocated, SubAccountClosed,
+    SubAccountCreated, SubAccountLockEvent, SubAccountMoved, SubAccountReleased,
+    SubAccountTransferEvent, SubAccountUnlockEvent, TransferEvent, UnlockEvent, VaultClosed,
+    VaultInitialized, VaultMigrated, WithdrawEvent,
+};
+
+// Errors
+pub use collateral_vault::VaultError;
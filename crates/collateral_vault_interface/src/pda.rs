@@ -0,0 +1,94 @@
+//! PDA derivation for every account owned by the collateral vault program.
+//!
+//! Each helper mirrors the `seeds` constraint of the matching account in the
+//! program and returns `(address, bump)`.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::ID;
+
+pub const VAULT_SEED: &[u8] = b"vault";
+pub const VAULT_AUTHORITY_SEED: &[u8] = b"vault_authority";
+pub const CUSTODY_POOL_SEED: &[u8] = b"custody_pool";
+pub const SUB_ACCOUNT_SEED: &[u8] = b"sub_account";
+pub const PORTFOLIO_SEED: &[u8] = b"portfolio";
+pub const ORACLE_CONFIG_SEED: &[u8] = b"oracle_config";
+pub const MINT_CONFIG_SEED: &[u8] = b"mint_config";
+pub const MINT_STATS_SEED: &[u8] = b"mint_stats";
+pub const INTEGRATOR_STATS_SEED: &[u8] = b"integrator_stats";
+pub const INTEGRATOR_POSITION_SEED: &[u8] = b"integrator_position";
+#[cfg(feature = "zero-copy")]
+pub const VAULT_ZC_SEED: &[u8] = b"vault_zc";
+#[cfg(feature = "zero-copy")]
+pub const VAULT_AUTHORITY_ZC_SEED: &[u8] = b"vault_authority_zc";
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+/// `CollateralVault` of `owner`
+pub fn vault(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, owner.as_ref()], &ID)
+}
+
+/// Global `VaultAuthority`. Token accounts are owned by their vault, portfolio or custody pool
+pub fn vault_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_AUTHORITY_SEED], &ID)
+}
+
+/// Shared `CustodyPool` of `token_mint`
+pub fn custody_pool(token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CUSTODY_POOL_SEED, token_mint.as_ref()], &ID)
+}
+
+/// `SubAccount` number `id` under `vault`
+pub fn sub_account(vault: &Pubkey, id: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SUB_ACCOUNT_SEED, vault.as_ref(), &[id]], &ID)
+}
+
+/// `PortfolioVault` of `owner`
+pub fn portfolio(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PORTFOLIO_SEED, owner.as_ref()], &ID)
+}
+
+/// `OracleConfig` of `token_mint`
+pub fn oracle_config(token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ORACLE_CONFIG_SEED, token_mint.as_ref()], &ID)
+}
+
+/// `MintConfig` registry entry of `token_mint`
+pub fn mint_config(token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MINT_CONFIG_SEED, token_mint.as_ref()], &ID)
+}
+
+/// `MintStats` of `token_mint`
+pub fn mint_stats(token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MINT_STATS_SEED, token_mint.as_ref()], &ID)
+}
+
+/// `IntegratorStats` of an authorized program (the key that signs its CPIs)
+pub fn integrator_stats(program: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[INTEGRATOR_STATS_SEED, program.as_ref()], &ID)
+}
+
+/// `IntegratorPosition` of `program` in `holder`, a vault, sub-account or portfolio
+pub fn integrator_position(program: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[INTEGRATOR_POSITION_SEED, program.as_ref(), holder.as_ref()],
+        &ID,
+    )
+}
+
+/// Zero-copy `CollateralVaultZc` of `owner`
+#[cfg(feature = "zero-copy")]
+pub fn vault_zc(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_ZC_SEED, owner.as_ref()], &ID)
+}
+
+/// Zero-copy `VaultAuthorityZc`
+#[cfg(feature = "zero-copy")]
+pub fn vault_authority_zc() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_AUTHORITY_ZC_SEED], &ID)
+}
+
+/// Signer of the program's event self-CPIs (`#[event_cpi]` `event_authority`)
+pub fn event_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &ID)
+}
@@ -0,0 +1,561 @@
+//! `vault-cli`: operate the collateral vault program from the command line.
+//!
+//! Every transaction command can be signed offline: pass `--sign-only` with
+//! `--blockhash` to print a base64 transaction without touching the network,
+//! then broadcast it later with `vault-cli submit`.
+
+use std::{error::Error, path::PathBuf};
+
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::instruction::Instruction,
+};
+use anchor_spl::token_2022;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use collateral_vault_client::{
+    accounts, instructions, pda, rpc_vault_error, CollateralVault, MintConfigParams, VaultAuthority,
+};
+use serde_json::{json, Value};
+use solana_commitment_config::CommitmentConfig;
+use solana_hash::Hash;
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(name = "vault-cli", version, about = "Admin and user tool for the collateral vault program")]
+struct Cli {
+    /// RPC endpoint
+    #[arg(long, short = 'u', global = true, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    
+    /// Keypair of the admin or vault owner [default: ~/.config/solana/id.json]
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<PathBuf>,
+    
+    #[arg(long, global = true, value_enum, default_value_t = Output::Display)]
+    output: Output,
+    
+    /// Print the signed transaction instead of sending it (requires --blockhash)
+    #[arg(long, global = true, requires = "blockhash")]
+    sign_only: bool,
+    
+    /// Recent blockhash to sign with instead of fetching one
+    #[arg(long, global = true)]
+    blockhash: Option<Hash>,
+    
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Output {
+    Display,
+    Json,
+}
+
+#[derive(Args)]
+struct MintArgs {
+    /// Collateral mint
+    #[arg(long)]
+    mint: Pubkey,
+    
+    /// Token program owning the mint
+    #[arg(long, default_value_t = token_2022::ID)]
+    token_program: Pubkey,
+}
+
+#[derive(Args)]
+struct MintConfigArgs {
+    /// Mint to register
+    mint: Pubkey,
+    
+    /// Register the mint without accepting it as collateral
+    #[arg(long)]
+    unapproved: bool,
+    
+    /// Bitmask of permitted Token-2022 extensions (decimal or 0x-prefixed hex)
+    #[arg(long, default_value = "0", value_parser = parse_mask)]
+    allowed_extensions: u64,
+    
+    /// Accept the mint even though it has a freeze authority
+    #[arg(long)]
+    allow_freeze_authority: bool,
+    
+    /// Max single deposit in base units (0 = unlimited)
+    #[arg(long, default_value_t = 0)]
+    max_deposit: u64,
+    
+    /// Max balance held by one vault in base units (0 = unlimited)
+    #[arg(long, default_value_t = 0)]
+    max_vault_balance: u64,
+    
+    /// Cap on the mint's total balance across all vaults (0 = unlimited)
+    #[arg(long, default_value_t = 0)]
+    max_total_balance: u64,
+}
+
+impl MintConfigArgs {
+    fn params(&self) -> MintConfigParams {
+        MintConfigParams {
+            approved: !self.unapproved,
+            allowed_extensions: self.allowed_extensions,
+            allow_freeze_authority: self.allow_freeze_authority,
+            max_deposit_amount: self.max_deposit,
+            max_vault_balance: self.max_vault_balance,
+            max_total_balance: self.max_total_balance,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the global vault authority with the signer as admin
+    InitAuthority {
+        /// Initially authorized program (repeatable)
+        #[arg(long = "program")]
+        programs: Vec<Pubkey>,
+    },
+    /// Authorize a program to lock, unlock and transfer collateral
+    AddProgram { program: Pubkey },
+    /// Revoke a program's authorization
+    RemoveProgram { program: Pubkey },
+    /// Register a mint as collateral; vaults can only be opened for registered mints
+    InitMintConfig {
+        #[command(flatten)]
+        config: MintConfigArgs,
+    },
+    /// Replace a registered mint's configuration
+    UpdateMintConfig {
+        #[command(flatten)]
+        config: MintConfigArgs,
+    },
+    /// Create a program's exposure counters ahead of its first lock
+    InitIntegratorStats {
+        program: Pubkey,
+        
+        /// Cap on the collateral the program can hold locked (0 = unlimited)
+        #[arg(long, default_value_t = 0)]
+        max_locked: u64,
+    },
+    /// Change a program's lock cap
+    SetIntegratorCap {
+        program: Pubkey,
+        
+        /// Cap on the collateral the program can hold locked (0 = unlimited)
+        max_locked: u64,
+    },
+    /// Move a pre-migration vault authority to the current layout
+    MigrateAuthority,
+    /// Create the signer's vault for a mint
+    InitVault {
+        #[command(flatten)]
+        mint: MintArgs,
+    },
+    /// Deposit tokens into the signer's vault
+    Deposit {
+        #[command(flatten)]
+        mint: MintArgs,
+        
+        /// Amount in base units
+        amount: u64,
+    },
+    /// Withdraw available tokens from the signer's vault
+    Withdraw {
+        #[command(flatten)]
+        mint: MintArgs,
+        
+        /// Amount in base units
+        amount: u64,
+    },
+    /// Close the signer's empty vault
+    Close {
+        #[command(flatten)]
+        mint: MintArgs,
+        
+        /// The vault keeps its tokens in the mint's custody pool
+        #[arg(long)]
+        pooled: bool,
+    },
+    /// Check a vault's balance invariants on-chain [default: the signer's]
+    CheckVault {
+        #[command(flatten)]
+        mint: MintArgs,
+        
+        owner: Option<Pubkey>,
+        
+        /// The vault keeps its tokens in the mint's custody pool
+        #[arg(long)]
+        pooled: bool,
+    },
+    /// Show a vault [default: the signer's]
+    ShowVault { owner: Option<Pubkey> },
+    /// Show the vault authority
+    ShowAuthority,
+    /// Broadcast a transaction produced with --sign-only
+    Submit { transaction: String },
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> CliResult<()> {
+    let rpc = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+    
+    let instruction = match &cli.command {
+        Command::InitAuthority { programs } => {
+            instructions::initialize_authority(&signer(&cli)?.pubkey(), programs.clone())
+        }
+        Command::AddProgram { program } => {
+            instructions::add_authorized_program(&signer(&cli)?.pubkey(), *program)
+        }
+        Command::RemoveProgram { program } => {
+            instructions::remove_authorized_program(&signer(&cli)?.pubkey(), *program)
+        }
+        Command::InitMintConfig { config } => {
+            instructions::initialize_mint_config(&signer(&cli)?.pubkey(), &config.mint, config.params())
+        }
+        Command::UpdateMintConfig { config } => {
+            instructions::update_mint_config(&signer(&cli)?.pubkey(), &config.mint, config.params())
+        }
+        Command::InitIntegratorStats { program, max_locked } => {
+            instructions::initialize_integrator_stats(&signer(&cli)?.pubkey(), *program, *max_locked)
+        }
+        Command::SetIntegratorCap { program, max_locked } => {
+            instructions::set_integrator_cap(&signer(&cli)?.pubkey(), program, *max_locked)
+        }
+        Command::MigrateAuthority => instructions::migrate_authority(&signer(&cli)?.pubkey()),
+        Command::InitVault { mint } => {
+            instructions::initialize_vault(&signer(&cli)?.pubkey(), &mint.mint, &mint.token_program)
+        }
+        Command::Deposit { mint, amount } => {
+            instructions::deposit(&signer(&cli)?.pubkey(), &mint.mint, &mint.token_program, *amount)
+        }
+        Command::Withdraw { mint, amount } => {
+            instructions::withdraw(&signer(&cli)?.pubkey(), &mint.mint, &mint.token_program, *amount)
+        }
+        Command::Close { mint, pooled } => {
+            instructions::close_vault(&signer(&cli)?.pubkey(), &mint.mint, &mint.token_program, *pooled)
+        }
+        Command::CheckVault { mint, owner, pooled } => {
+            let owner = match owner {
+                Some(owner) => *owner,
+                None => signer(&cli)?.pubkey(),
+            };
+            instructions::assert_invariants(&owner, &mint.mint, &mint.token_program, *pooled)
+        }
+        Command::ShowVault { owner } => {
+            let owner = match owner {
+                Some(owner) => *owner,
+                None => signer(&cli)?.pubkey(),
+            };
+            return show_vault(&cli, &rpc, &owner);
+        }
+        Command::ShowAuthority => return show_authority(&cli, &rpc),
+        Command::Submit { transaction } => return send(&cli, &rpc, &decode_transaction(transaction)?),
+    };
+    
+    execute(&cli, &rpc, instruction)
+}
+
+fn signer(cli: &Cli) -> CliResult<Keypair> {
+    let path = match &cli.keypair {
+        Some(path) => path.clone(),
+        None => PathBuf::from(std::env::var("HOME")?).join(".config/solana/id.json"),
+    };
+    
+    read_keypair_file(&path).map_err(|err| format!("failed to read keypair {}: {err}", path.display()).into())
+}
+
+fn execute(cli: &Cli, rpc: &RpcClient, instruction: Instruction) -> CliResult<()> {
+    let signer = signer(cli)?;
+    let blockhash = match cli.blockhash {
+        Some(blockhash) => blockhash,
+        None => rpc.get_latest_blockhash()?,
+    };
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&signer.pubkey()),
+        &[&signer],
+        blockhash,
+    );
+    
+    if cli.sign_only {
+        return print(cli, signed_json(&transaction)?);
+    }
+    
+    send(cli, rpc, &transaction)
+}
+
+/// `--sign-only` output: the fee payer, the blockhash and the base64 bincode transaction
+fn signed_json(transaction: &Transaction) -> CliResult<Value> {
+    Ok(json!({
+        "signer": transaction.message.account_keys[0].to_string(),
+        "blockhash": transaction.message.recent_blockhash.to_string(),
+        "transaction": STANDARD.encode(bincode::serialize(transaction)?),
+    }))
+}
+
+fn decode_transaction(encoded: &str) -> CliResult<Transaction> {
+    Ok(bincode::deserialize(&STANDARD.decode(encoded.trim())?)?)
+}
+
+fn parse_mask(value: &str) -> Result<u64, String> {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|err| format!("invalid mask {value}: {err}"))
+}
+
+fn send(cli: &Cli, rpc: &RpcClient, transaction: &Transaction) -> CliResult<()> {
+    match rpc.send_and_confirm_transaction(transaction) {
+        Ok(signature) => print(cli, json!({ "signature": signature.to_string() })),
+        Err(err) => match rpc_vault_error(&err) {
+            Some(vault_error) => Err(format!("{vault_error:?}: {vault_error}").into()),
+            None => Err(err.into()),
+        },
+    }
+}
+
+fn show_vault(cli: &Cli, rpc: &RpcClient, owner: &Pubkey) -> CliResult<()> {
+    print(cli, vault_json(owner, &accounts::fetch_vault(rpc, owner)?))
+}
+
+fn vault_json(owner: &Pubkey, vault: &CollateralVault) -> Value {
+    json!({
+        "address": pda::vault(owner).0.to_string(),
+        "owner": vault.owner.to_string(),
+        "token_mint": vault.token_mint.to_string(),
+        "token_account": vault.token_account.to_string(),
+        "total_balance": vault.total_balance,
+        "locked_balance": vault.locked_balance,
+        "available_balance": vault.available_balance,
+        "sub_account_balance": vault.sub_account_balance,
+        "total_deposited": vault.total_deposited,
+        "total_withdrawn": vault.total_withdrawn,
+        "total_transferred_in": vault.total_transferred_in,
+        "total_transferred_out": vault.total_transferred_out,
+        "total_seized": vault.total_seized,
+        "total_fees_paid": vault.total_fees_paid,
+        "pooled": vault.pooled,
+        "created_at": vault.created_at,
+        "version": vault.version,
+        "sequence": vault.sequence,
+    })
+}
+
+fn show_authority(cli: &Cli, rpc: &RpcClient) -> CliResult<()> {
+    print(cli, authority_json(&accounts::fetch_vault_authority(rpc)?))
+}
+
+fn authority_json(authority: &VaultAuthority) -> Value {
+    let programs: Vec<String> = authority.authorized_programs.iter().map(Pubkey::to_string).collect();
+    json!({
+        "address": pda::vault_authority().0.to_string(),
+        "admin": authority.admin.to_string(),
+        "authorized_programs": programs,
+        "denied_extensions": format!("{:#x}", authority.denied_extensions),
+        "sequence": authority.sequence,
+    })
+}
+
+fn print(cli: &Cli, value: Value) -> CliResult<()> {
+    println!("{}", render(cli.output, &value)?);
+    Ok(())
+}
+
+fn render(output: Output, value: &Value) -> CliResult<String> {
+    if let Output::Json = output {
+        return Ok(serde_json::to_string_pretty(value)?);
+    }
+    
+    let mut lines = Vec::new();
+    for (key, value) in value.as_object().into_iter().flatten() {
+        match value {
+            Value::String(value) => lines.push(format!("{key}: {value}")),
+            Value::Array(values) => {
+                lines.push(format!("{key}:"));
+                for value in values {
+                    lines.push(format!("  {}", value.as_str().map_or_else(|| value.to_string(), str::to_string)));
+                }
+            }
+            value => lines.push(format!("{key}: {value}")),
+        }
+    }
+    
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("vault-cli").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn command_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_mint_config() {
+        let mint = Pubkey::new_unique();
+        let cli = parse(&[
+            "init-mint-config",
+            &mint.to_string(),
+            "--allowed-extensions",
+            "0x41",
+            "--max-deposit",
+            "500",
+            "--max-total-balance",
+            "9000",
+        ])
+        .unwrap();
+        let Command::InitMintConfig { config } = cli.command else {
+            panic!("parsed the wrong command");
+        };
+        let params = config.params();
+        assert_eq!(config.mint, mint);
+        assert!(params.approved);
+        assert_eq!(params.allowed_extensions, 0x41);
+        assert!(!params.allow_freeze_authority);
+        assert_eq!(params.max_deposit_amount, 500);
+        assert_eq!(params.max_vault_balance, 0);
+        assert_eq!(params.max_total_balance, 9000);
+
+        let cli = parse(&[
+            "update-mint-config",
+            &mint.to_string(),
+            "--unapproved",
+            "--allow-freeze-authority",
+            "--allowed-extensions",
+            "12",
+        ])
+        .unwrap();
+        let Command::UpdateMintConfig { config } = cli.command else {
+            panic!("parsed the wrong command");
+        };
+        let params = config.params();
+        assert!(!params.approved);
+        assert!(params.allow_freeze_authority);
+        assert_eq!(params.allowed_extensions, 12);
+
+        assert!(parse(&["init-mint-config", &mint.to_string(), "--allowed-extensions", "0xz"]).is_err());
+        assert!(parse(&["init-mint-config", "not-a-pubkey"]).is_err());
+    }
+
+    #[test]
+    fn parses_integrator_commands() {
+        let program = Pubkey::new_unique();
+        let cli = parse(&["init-integrator-stats", &program.to_string()]).unwrap();
+        let Command::InitIntegratorStats { program: parsed, max_locked } = cli.command else {
+            panic!("parsed the wrong command");
+        };
+        assert_eq!(parsed, program);
+        assert_eq!(max_locked, 0);
+
+        let cli = parse(&["init-integrator-stats", &program.to_string(), "--max-locked", "77"]).unwrap();
+        assert!(matches!(cli.command, Command::InitIntegratorStats { max_locked: 77, .. }));
+
+        let cli = parse(&["set-integrator-cap", &program.to_string(), "5"]).unwrap();
+        assert!(matches!(cli.command, Command::SetIntegratorCap { max_locked: 5, .. }));
+        assert!(parse(&["set-integrator-cap", &program.to_string()]).is_err());
+    }
+
+    #[test]
+    fn parses_global_options() {
+        let mint = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+        let cli = parse(&[
+            "deposit",
+            "--mint",
+            &mint.to_string(),
+            "42",
+            "--output",
+            "json",
+            "--sign-only",
+            "--blockhash",
+            &blockhash.to_string(),
+            "-k",
+            "/tmp/admin.json",
+        ])
+        .unwrap();
+        assert!(cli.sign_only);
+        assert_eq!(cli.blockhash, Some(blockhash));
+        assert!(matches!(cli.output, Output::Json));
+        assert_eq!(cli.keypair, Some(PathBuf::from("/tmp/admin.json")));
+        let Command::Deposit { mint: mint_args, amount } = cli.command else {
+            panic!("parsed the wrong command");
+        };
+        assert_eq!(mint_args.mint, mint);
+        assert_eq!(mint_args.token_program, token_2022::ID);
+        assert_eq!(amount, 42);
+    }
+
+    #[test]
+    fn sign_only_requires_blockhash() {
+        let err = parse(&["migrate-authority", "--sign-only"]).err().unwrap();
+        assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn signed_transaction_round_trips() {
+        let signer = Keypair::new();
+        let blockhash = Hash::new_unique();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instructions::migrate_authority(&signer.pubkey())],
+            Some(&signer.pubkey()),
+            &[&signer],
+            blockhash,
+        );
+
+        let output = signed_json(&transaction).unwrap();
+        assert_eq!(output["signer"], signer.pubkey().to_string());
+        assert_eq!(output["blockhash"], blockhash.to_string());
+
+        let decoded = decode_transaction(output["transaction"].as_str().unwrap()).unwrap();
+        assert_eq!(decoded, transaction);
+        assert!(decoded.verify().is_ok());
+        assert!(decode_transaction("not base64").is_err());
+    }
+
+    #[test]
+    fn renders_json_and_display() {
+        let admin = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let authority = VaultAuthority {
+            admin,
+            authorized_programs: vec![program],
+            bump: 255,
+            denied_extensions: 0x30,
+            sequence: 7,
+            version: 1,
+        };
+        let value = authority_json(&authority);
+
+        let json: Value = serde_json::from_str(&render(Output::Json, &value).unwrap()).unwrap();
+        assert_eq!(json, value);
+        assert_eq!(json["admin"], admin.to_string());
+        assert_eq!(json["authorized_programs"], json!([program.to_string()]));
+        assert_eq!(json["denied_extensions"], "0x30");
+        assert_eq!(json["sequence"], 7);
+
+        let display = render(Output::Display, &value).unwrap();
+        assert!(display.contains(&format!("admin: {admin}")));
+        assert!(display.contains(&format!("authorized_programs:\n  {program}")));
+        assert!(display.contains("sequence: 7"));
+    }
+}
@@ -0,0 +1,192 @@
+//! Balance effect of each event on the vaults it touches.
+//!
+//! Deltas are derived from event amounts alone, so a vault's balances can be
+//! replayed from its history. Each entry also keeps the vault's event sequence
+//! and the post-event balances the event reports, to detect gaps in that history.
+
+use anchor_lang::prelude::Pubkey;
+use collateral_vault_client::VaultEvent;
+
+/// One vault's side of an event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LedgerEntry {
+    pub vault: Pubkey,
+    pub sequence: u64,
+    pub amount: u64,
+    pub delta: Delta,
+    pub reported: Reported,
+}
+
+/// Signed change to each vault balance
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Delta {
+    pub total: i128,
+    pub locked: i128,
+    pub available: i128,
+    pub sub_account: i128,
+}
+
+/// Post-event balances, where the event reports them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reported {
+    pub total: Option<u64>,
+    pub locked: Option<u64>,
+    pub available: Option<u64>,
+    pub sub_account: Option<u64>,
+}
+
+/// Vault balances rebuilt from its ledger
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Balances {
+    pub total: i128,
+    pub locked: i128,
+    pub available: i128,
+    pub sub_account: i128,
+}
+
+impl Balances {
+    pub fn apply(&mut self, delta: &Delta) {
+        self.total += delta.total;
+        self.locked += delta.locked;
+        self.available += delta.available;
+        self.sub_account += delta.sub_account;
+    }
+}
+
+fn entry(vault: Pubkey, sequence: u64, amount: u64, delta: Delta) -> LedgerEntry {
+    LedgerEntry {
+        vault,
+        sequence,
+        amount,
+        delta,
+        reported: Reported::default(),
+    }
+}
+
+/// A vault's reported total, locked and available balances
+fn balances(total: u64, locked: u64, available: u64) -> Reported {
+    Reported {
+        total: Some(total),
+        locked: Some(locked),
+        available: Some(available),
+        sub_account: None,
+    }
+}
+
+/// Free balance moving in (`sign` 1) or out (`sign` -1) of a vault
+fn free(sign: i128, amount: u64) -> Delta {
+    Delta {
+        total: sign * amount as i128,
+        available: sign * amount as i128,
+        ..Delta::default()
+    }
+}
+
+/// Available balance becoming locked (`sign` 1) or unlocked (`sign` -1)
+fn locking(sign: i128, amount: u64) -> Delta {
+    Delta {
+        locked: sign * amount as i128,
+        available: -sign * amount as i128,
+        ..Delta::default()
+    }
+}
+
+/// Vault balance moving into (`sign` 1) or out of (`sign` -1) sub-accounts
+fn allocation(sign: i128, amount: u64) -> Delta {
+    Delta {
+        sub_account: sign * amount as i128,
+        ..free(-sign, amount)
+    }
+}
+
+/// Ledger entries for `event`, one per vault whose sequence it advances; empty
+/// for admin and portfolio events
+pub fn entries(event: &VaultEvent) -> Vec<LedgerEntry> {
+    match event {
+        VaultEvent::VaultInitialized(e) => vec![entry(e.vault, e.sequence, 0, Delta::default())],
+        VaultEvent::VaultClosed(e) => vec![entry(e.vault, e.sequence, 0, Delta::default())],
+        VaultEvent::DepositEvent(e) => vec![LedgerEntry {
+            reported: balances(e.new_balance, e.locked_balance, e.available_balance),
+            ..entry(e.vault, e.sequence, e.amount, free(1, e.amount))
+        }],
+        VaultEvent::WithdrawEvent(e) => vec![LedgerEntry {
+            reported: balances(e.new_balance, e.locked_balance, e.available_balance),
+            ..entry(e.vault, e.sequence, e.amount, free(-1, e.amount))
+        }],
+        VaultEvent::LockEvent(e) => vec![LedgerEntry {
+            reported: balances(e.total_balance, e.locked_balance, e.available_balance),
+            ..entry(e.vault, e.sequence, e.amount, locking(1, e.amount))
+        }],
+        VaultEvent::UnlockEvent(e) => vec![LedgerEntry {
+            reported: balances(e.total_balance, e.locked_balance, e.available_balance),
+            ..entry(e.vault, e.sequence, e.amount, locking(-1, e.amount))
+        }],
+        VaultEvent::TransferEvent(e) => vec![
+            LedgerEntry {
+                reported: balances(
+                    e.from_total_balance,
+                    e.from_locked_balance,
+                    e.from_available_balance,
+                ),
+                ..entry(e.from_vault, e.from_sequence, e.amount, free(-1, e.amount))
+            },
+            LedgerEntry {
+                reported: balances(e.to_total_balance, e.to_locked_balance, e.to_available_balance),
+                ..entry(e.to_vault, e.to_sequence, e.amount, free(1, e.amount))
+            },
+        ],
+        VaultEvent::SubAccountAllocated(e) => vec![LedgerEntry {
+            reported: Reported {
+                sub_account: Some(e.vault_sub_account_balance),
+                ..balances(e.vault_total_balance, e.vault_locked_balance, e.vault_available_balance)
+            },
+            ..entry(e.vault, e.sequence, e.amount, allocation(1, e.amount))
+        }],
+        VaultEvent::SubAccountReleased(e) => vec![LedgerEntry {
+            reported: Reported {
+                sub_account: Some(e.vault_sub_account_balance),
+                ..balances(e.vault_total_balance, e.vault_locked_balance, e.vault_available_balance)
+            },
+            ..entry(e.vault, e.sequence, e.amount, allocation(-1, e.amount))
+        }],
+        VaultEvent::SubAccountTransferEvent(e) => vec![
+            LedgerEntry {
+                reported: Reported {
+                    sub_account: Some(e.from_vault_sub_account_balance),
+                    ..Reported::default()
+                },
+                ..entry(
+                    e.from_vault,
+                    e.from_sequence,
+                    e.amount,
+                    Delta {
+                        sub_account: -(e.amount as i128),
+                        ..Delta::default()
+                    },
+                )
+            },
+            LedgerEntry {
+                reported: balances(e.to_total_balance, e.to_locked_balance, e.to_available_balance),
+                ..entry(e.to_vault, e.to_sequence, e.amount, free(1, e.amount))
+            },
+        ],
+        // Sub-account bookkeeping leaves the vault's own balances alone but
+        // still advances its sequence
+        VaultEvent::SubAccountCreated(e) => vec![entry(e.vault, e.sequence, 0, Delta::default())],
+        VaultEvent::SubAccountClosed(e) => vec![entry(e.vault, e.sequence, 0, Delta::default())],
+        VaultEvent::SubAccountMoved(e) => {
+            vec![entry(e.vault, e.sequence, e.amount, Delta::default())]
+        }
+        VaultEvent::SubAccountLockEvent(e) => {
+            vec![entry(e.vault, e.sequence, e.amount, Delta::default())]
+        }
+        VaultEvent::SubAccountUnlockEvent(e) => {
+            vec![entry(e.vault, e.sequence, e.amount, Delta::default())]
+        }
+        VaultEvent::VaultMigrated(e) => vec![LedgerEntry {
+            reported: balances(e.total_balance, e.locked_balance, e.available_balance),
+            ..entry(e.vault, e.sequence, 0, Delta::default())
+        }],
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,30 @@
+//! Event indexer for the collateral vault program.
+//!
+//! - [`transaction`]: extracting vault events from `getTransaction` JSON
+//! - [`ledger`]: the balance effect of each event on the vaults it touches
+//! - [`store`]: the SQLite ledger, per-vault history and balance replay
+//!
+//! Transactions are read in the RPC's `json` encoding, so recorded responses
+//! and live `getTransaction` results go through the same path.
+
+pub mod ledger;
+pub mod store;
+pub mod transaction;
+
+pub use ledger::{Balances, LedgerEntry};
+pub use store::{Gap, HistoryEntry, Mismatch, Replay, Store};
+pub use transaction::{parse_transaction, IndexedTransaction};
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("invalid transaction: {0}")]
+    InvalidTransaction(&'static str),
+    #[error("amount {0} does not fit the ledger")]
+    AmountOutOfRange(u64),
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, IndexerError>;
@@ -0,0 +1,169 @@
+//! `vault-indexer`: index collateral vault events into a SQLite ledger.
+//!
+//! Transactions come either from recorded `getTransaction` responses
+//! (`vault-indexer ingest`) or from an RPC node (`vault-indexer sync`);
+//! `history` and `replay` then query the ledger per vault.
+
+use std::{error::Error, fs, path::PathBuf};
+
+use anchor_lang::prelude::Pubkey;
+use clap::{Parser, Subcommand};
+use collateral_vault_client::ID;
+use serde_json::Value;
+use solana_commitment_config::CommitmentConfig;
+use solana_rpc_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_rpc_client_api::config::RpcTransactionConfig;
+use solana_signature::Signature;
+use solana_transaction_status_client_types::UiTransactionEncoding;
+use vault_indexer::{parse_transaction, Store};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(name = "vault-indexer", version, about = "Index collateral vault events into a SQLite ledger")]
+struct Cli {
+    /// SQLite database file, created if missing
+    #[arg(long, global = true, default_value = "vault-index.db")]
+    db: PathBuf,
+    
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Ingest recorded getTransaction responses (json encoding), one response or an array per file
+    Ingest { files: Vec<PathBuf> },
+    /// Fetch and ingest every program transaction since the last indexed one
+    Sync {
+        /// RPC endpoint
+        #[arg(long, short = 'u', default_value = "http://127.0.0.1:8899")]
+        url: String,
+    },
+    /// Print every indexed event touching a vault with its running balances
+    History { vault: Pubkey },
+    /// Replay a vault's balances and check them against the balances and sequence numbers its events reported
+    Replay { vault: Pubkey },
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> CliResult<()> {
+    let mut store = Store::open(&cli.db)?;
+    
+    match cli.command {
+        Command::Ingest { files } => {
+            let mut responses = Vec::new();
+            for file in files {
+                match serde_json::from_str(&fs::read_to_string(&file)?)? {
+                    Value::Array(values) => responses.extend(values),
+                    value => responses.push(value),
+                }
+            }
+            ingest(&mut store, &responses)
+        }
+        Command::Sync { url } => sync(&mut store, &url),
+        Command::History { vault } => {
+            for entry in store.history(&vault)? {
+                println!(
+                    "{:>10} {:>6} {:<24} {:>12}  total {:>12}  locked {:>12}  available {:>12}  sub-accounts {:>12}  {}",
+                    entry.slot,
+                    entry.sequence,
+                    entry.event,
+                    entry.amount,
+                    entry.balances.total,
+                    entry.balances.locked,
+                    entry.balances.available,
+                    entry.balances.sub_account,
+                    entry.signature,
+                );
+            }
+            Ok(())
+        }
+        Command::Replay { vault } => {
+            let replay = store.replay(&vault)?;
+            println!("Events:           {}", replay.events);
+            println!("Total:            {}", replay.balances.total);
+            println!("Locked:           {}", replay.balances.locked);
+            println!("Available:        {}", replay.balances.available);
+            println!("Sub-accounts:     {}", replay.balances.sub_account);
+            
+            for mismatch in &replay.mismatches {
+                println!(
+                    "Mismatch: {} reported {} {} but replay has {} ({})",
+                    mismatch.event, mismatch.field, mismatch.reported, mismatch.replayed, mismatch.signature,
+                );
+            }
+            for gap in &replay.gaps {
+                println!(
+                    "Gap: expected sequence {} but {} has {} ({})",
+                    gap.expected, gap.event, gap.sequence, gap.signature,
+                );
+            }
+            if !replay.mismatches.is_empty() {
+                return Err(format!("{} reported balances disagree with the ledger", replay.mismatches.len()).into());
+            }
+            if !replay.gaps.is_empty() {
+                return Err(format!("{} gaps in the indexed event sequence", replay.gaps.len()).into());
+            }
+            Ok(())
+        }
+    }
+}
+
+fn ingest(store: &mut Store, responses: &[Value]) -> CliResult<()> {
+    let (mut indexed, mut skipped) = (0, 0);
+    
+    for response in responses {
+        match parse_transaction(response)? {
+            Some(transaction) if store.ingest(&transaction)? => indexed += 1,
+            _ => skipped += 1,
+        }
+    }
+    
+    println!("Indexed {indexed} transactions, skipped {skipped} failed or already indexed");
+    Ok(())
+}
+
+fn sync(store: &mut Store, url: &str) -> CliResult<()> {
+    let rpc = RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed());
+    let until = store.latest_signature()?.map(|signature| signature.parse::<Signature>()).transpose()?;
+    
+    // Signatures come back newest first, a page at a time
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let page = rpc.get_signatures_for_address_with_config(
+            &ID,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: None,
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+        let Some(last) = page.last() else { break };
+        before = Some(last.signature.parse()?);
+        signatures.extend(page.into_iter().filter(|status| status.err.is_none()));
+    }
+    
+    let mut responses = Vec::with_capacity(signatures.len());
+    for status in signatures.iter().rev() {
+        let transaction = rpc.get_transaction_with_config(
+            &status.signature.parse()?,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        responses.push(serde_json::to_value(transaction)?);
+    }
+    
+    ingest(store, &responses)
+}
@@ -0,0 +1,285 @@
+//! SQLite ledger of indexed vault events.
+//!
+//! - `transactions`: one row per successful transaction, keyed by signature
+//! - `events`: every vault event, in emission order within its transaction
+//! - `vault_ledger`: each event's sequence and balance deltas per vault it touches
+//!
+//! History is ordered by slot, then by the vault's event sequence within a slot.
+
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::{
+    ledger::{self, Balances, Delta, Reported},
+    IndexedTransaction, IndexerError, Result,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    id INTEGER PRIMARY KEY,
+    signature TEXT NOT NULL UNIQUE,
+    slot INTEGER NOT NULL,
+    block_time INTEGER
+);
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY,
+    transaction_id INTEGER NOT NULL REFERENCES transactions(id),
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS vault_ledger (
+    id INTEGER PRIMARY KEY,
+    event_id INTEGER NOT NULL REFERENCES events(id),
+    vault TEXT NOT NULL,
+    sequence INTEGER NOT NULL,
+    amount INTEGER NOT NULL,
+    total_delta INTEGER NOT NULL,
+    locked_delta INTEGER NOT NULL,
+    available_delta INTEGER NOT NULL,
+    sub_account_delta INTEGER NOT NULL,
+    reported_total INTEGER,
+    reported_locked INTEGER,
+    reported_available INTEGER,
+    reported_sub_account INTEGER
+);
+CREATE INDEX IF NOT EXISTS transactions_slot ON transactions(slot, id);
+CREATE INDEX IF NOT EXISTS vault_ledger_vault ON vault_ledger(vault);
+";
+
+const HISTORY: &str = "
+SELECT t.signature, t.slot, t.block_time, e.name, l.sequence, l.amount,
+       l.total_delta, l.locked_delta, l.available_delta, l.sub_account_delta,
+       l.reported_total, l.reported_locked, l.reported_available, l.reported_sub_account
+FROM vault_ledger l
+JOIN events e ON e.id = l.event_id
+JOIN transactions t ON t.id = e.transaction_id
+WHERE l.vault = ?1
+ORDER BY t.slot, l.sequence, t.id, e.position, l.id
+";
+
+/// One event in a vault's history, with the balances replayed up to it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub event: String,
+    pub sequence: u64,
+    pub amount: u64,
+    pub delta: Delta,
+    pub reported: Reported,
+    pub balances: Balances,
+}
+
+/// A balance an event reported that the replayed ledger disagrees with
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub signature: String,
+    pub event: String,
+    pub field: &'static str,
+    pub reported: u64,
+    pub replayed: i128,
+}
+
+/// An event whose sequence doesn't follow the vault's previous indexed event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gap {
+    pub signature: String,
+    pub event: String,
+    pub expected: u64,
+    pub sequence: u64,
+}
+
+/// A vault's balances replayed from its full indexed history
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub balances: Balances,
+    pub events: usize,
+    pub mismatches: Vec<Mismatch>,
+    pub gaps: Vec<Gap>,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+    
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+    
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+    
+    /// Record a transaction and its events.
+    ///
+    /// Returns `false` without writing anything if the signature is already indexed.
+    pub fn ingest(&mut self, transaction: &IndexedTransaction) -> Result<bool> {
+        let tx = self.conn.transaction()?;
+        
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time) VALUES (?1, ?2, ?3)",
+            params![transaction.signature, to_sql(transaction.slot)?, transaction.block_time],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+        let transaction_id = tx.last_insert_rowid();
+        
+        for (position, event) in transaction.events.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO events (transaction_id, position, name) VALUES (?1, ?2, ?3)",
+                params![transaction_id, position as i64, event.name()],
+            )?;
+            let event_id = tx.last_insert_rowid();
+            
+            for entry in ledger::entries(event) {
+                let delta = |value: i128| {
+                    i64::try_from(value).map_err(|_| IndexerError::AmountOutOfRange(entry.amount))
+                };
+                let reported = |value: Option<u64>| value.map(to_sql).transpose();
+                tx.execute(
+                    "INSERT INTO vault_ledger (
+                        event_id, vault, sequence, amount,
+                        total_delta, locked_delta, available_delta, sub_account_delta,
+                        reported_total, reported_locked, reported_available, reported_sub_account
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                    params![
+                        event_id,
+                        entry.vault.to_string(),
+                        to_sql(entry.sequence)?,
+                        to_sql(entry.amount)?,
+                        delta(entry.delta.total)?,
+                        delta(entry.delta.locked)?,
+                        delta(entry.delta.available)?,
+                        delta(entry.delta.sub_account)?,
+                        reported(entry.reported.total)?,
+                        reported(entry.reported.locked)?,
+                        reported(entry.reported.available)?,
+                        reported(entry.reported.sub_account)?,
+                    ],
+                )?;
+            }
+        }
+        
+        tx.commit()?;
+        Ok(true)
+    }
+    
+    /// Signature of the most recent indexed transaction
+    pub fn latest_signature(&self) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT signature FROM transactions ORDER BY slot DESC, id DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+    
+    /// Every indexed event touching `vault`, oldest first
+    pub fn history(&self, vault: &Pubkey) -> Result<Vec<HistoryEntry>> {
+        let mut statement = self.conn.prepare(HISTORY)?;
+        let rows = statement.query_map([vault.to_string()], history_row)?;
+        
+        let mut balances = Balances::default();
+        let mut history = Vec::new();
+        for row in rows {
+            let mut entry = row?;
+            balances.apply(&entry.delta);
+            entry.balances = balances;
+            history.push(entry);
+        }
+        
+        Ok(history)
+    }
+    
+    /// Replay `vault`'s balances, checking them against every balance its events
+    /// reported and checking that its event sequence has no holes.
+    ///
+    /// Sequences start at 1 and restart when a closed vault is reopened.
+    pub fn replay(&self, vault: &Pubkey) -> Result<Replay> {
+        let history = self.history(vault)?;
+        let mut mismatches = Vec::new();
+        let mut gaps = Vec::new();
+        let mut expected = 1;
+        
+        for entry in &history {
+            if entry.sequence != expected {
+                gaps.push(Gap {
+                    signature: entry.signature.clone(),
+                    event: entry.event.clone(),
+                    expected,
+                    sequence: entry.sequence,
+                });
+            }
+            expected = if entry.event == "VaultClosed" { 1 } else { entry.sequence + 1 };
+            
+            let checks = [
+                ("total", entry.reported.total, entry.balances.total),
+                ("locked", entry.reported.locked, entry.balances.locked),
+                ("available", entry.reported.available, entry.balances.available),
+                ("sub_account", entry.reported.sub_account, entry.balances.sub_account),
+            ];
+            for (field, reported, replayed) in checks {
+                match reported {
+                    Some(reported) if reported as i128 != replayed => mismatches.push(Mismatch {
+                        signature: entry.signature.clone(),
+                        event: entry.event.clone(),
+                        field,
+                        reported,
+                        replayed,
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        
+        Ok(Replay {
+            balances: history.last().map(|entry| entry.balances).unwrap_or_default(),
+            events: history.len(),
+            mismatches,
+            gaps,
+        })
+    }
+}
+
+/// SQLite integers are signed; amounts past `i64::MAX` are rejected rather than wrapped
+fn to_sql(value: u64) -> Result<i64> {
+    i64::try_from(value).map_err(|_| IndexerError::AmountOutOfRange(value))
+}
+
+fn history_row(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    let reported = |index| row.get::<_, Option<i64>>(index).map(|value| value.map(|v| v as u64));
+    
+    Ok(HistoryEntry {
+        signature: row.get(0)?,
+        slot: row.get::<_, i64>(1)? as u64,
+        block_time: row.get(2)?,
+        event: row.get(3)?,
+        sequence: row.get::<_, i64>(4)? as u64,
+        amount: row.get::<_, i64>(5)? as u64,
+        delta: Delta {
+            total: row.get::<_, i64>(6)? as i128,
+            locked: row.get::<_, i64>(7)? as i128,
+            available: row.get::<_, i64>(8)? as i128,
+            sub_account: row.get::<_, i64>(9)? as i128,
+        },
+        reported: Reported {
+            total: reported(10)?,
+            locked: reported(11)?,
+            available: reported(12)?,
+            sub_account: reported(13)?,
+        },
+        balances: Balances::default(),
+    })
+}
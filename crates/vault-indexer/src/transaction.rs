@@ -0,0 +1,93 @@
+//! Extracting vault events from `getTransaction` responses.
+
+use collateral_vault_client::{decode_event_cpi, events_from_logs, VaultEvent, ID};
+use serde_json::Value;
+
+use crate::{IndexerError, Result};
+
+/// A successful transaction and the vault events it emitted
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub events: Vec<VaultEvent>,
+}
+
+/// Parse a `getTransaction` response in `json` encoding.
+///
+/// Returns `None` for failed transactions, whose events were rolled back.
+/// Events are taken from `emit_cpi!` inner instructions when there are any,
+/// and from the logs otherwise.
+pub fn parse_transaction(response: &Value) -> Result<Option<IndexedTransaction>> {
+    let meta = &response["meta"];
+    if meta.is_null() {
+        return Err(IndexerError::InvalidTransaction("missing meta"));
+    }
+    if !meta["err"].is_null() {
+        return Ok(None);
+    }
+    
+    let signature = response["transaction"]["signatures"][0]
+        .as_str()
+        .ok_or(IndexerError::InvalidTransaction("missing signature"))?;
+    let slot = response["slot"]
+        .as_u64()
+        .ok_or(IndexerError::InvalidTransaction("missing slot"))?;
+    
+    let mut events = cpi_events(response)?;
+    if events.is_empty() {
+        let logs: Vec<&str> = meta["logMessages"]
+            .as_array()
+            .ok_or(IndexerError::InvalidTransaction("missing logMessages"))?
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        events = events_from_logs(&logs);
+    }
+    
+    Ok(Some(IndexedTransaction {
+        signature: signature.to_string(),
+        slot,
+        block_time: response["blockTime"].as_i64(),
+        events,
+    }))
+}
+
+/// Events from inner instructions invoking the vault program with event CPI data
+fn cpi_events(response: &Value) -> Result<Vec<VaultEvent>> {
+    let program = ID.to_string();
+    let meta = &response["meta"];
+    
+    // Lookup-table addresses follow the static keys, writable first
+    let account_keys: Vec<&str> = [
+        &response["transaction"]["message"]["accountKeys"],
+        &meta["loadedAddresses"]["writable"],
+        &meta["loadedAddresses"]["readonly"],
+    ]
+    .into_iter()
+    .filter_map(Value::as_array)
+    .flatten()
+    .filter_map(Value::as_str)
+    .collect();
+    
+    let mut events = Vec::new();
+    let inner = meta["innerInstructions"].as_array().into_iter().flatten();
+    for instruction in inner.flat_map(|set| set["instructions"].as_array().into_iter().flatten()) {
+        let program_id = instruction["programIdIndex"]
+            .as_u64()
+            .and_then(|index| account_keys.get(index as usize));
+        if program_id != Some(&program.as_str()) {
+            continue;
+        }
+        
+        let data = instruction["data"]
+            .as_str()
+            .ok_or(IndexerError::InvalidTransaction("missing instruction data"))?;
+        let data = bs58::decode(data)
+            .into_vec()
+            .map_err(|_| IndexerError::InvalidTransaction("instruction data is not base58"))?;
+        events.extend(decode_event_cpi(&data));
+    }
+    
+    Ok(events)
+}
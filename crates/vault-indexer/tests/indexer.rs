@@ -0,0 +1,214 @@
+//! Indexing recorded `getTransaction` responses, no RPC involved.
+//!
+//! Fixtures replay two vaults: A deposits 1000, an integrator locks 300 of it
+//! and transfers 200 to B through CPI, B withdraws 100 in the same slot, a
+//! withdrawal from A fails, and A unlocks 100 with the event delivered by
+//! `emit_cpi!`.
+
+use std::{fs, path::Path};
+
+use anchor_lang::prelude::Pubkey;
+use serde_json::Value;
+use vault_indexer::{ledger::Balances, parse_transaction, Store};
+
+const VAULT_A: &str = "HSdBf7q6bP1QygfoFxxer2DUZf2Dr3bdUgJ4xFjZtAcr";
+const VAULT_B: &str = "6JD3ogBqqm8bkCDsSVjwnHDD22aL6zbRz5ZXSKJJJD9S";
+
+const FIXTURES: [&str; 6] = [
+    "01_initialize_and_deposit",
+    "02_second_vault",
+    "03_cpi_lock_and_transfer",
+    "04_withdraw",
+    "05_failed_withdraw",
+    "06_event_cpi_unlock",
+];
+
+fn fixture(name: &str) -> Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(format!("{name}.json"));
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn vault(address: &str) -> Pubkey {
+    address.parse().unwrap()
+}
+
+/// Ingest fixtures in order, returning how many were newly indexed
+fn ingest(store: &mut Store, names: &[&str]) -> usize {
+    names
+        .iter()
+        .filter_map(|name| parse_transaction(&fixture(name)).unwrap())
+        .filter(|transaction| store.ingest(transaction).unwrap())
+        .count()
+}
+
+fn indexed_store() -> Store {
+    let mut store = Store::open_in_memory().unwrap();
+    assert_eq!(ingest(&mut store, &FIXTURES), 5);
+    store
+}
+
+#[test]
+fn events_are_read_from_logs_and_event_cpis() {
+    let names = |name| -> Vec<&str> {
+        let transaction = parse_transaction(&fixture(name)).unwrap().unwrap();
+        transaction.events.iter().map(|event| event.name()).collect()
+    };
+
+    assert_eq!(names("01_initialize_and_deposit"), ["VaultInitialized", "DepositEvent"]);
+    assert_eq!(names("03_cpi_lock_and_transfer"), ["LockEvent", "TransferEvent"]);
+    assert_eq!(names("06_event_cpi_unlock"), ["UnlockEvent"]);
+}
+
+#[test]
+fn failed_transactions_are_skipped() {
+    assert!(parse_transaction(&fixture("05_failed_withdraw")).unwrap().is_none());
+}
+
+#[test]
+fn history_is_ordered_with_running_balances() {
+    let store = indexed_store();
+    let history = store.history(&vault(VAULT_A)).unwrap();
+
+    let events: Vec<&str> = history.iter().map(|entry| entry.event.as_str()).collect();
+    assert_eq!(
+        events,
+        ["VaultInitialized", "DepositEvent", "LockEvent", "TransferEvent", "UnlockEvent"]
+    );
+
+    let totals: Vec<i128> = history.iter().map(|entry| entry.balances.total).collect();
+    assert_eq!(totals, [0, 1000, 1000, 800, 800]);
+
+    let locked: Vec<i128> = history.iter().map(|entry| entry.balances.locked).collect();
+    assert_eq!(locked, [0, 0, 300, 300, 200]);
+
+    let sequences: Vec<u64> = history.iter().map(|entry| entry.sequence).collect();
+    assert_eq!(sequences, [1, 2, 3, 4, 5]);
+
+    // Both sides of the transfer carry the same transaction and their own post-state
+    let transfer = &history[3];
+    assert_eq!(transfer.reported.total, Some(800));
+    assert_eq!(transfer.reported.locked, Some(300));
+    assert_eq!(transfer.reported.available, Some(500));
+    let b_history = store.history(&vault(VAULT_B)).unwrap();
+    let b_transfer = b_history.iter().find(|entry| entry.signature == transfer.signature).unwrap();
+    assert_eq!(b_transfer.reported.total, Some(700));
+    assert_eq!(b_transfer.reported.available, Some(700));
+}
+
+#[test]
+fn replay_matches_reported_balances() {
+    let store = indexed_store();
+
+    let a = store.replay(&vault(VAULT_A)).unwrap();
+    assert_eq!(a.events, 5);
+    assert_eq!(
+        a.balances,
+        Balances {
+            total: 800,
+            locked: 200,
+            available: 600,
+            sub_account: 0,
+        }
+    );
+    assert!(a.mismatches.is_empty());
+    assert!(a.gaps.is_empty());
+
+    let b = store.replay(&vault(VAULT_B)).unwrap();
+    assert_eq!(b.balances.total, 600);
+    assert_eq!(b.balances.available, 600);
+    assert!(b.mismatches.is_empty());
+    assert!(b.gaps.is_empty());
+}
+
+#[test]
+fn other_programs_data_is_not_indexed() {
+    // The integrator logs its own DepositEvent-shaped data for vault A before the CPI
+    let store = indexed_store();
+    let deposits = store
+        .history(&vault(VAULT_A))
+        .unwrap()
+        .into_iter()
+        .filter(|entry| entry.event == "DepositEvent")
+        .count();
+
+    assert_eq!(deposits, 1);
+}
+
+#[test]
+fn ingest_is_idempotent() {
+    let mut store = indexed_store();
+
+    assert_eq!(ingest(&mut store, &FIXTURES), 0);
+    assert_eq!(store.history(&vault(VAULT_A)).unwrap().len(), 5);
+    assert_eq!(store.replay(&vault(VAULT_B)).unwrap().balances.total, 600);
+}
+
+#[test]
+fn out_of_order_ingest_replays_by_slot_and_sequence() {
+    let mut store = Store::open_in_memory().unwrap();
+    let mut reversed = FIXTURES;
+    reversed.reverse();
+    ingest(&mut store, &reversed);
+
+    let replay = store.replay(&vault(VAULT_A)).unwrap();
+    assert_eq!(replay.balances.total, 800);
+    assert!(replay.mismatches.is_empty());
+
+    // B's transfer in and withdrawal share a slot but were ingested withdrawal first
+    let history = store.history(&vault(VAULT_B)).unwrap();
+    let events: Vec<&str> = history.iter().map(|entry| entry.event.as_str()).collect();
+    assert_eq!(events, ["VaultInitialized", "DepositEvent", "TransferEvent", "WithdrawEvent"]);
+    let replay = store.replay(&vault(VAULT_B)).unwrap();
+    assert!(replay.mismatches.is_empty());
+    assert!(replay.gaps.is_empty());
+}
+
+#[test]
+fn replay_flags_gaps_in_history() {
+    // Indexing B from its withdrawal onwards misses the deposit and transfer in
+    let mut store = Store::open_in_memory().unwrap();
+    ingest(&mut store, &["04_withdraw"]);
+
+    let replay = store.replay(&vault(VAULT_B)).unwrap();
+    assert_eq!(replay.balances.total, -100);
+
+    let fields: Vec<&str> = replay.mismatches.iter().map(|mismatch| mismatch.field).collect();
+    assert_eq!(fields, ["total", "available"]);
+    for mismatch in &replay.mismatches {
+        assert_eq!(mismatch.event, "WithdrawEvent");
+        assert_eq!(mismatch.reported, 600);
+        assert_eq!(mismatch.replayed, -100);
+    }
+
+    assert_eq!(replay.gaps.len(), 1);
+    assert_eq!(replay.gaps[0].expected, 1);
+    assert_eq!(replay.gaps[0].sequence, 4);
+}
+
+#[test]
+fn replay_flags_missing_sequence_numbers() {
+    // Skipping the CPI transaction loses A's lock and transfer, sequences 3 and 4
+    let mut store = Store::open_in_memory().unwrap();
+    ingest(&mut store, &["01_initialize_and_deposit", "06_event_cpi_unlock"]);
+
+    let replay = store.replay(&vault(VAULT_A)).unwrap();
+    assert_eq!(replay.gaps.len(), 1);
+
+    let gap = &replay.gaps[0];
+    assert_eq!(gap.event, "UnlockEvent");
+    assert_eq!(gap.expected, 3);
+    assert_eq!(gap.sequence, 5);
+
+    // The unlock's post-state also exposes the missed transfer out
+    let total = replay.mismatches.iter().find(|mismatch| mismatch.field == "total").unwrap();
+    assert_eq!(total.reported, 800);
+    assert_eq!(total.replayed, 1000);
+}
+
+#[test]
+fn latest_signature_follows_slot_order() {
+    let store = indexed_store();
+    let latest = parse_transaction(&fixture("06_event_cpi_unlock")).unwrap().unwrap();
+
+    assert_eq!(store.latest_signature().unwrap(), Some(latest.signature));
+}
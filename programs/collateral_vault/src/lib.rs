@@ -1,11 +1,38 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
     associated_token::AssociatedToken,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            default_account_state::DefaultAccountState,
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        },
+        state::AccountState,
+    },
 };
 
 declare_id!("G6TF8EdpP7gKwfPmNEhMLU7E34X5Fr3ujpAMdCzwHz8R");
 
+/// Emits an event from an instruction handler.
+///
+/// With the `event-cpi` feature the event is sent as a self-CPI signed by the
+/// event authority, where log truncation can't drop it; the instruction's
+/// context then carries `event_authority` and `program` via `#[event_cpi]`.
+/// Otherwise it is written to the program logs with `emit!`.
+macro_rules! emit_event {
+    ($ctx:ident, $event:expr) => {{
+        #[cfg(feature = "event-cpi")]
+        emit_cpi_event(
+            &$ctx.accounts.event_authority,
+            $ctx.bumps.event_authority,
+            &$event,
+        )?;
+        #[cfg(not(feature = "event-cpi"))]
+        emit!($event);
+    }};
+}
+
 #[program]
 pub mod collateral_vault {
     use super::*;
@@ -24,11 +51,15 @@ pub mod collateral_vault {
         vault_authority.authorized_programs = authorized_programs.clone();
         vault_authority.bump = ctx.bumps.vault_authority;
         vault_authority.admin = ctx.accounts.admin.key();
+        vault_authority.denied_extensions = DEFAULT_DENIED_EXTENSIONS;
+        vault_authority.sequence = 0;
+        vault_authority.version = AUTHORITY_VERSION;
         
-        emit!(AuthorityInitialized {
+        emit_event!(ctx, AuthorityInitialized {
             admin: ctx.accounts.admin.key(),
             authority: vault_authority.key(),
             authorized_programs,
+            sequence: next_sequence(&mut vault_authority.sequence)?,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -37,6 +68,12 @@ pub mod collateral_vault {
 
     /// 2. Initialize user vault
     pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
+        check_mint_allowed(
+            &ctx.accounts.token_mint,
+            &ctx.accounts.mint_config,
+            ctx.accounts.vault_authority.denied_extensions,
+        )?;
+        
         let vault = &mut ctx.accounts.vault;
         let clock = Clock::get()?;
         
@@ -50,11 +87,25 @@ pub mod collateral_vault {
         vault.created_at = clock.unix_timestamp;
         vault.bump = ctx.bumps.vault;
         vault.token_mint = ctx.accounts.token_mint.key();
+        vault.pooled = false;
+        vault.sub_account_balance = 0;
+        vault.total_transferred_in = 0;
+        vault.total_transferred_out = 0;
+        vault.sequence = 0;
+        vault.version = VAULT_VERSION;
+        vault.partial_history = false;
+        vault.total_seized = 0;
+        vault.total_fees_paid = 0;
+        vault.unattributed_locked = 0;
+        vault.reserved = [0; 15];
+        
+        ctx.accounts.mint_stats.record_vault_opened()?;
         
-        emit!(VaultInitialized {
+        emit_event!(ctx, VaultInitialized {
             user: ctx.accounts.user.key(),
             vault: vault.key(),
             token_mint: vault.token_mint,
+            sequence: next_sequence(&mut vault.sequence)?,
             timestamp: clock.unix_timestamp,
         });
         
@@ -62,8 +113,12 @@ pub mod collateral_vault {
     }
 
     /// 3. Deposit collateral into vault
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    pub fn deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
+        amount: u64,
+    ) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
+        ctx.accounts.mint_config.check_deposit(amount)?;
         
         // Store vault key before mutable borrow
         let vault_key = ctx.accounts.vault.key();
@@ -79,27 +134,33 @@ pub mod collateral_vault {
         vault.total_deposited = vault.total_deposited
             .checked_add(amount)
             .ok_or(VaultError::Overflow)?;
+        let sequence = next_sequence(&mut vault.sequence)?;
         
         let new_balance = vault.total_balance;
+        ctx.accounts.mint_config.check_vault_balance(new_balance)?;
+        ctx.accounts.mint_stats.record_deposit(amount, &ctx.accounts.mint_config)?;
         
         // Transfer tokens from user to vault using Token-2022
-        let cpi_accounts = TransferChecked {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            mint: ctx.accounts.token_mint.to_account_info(),
-            to: ctx.accounts.vault_token_account.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
-        };
-        
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        
-        transfer_checked(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            &[],
+        )?;
         
-        emit!(DepositEvent {
+        emit_event!(ctx, DepositEvent {
             user: ctx.accounts.user.key(),
             vault: vault_key,
+            token_mint: ctx.accounts.token_mint.key(),
             amount,
             new_balance,
+            locked_balance: ctx.accounts.vault.locked_balance,
+            available_balance: ctx.accounts.vault.available_balance,
+            sequence,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -108,38 +169,32 @@ pub mod collateral_vault {
 
     /// 4. Lock collateral (called by authorized programs via CPI)
     pub fn lock_collateral(ctx: Context<LockCollateral>, amount: u64) -> Result<()> {
-        require!(amount > 0, VaultError::InvalidAmount);
-        
-        let vault = &ctx.accounts.vault;
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
         
-        require!(
-            vault.available_balance >= amount,
-            VaultError::InsufficientAvailableBalance
+        ctx.accounts.integrator_position.open(
+            ctx.accounts.signer.key(),
+            ctx.accounts.vault.key(),
+            ctx.bumps.integrator_position,
         );
-        
-        // Update vault state
-        let vault = &mut ctx.accounts.vault;
-        vault.available_balance = vault.available_balance
-            .checked_sub(amount)
-            .ok_or(VaultError::Underflow)?;
-        vault.locked_balance = vault.locked_balance
-            .checked_add(amount)
-            .ok_or(VaultError::Overflow)?;
-        
-        emit!(LockEvent {
-            vault: vault.key(),
+        let event = lock_vault_collateral(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.mint_stats,
+            &mut ctx.accounts.integrator_stats,
+            &mut ctx.accounts.integrator_position,
+            ctx.accounts.signer.key(),
             amount,
-            locked_balance: vault.locked_balance,
-            available_balance: vault.available_balance,
-            caller: ctx.accounts.signer.key(),
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+            None,
+            None,
+        )?;
+        emit_event!(ctx, event);
         
         Ok(())
     }
 
     /// 5. Unlock collateral (called by authorized programs via CPI)
     pub fn unlock_collateral(ctx: Context<UnlockCollateral>, amount: u64) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
         require!(amount > 0, VaultError::InvalidAmount);
         
         require!(
@@ -155,13 +210,27 @@ pub mod collateral_vault {
         vault.available_balance = vault.available_balance
             .checked_add(amount)
             .ok_or(VaultError::Overflow)?;
+        ctx.accounts.mint_stats.record_unlock(amount);
+        
+        // Charge the caller's own locks first; only locks carried over from
+        // before positions existed may cover the rest
+        let position = &mut ctx.accounts.integrator_position;
+        position.open(ctx.accounts.signer.key(), vault.key(), ctx.bumps.integrator_position);
+        let attributed = amount.min(position.locked(&vault.token_mint));
+        vault.unattributed_locked = vault.unattributed_locked
+            .checked_sub(amount - attributed)
+            .ok_or(VaultError::LockNotHeld)?;
+        ctx.accounts.integrator_stats.record_unlock(position, &vault.token_mint, attributed)?;
         
-        emit!(UnlockEvent {
+        emit_event!(ctx, UnlockEvent {
             vault: vault.key(),
+            token_mint: vault.token_mint,
             amount,
+            total_balance: vault.total_balance,
             locked_balance: vault.locked_balance,
             available_balance: vault.available_balance,
             caller: ctx.accounts.signer.key(),
+            sequence: next_sequence(&mut vault.sequence)?,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -169,7 +238,10 @@ pub mod collateral_vault {
     }
 
     /// 6. Withdraw collateral from vault
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+    pub fn withdraw<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>,
+        amount: u64,
+    ) -> Result<()> {
         require!(amount > 0, VaultError::InvalidAmount);
         
         require!(
@@ -193,8 +265,10 @@ pub mod collateral_vault {
         vault.total_withdrawn = vault.total_withdrawn
             .checked_add(amount)
             .ok_or(VaultError::Overflow)?;
+        let sequence = next_sequence(&mut vault.sequence)?;
         
         let new_balance = vault.total_balance;
+        ctx.accounts.mint_stats.record_withdrawal(amount)?;
         
         // PDA seeds for signing
         let seeds = &[
@@ -205,23 +279,26 @@ pub mod collateral_vault {
         let signer_seeds = &[&seeds[..]];
         
         // Transfer with PDA signer using Token-2022
-        let cpi_accounts = TransferChecked {
-            from: ctx.accounts.vault_token_account.to_account_info(),
-            mint: ctx.accounts.token_mint.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.vault.to_account_info(),
-        };
-        
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        
-        transfer_checked(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer_seeds,
+        )?;
         
-        emit!(WithdrawEvent {
+        emit_event!(ctx, WithdrawEvent {
             user: ctx.accounts.user.key(),
             vault: vault_key,
+            token_mint: ctx.accounts.token_mint.key(),
             amount,
             new_balance,
+            locked_balance: ctx.accounts.vault.locked_balance,
+            available_balance: ctx.accounts.vault.available_balance,
+            sequence,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -229,10 +306,12 @@ pub mod collateral_vault {
     }
 
     /// 7. Transfer collateral between vaults (for settlements/liquidations)
-    pub fn transfer_collateral(
-        ctx: Context<TransferCollateral>,
+    pub fn transfer_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TransferCollateral<'info>>,
         amount: u64,
     ) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
         require!(amount > 0, VaultError::InvalidAmount);
         
         // Check source vault has sufficient available balance
@@ -255,6 +334,10 @@ pub mod collateral_vault {
         from_vault.available_balance = from_vault.available_balance
             .checked_sub(amount)
             .ok_or(VaultError::Underflow)?;
+        from_vault.total_transferred_out = from_vault.total_transferred_out
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        let from_sequence = next_sequence(&mut from_vault.sequence)?;
         
         // Update destination vault
         let to_vault = &mut ctx.accounts.to_vault;
@@ -264,6 +347,12 @@ pub mod collateral_vault {
         to_vault.available_balance = to_vault.available_balance
             .checked_add(amount)
             .ok_or(VaultError::Overflow)?;
+        to_vault.total_transferred_in = to_vault.total_transferred_in
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        let to_sequence = next_sequence(&mut to_vault.sequence)?;
+        
+        ctx.accounts.integrator_stats.record_transfer(amount)?;
         
         // PDA seeds for signing
         let seeds = &[
@@ -274,23 +363,31 @@ pub mod collateral_vault {
         let signer_seeds = &[&seeds[..]];
         
         // Transfer tokens between vault token accounts
-        let cpi_accounts = TransferChecked {
-            from: ctx.accounts.from_vault_token_account.to_account_info(),
-            mint: ctx.accounts.token_mint.to_account_info(),
-            to: ctx.accounts.to_vault_token_account.to_account_info(),
-            authority: ctx.accounts.from_vault.to_account_info(),
-        };
-        
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        
-        transfer_checked(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.from_vault_token_account.to_account_info(),
+            ctx.accounts.to_vault_token_account.to_account_info(),
+            ctx.accounts.from_vault.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer_seeds,
+        )?;
         
-        emit!(TransferEvent {
+        emit_event!(ctx, TransferEvent {
             from_vault: from_vault_key,
             to_vault: to_vault_key,
+            token_mint: ctx.accounts.from_vault.token_mint,
             amount,
+            from_total_balance: ctx.accounts.from_vault.total_balance,
+            from_locked_balance: ctx.accounts.from_vault.locked_balance,
+            from_available_balance: ctx.accounts.from_vault.available_balance,
+            to_total_balance: ctx.accounts.to_vault.total_balance,
+            to_locked_balance: ctx.accounts.to_vault.locked_balance,
+            to_available_balance: ctx.accounts.to_vault.available_balance,
             caller: ctx.accounts.signer.key(),
+            from_sequence,
+            to_sequence,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -299,7 +396,7 @@ pub mod collateral_vault {
 
     /// 8. Close vault (only when balance is zero and no locked collateral)
     pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
-        let vault = &ctx.accounts.vault;
+        let vault = &mut ctx.accounts.vault;
         
         require!(
             vault.total_balance == 0,
@@ -311,9 +408,32 @@ pub mod collateral_vault {
             VaultError::HasLockedCollateral
         );
         
-        emit!(VaultClosed {
+        require!(
+            vault.sub_account_balance == 0,
+            VaultError::HasSubAccountBalance
+        );
+        
+        // Pooled vaults are counted by their custody pool
+        if vault.pooled {
+            let custody_pool = ctx.accounts.custody_pool
+                .as_mut()
+                .ok_or(VaultError::MissingCustodyPool)?;
+            require!(
+                custody_pool.token_mint == vault.token_mint,
+                VaultError::InvalidTokenMint
+            );
+            custody_pool.vault_count = custody_pool.vault_count
+                .checked_sub(1)
+                .ok_or(VaultError::Underflow)?;
+        }
+        
+        ctx.accounts.mint_stats.record_vault_closed();
+        
+        emit_event!(ctx, VaultClosed {
             user: ctx.accounts.user.key(),
             vault: vault.key(),
+            token_mint: vault.token_mint,
+            sequence: next_sequence(&mut vault.sequence)?,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -340,9 +460,10 @@ pub mod collateral_vault {
         
         vault_authority.authorized_programs.push(program);
         
-        emit!(ProgramAuthorized {
+        emit_event!(ctx, ProgramAuthorized {
             admin: ctx.accounts.admin.key(),
             program,
+            sequence: next_sequence(&mut vault_authority.sequence)?,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
@@ -356,204 +477,4824 @@ pub mod collateral_vault {
     ) -> Result<()> {
         let vault_authority = &mut ctx.accounts.vault_authority;
         
-        vault_authority.authorized_programs.retain(|&p| p != program);
+        let index = vault_authority.authorized_programs
+            .iter()
+            .position(|p| *p == program)
+            .ok_or(VaultError::ProgramNotAuthorized)?;
+        vault_authority.authorized_programs.remove(index);
         
-        emit!(ProgramDeauthorized {
+        emit_event!(ctx, ProgramDeauthorized {
             admin: ctx.accounts.admin.key(),
             program,
+            sequence: next_sequence(&mut vault_authority.sequence)?,
             timestamp: Clock::get()?.unix_timestamp,
         });
         
         Ok(())
     }
-}
 
-// ============ ACCOUNT STRUCTURES ============
+    /// 11. Initialize custody pool for a mint (admin only)
+    pub fn initialize_custody_pool(ctx: Context<InitializeCustodyPool>) -> Result<()> {
+        let custody_pool = &mut ctx.accounts.custody_pool;
+        
+        custody_pool.token_mint = ctx.accounts.token_mint.key();
+        custody_pool.custody_token_account = ctx.accounts.custody_token_account.key();
+        custody_pool.total_balance = 0;
+        custody_pool.vault_count = 0;
+        custody_pool.bump = ctx.bumps.custody_pool;
+        
+        emit_event!(ctx, CustodyPoolInitialized {
+            admin: ctx.accounts.admin.key(),
+            custody_pool: custody_pool.key(),
+            token_mint: custody_pool.token_mint,
+            custody_token_account: custody_pool.custody_token_account,
+            sequence: next_sequence(&mut ctx.accounts.vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
 
-#[account]
-#[derive(InitSpace)]
-pub struct CollateralVault {
-    pub owner: Pubkey,              // 32 - Vault owner
-    pub token_account: Pubkey,      // 32 - Associated token account
-    pub total_balance: u64,         // 8 - Total balance in vault
-    pub locked_balance: u64,        // 8 - Collateral locked for positions
-    pub available_balance: u64,     // 8 - Available for withdrawal (total - locked)
-    pub total_deposited: u64,       // 8 - Lifetime deposits
-    pub total_withdrawn: u64,       // 8 - Lifetime withdrawals
-    pub created_at: i64,            // 8 - Unix timestamp of creation
-    pub bump: u8,                   // 1 - PDA bump seed
-    pub token_mint: Pubkey,         // 32 - Token mint address (USDT)
-}
+    /// 12. Initialize user vault backed by the mint's custody pool
+    pub fn initialize_pooled_vault(ctx: Context<InitializePooledVault>) -> Result<()> {
+        check_mint_allowed(
+            &ctx.accounts.token_mint,
+            &ctx.accounts.mint_config,
+            ctx.accounts.vault_authority.denied_extensions,
+        )?;
+        
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+        
+        vault.owner = ctx.accounts.user.key();
+        vault.token_account = ctx.accounts.custody_pool.custody_token_account;
+        vault.total_balance = 0;
+        vault.locked_balance = 0;
+        vault.available_balance = 0;
+        vault.total_deposited = 0;
+        vault.total_withdrawn = 0;
+        vault.created_at = clock.unix_timestamp;
+        vault.bump = ctx.bumps.vault;
+        vault.token_mint = ctx.accounts.token_mint.key();
+        vault.pooled = true;
+        vault.sub_account_balance = 0;
+        vault.total_transferred_in = 0;
+        vault.total_transferred_out = 0;
+        vault.sequence = 0;
+        vault.version = VAULT_VERSION;
+        vault.partial_history = false;
+        vault.total_seized = 0;
+        vault.total_fees_paid = 0;
+        vault.unattributed_locked = 0;
+        vault.reserved = [0; 15];
+        
+        let custody_pool = &mut ctx.accounts.custody_pool;
+        custody_pool.vault_count = custody_pool.vault_count
+            .checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+        
+        ctx.accounts.mint_stats.record_vault_opened()?;
+        
+        emit_event!(ctx, VaultInitialized {
+            user: ctx.accounts.user.key(),
+            vault: vault.key(),
+            token_mint: vault.token_mint,
+            sequence: next_sequence(&mut vault.sequence)?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
 
-#[account]
-#[derive(InitSpace)]
-pub struct VaultAuthority {
-    pub admin: Pubkey,              // 32 - Authority admin
-    #[max_len(10)]
-    pub authorized_programs: Vec<Pubkey>, // 4 + (10 * 32) - Programs allowed to lock/unlock
-    pub bump: u8,                   // 1 - PDA bump seed
-}
+    /// 13. Deposit collateral into a pooled vault
+    pub fn deposit_pooled<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositPooled<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        ctx.accounts.mint_config.check_deposit(amount)?;
+        
+        let vault_key = ctx.accounts.vault.key();
+        
+        // Update vault and pool state BEFORE transfer (CEI pattern)
+        let vault = &mut ctx.accounts.vault;
+        vault.total_balance = vault.total_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        vault.available_balance = vault.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        vault.total_deposited = vault.total_deposited
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        let sequence = next_sequence(&mut vault.sequence)?;
+        
+        let new_balance = vault.total_balance;
+        ctx.accounts.mint_config.check_vault_balance(new_balance)?;
+        ctx.accounts.mint_stats.record_deposit(amount, &ctx.accounts.mint_config)?;
+        
+        let custody_pool = &mut ctx.accounts.custody_pool;
+        custody_pool.total_balance = custody_pool.total_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        
+        // Transfer tokens from user into the shared custody account
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.custody_token_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            &[],
+        )?;
+        
+        emit_event!(ctx, DepositEvent {
+            user: ctx.accounts.user.key(),
+            vault: vault_key,
+            token_mint: ctx.accounts.token_mint.key(),
+            amount,
+            new_balance,
+            locked_balance: ctx.accounts.vault.locked_balance,
+            available_balance: ctx.accounts.vault.available_balance,
+            sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
 
-// ============ CONTEXTS ============
+    /// 14. Withdraw collateral from a pooled vault (paid out of the custody pool)
+    pub fn withdraw_pooled<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawPooled<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        require!(
+            ctx.accounts.vault.available_balance >= amount,
+            VaultError::InsufficientAvailableBalance
+        );
+        
+        // Store values needed for transfer before mutable borrow
+        let token_mint_key = ctx.accounts.custody_pool.token_mint;
+        let pool_bump = ctx.accounts.custody_pool.bump;
+        let vault_key = ctx.accounts.vault.key();
+        
+        // Update vault and pool state BEFORE transfer (CEI pattern)
+        let vault = &mut ctx.accounts.vault;
+        vault.total_balance = vault.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        vault.available_balance = vault.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        vault.total_withdrawn = vault.total_withdrawn
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        let sequence = next_sequence(&mut vault.sequence)?;
+        
+        let new_balance = vault.total_balance;
+        ctx.accounts.mint_stats.record_withdrawal(amount)?;
+        
+        let custody_pool = &mut ctx.accounts.custody_pool;
+        custody_pool.total_balance = custody_pool.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        
+        // Custody pool PDA seeds for signing
+        let seeds = &[
+            b"custody_pool",
+            token_mint_key.as_ref(),
+            &[pool_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.custody_token_account.to_account_info(),
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.custody_pool.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer_seeds,
+        )?;
+        
+        emit_event!(ctx, WithdrawEvent {
+            user: ctx.accounts.user.key(),
+            vault: vault_key,
+            token_mint: ctx.accounts.token_mint.key(),
+            amount,
+            new_balance,
+            locked_balance: ctx.accounts.vault.locked_balance,
+            available_balance: ctx.accounts.vault.available_balance,
+            sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct InitializeAuthority<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = admin,
-        space = 8 + VaultAuthority::INIT_SPACE,
-        seeds = [b"vault_authority"],
-        bump
-    )]
-    pub vault_authority: Account<'info, VaultAuthority>,
-    
-    pub system_program: Program<'info, System>,
-}
+    /// 15. Transfer collateral between pooled vaults (ledger update only, no token CPI)
+    pub fn transfer_collateral_internal(
+        ctx: Context<TransferCollateralInternal>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        require!(
+            ctx.accounts.from_vault.available_balance >= amount,
+            VaultError::InsufficientAvailableBalance
+        );
+        
+        let from_vault_key = ctx.accounts.from_vault.key();
+        let to_vault_key = ctx.accounts.to_vault.key();
+        
+        // Update source vault
+        let from_vault = &mut ctx.accounts.from_vault;
+        from_vault.total_balance = from_vault.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        from_vault.available_balance = from_vault.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        from_vault.total_transferred_out = from_vault.total_transferred_out
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        let from_sequence = next_sequence(&mut from_vault.sequence)?;
+        
+        // Update destination vault
+        let to_vault = &mut ctx.accounts.to_vault;
+        to_vault.total_balance = to_vault.total_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        to_vault.available_balance = to_vault.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        to_vault.total_transferred_in = to_vault.total_transferred_in
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        let to_sequence = next_sequence(&mut to_vault.sequence)?;
+        
+        ctx.accounts.integrator_stats.record_transfer(amount)?;
+        
+        // Tokens stay in the custody account, so the pool total is unchanged
+        emit_event!(ctx, TransferEvent {
+            from_vault: from_vault_key,
+            to_vault: to_vault_key,
+            token_mint: ctx.accounts.from_vault.token_mint,
+            amount,
+            from_total_balance: ctx.accounts.from_vault.total_balance,
+            from_locked_balance: ctx.accounts.from_vault.locked_balance,
+            from_available_balance: ctx.accounts.from_vault.available_balance,
+            to_total_balance: ctx.accounts.to_vault.total_balance,
+            to_locked_balance: ctx.accounts.to_vault.locked_balance,
+            to_available_balance: ctx.accounts.to_vault.available_balance,
+            caller: ctx.accounts.signer.key(),
+            from_sequence,
+            to_sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct InitializeVault<'info> {
+    /// 16. Verify custody pool invariants (permissionless)
+    ///
+    /// Every pooled vault of the mint must be passed in `remaining_accounts`.
+    /// Checks that the sum of their balances equals the pool total, and that
+    /// the pool total equals the custody token account balance.
+    pub fn verify_custody_pool<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyCustodyPool<'info>>,
+    ) -> Result<()> {
+        let custody_pool = &ctx.accounts.custody_pool;
+        
+        require!(
+            ctx.remaining_accounts.len() == custody_pool.vault_count as usize,
+            VaultError::PoolVaultSetMismatch
+        );
+        
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut vault_sum: u64 = 0;
+        
+        for info in ctx.remaining_accounts.iter() {
+            require!(!seen.contains(info.key), VaultError::PoolVaultSetMismatch);
+            seen.push(info.key());
+            
+            let vault = Account::<CollateralVault>::try_from(info)?;
+            require!(
+                vault.pooled && vault.token_mint == custody_pool.token_mint,
+                VaultError::PoolVaultSetMismatch
+            );
+            
+            vault_sum = vault_sum
+                .checked_add(vault.total_balance)
+                .ok_or(VaultError::Overflow)?;
+        }
+        
+        require!(
+            vault_sum == custody_pool.total_balance,
+            VaultError::CustodyBalanceMismatch
+        );
+        
+        require!(
+            custody_pool.total_balance == ctx.accounts.custody_token_account.amount,
+            VaultError::CustodyBalanceMismatch
+        );
+        
+        emit_event!(ctx, CustodyPoolVerified {
+            custody_pool: custody_pool.key(),
+            token_mint: custody_pool.token_mint,
+            total_balance: custody_pool.total_balance,
+            vault_count: custody_pool.vault_count,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 17. Create an isolated sub-account under the caller's vault
+    pub fn create_sub_account(ctx: Context<CreateSubAccount>, id: u8) -> Result<()> {
+        let sub_account = &mut ctx.accounts.sub_account;
+        let clock = Clock::get()?;
+        
+        sub_account.vault = ctx.accounts.vault.key();
+        sub_account.id = id;
+        sub_account.total_balance = 0;
+        sub_account.locked_balance = 0;
+        sub_account.available_balance = 0;
+        sub_account.created_at = clock.unix_timestamp;
+        sub_account.bump = ctx.bumps.sub_account;
+        
+        emit_event!(ctx, SubAccountCreated {
+            vault: sub_account.vault,
+            sub_account: sub_account.key(),
+            token_mint: ctx.accounts.vault.token_mint,
+            id,
+            sequence: next_sequence(&mut ctx.accounts.vault.sequence)?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 18. Move available vault balance into a sub-account (owner only)
+    pub fn allocate_to_sub_account(ctx: Context<ManageSubAccount>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        require!(
+            ctx.accounts.vault.available_balance >= amount,
+            VaultError::InsufficientAvailableBalance
+        );
+        
+        // Funds leave the vault's own balances but stay in its token account
+        let vault = &mut ctx.accounts.vault;
+        vault.total_balance = vault.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        vault.available_balance = vault.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        vault.sub_account_balance = vault.sub_account_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        
+        let sub_account = &mut ctx.accounts.sub_account;
+        sub_account.total_balance = sub_account.total_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        sub_account.available_balance = sub_account.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        
+        emit_event!(ctx, SubAccountAllocated {
+            vault: vault.key(),
+            sub_account: sub_account.key(),
+            token_mint: vault.token_mint,
+            amount,
+            sub_account_balance: sub_account.total_balance,
+            sub_account_locked_balance: sub_account.locked_balance,
+            sub_account_available_balance: sub_account.available_balance,
+            vault_total_balance: vault.total_balance,
+            vault_locked_balance: vault.locked_balance,
+            vault_available_balance: vault.available_balance,
+            vault_sub_account_balance: vault.sub_account_balance,
+            sequence: next_sequence(&mut vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 19. Move available sub-account balance back into the vault (owner only)
+    pub fn release_from_sub_account(ctx: Context<ManageSubAccount>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        require!(
+            ctx.accounts.sub_account.available_balance >= amount,
+            VaultError::InsufficientAvailableBalance
+        );
+        
+        let sub_account = &mut ctx.accounts.sub_account;
+        sub_account.total_balance = sub_account.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        sub_account.available_balance = sub_account.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        
+        let vault = &mut ctx.accounts.vault;
+        vault.sub_account_balance = vault.sub_account_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        vault.total_balance = vault.total_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        vault.available_balance = vault.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        
+        emit_event!(ctx, SubAccountReleased {
+            vault: vault.key(),
+            sub_account: sub_account.key(),
+            token_mint: vault.token_mint,
+            amount,
+            sub_account_balance: sub_account.total_balance,
+            sub_account_locked_balance: sub_account.locked_balance,
+            sub_account_available_balance: sub_account.available_balance,
+            vault_total_balance: vault.total_balance,
+            vault_locked_balance: vault.locked_balance,
+            vault_available_balance: vault.available_balance,
+            vault_sub_account_balance: vault.sub_account_balance,
+            sequence: next_sequence(&mut vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 20. Move available balance between two sub-accounts of the same vault (owner only)
+    pub fn move_between_sub_accounts(
+        ctx: Context<MoveBetweenSubAccounts>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        require!(
+            ctx.accounts.from_sub_account.available_balance >= amount,
+            VaultError::InsufficientAvailableBalance
+        );
+        
+        let from_sub_account = &mut ctx.accounts.from_sub_account;
+        from_sub_account.total_balance = from_sub_account.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        from_sub_account.available_balance = from_sub_account.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        
+        let to_sub_account = &mut ctx.accounts.to_sub_account;
+        to_sub_account.total_balance = to_sub_account.total_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        to_sub_account.available_balance = to_sub_account.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        
+        emit_event!(ctx, SubAccountMoved {
+            vault: ctx.accounts.vault.key(),
+            from_sub_account: ctx.accounts.from_sub_account.key(),
+            to_sub_account: ctx.accounts.to_sub_account.key(),
+            token_mint: ctx.accounts.vault.token_mint,
+            amount,
+            from_total_balance: ctx.accounts.from_sub_account.total_balance,
+            from_locked_balance: ctx.accounts.from_sub_account.locked_balance,
+            from_available_balance: ctx.accounts.from_sub_account.available_balance,
+            to_total_balance: ctx.accounts.to_sub_account.total_balance,
+            to_locked_balance: ctx.accounts.to_sub_account.locked_balance,
+            to_available_balance: ctx.accounts.to_sub_account.available_balance,
+            sequence: next_sequence(&mut ctx.accounts.vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 21. Lock sub-account collateral (called by authorized programs via CPI)
+    pub fn lock_sub_account_collateral(
+        ctx: Context<LockSubAccountCollateral>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        require!(
+            ctx.accounts.sub_account.available_balance >= amount,
+            VaultError::InsufficientAvailableBalance
+        );
+        
+        let sub_account = &mut ctx.accounts.sub_account;
+        sub_account.available_balance = sub_account.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        sub_account.locked_balance = sub_account.locked_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        ctx.accounts.mint_stats.record_lock(amount)?;
+        ctx.accounts.integrator_position.open(
+            ctx.accounts.signer.key(),
+            sub_account.key(),
+            ctx.bumps.integrator_position,
+        );
+        ctx.accounts.integrator_stats.record_lock(
+            &mut ctx.accounts.integrator_position,
+            &ctx.accounts.vault.token_mint,
+            amount,
+        )?;
+        
+        emit_event!(ctx, SubAccountLockEvent {
+            vault: sub_account.vault,
+            sub_account: sub_account.key(),
+            token_mint: ctx.accounts.vault.token_mint,
+            amount,
+            total_balance: sub_account.total_balance,
+            locked_balance: sub_account.locked_balance,
+            available_balance: sub_account.available_balance,
+            caller: ctx.accounts.signer.key(),
+            sequence: next_sequence(&mut ctx.accounts.vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 22. Unlock sub-account collateral (called by authorized programs via CPI)
+    pub fn unlock_sub_account_collateral(
+        ctx: Context<UnlockSubAccountCollateral>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        require!(
+            ctx.accounts.sub_account.locked_balance >= amount,
+            VaultError::InsufficientLockedBalance
+        );
+        
+        let sub_account = &mut ctx.accounts.sub_account;
+        sub_account.locked_balance = sub_account.locked_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        sub_account.available_balance = sub_account.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        ctx.accounts.mint_stats.record_unlock(amount);
+        ctx.accounts.integrator_stats.record_unlock(
+            &mut ctx.accounts.integrator_position,
+            &ctx.accounts.vault.token_mint,
+            amount,
+        )?;
+        
+        emit_event!(ctx, SubAccountUnlockEvent {
+            vault: sub_account.vault,
+            sub_account: sub_account.key(),
+            token_mint: ctx.accounts.vault.token_mint,
+            amount,
+            total_balance: sub_account.total_balance,
+            locked_balance: sub_account.locked_balance,
+            available_balance: sub_account.available_balance,
+            caller: ctx.accounts.signer.key(),
+            sequence: next_sequence(&mut ctx.accounts.vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 23. Transfer sub-account collateral to another vault (for settlements/liquidations)
+    pub fn transfer_sub_account_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TransferSubAccountCollateral<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        require!(
+            ctx.accounts.from_sub_account.available_balance >= amount,
+            VaultError::InsufficientAvailableBalance
+        );
+        
+        // Store values before mutable borrows
+        let from_vault_owner = ctx.accounts.from_vault.owner;
+        let from_vault_bump = ctx.accounts.from_vault.bump;
+        let from_vault_key = ctx.accounts.from_vault.key();
+        let from_sub_account_key = ctx.accounts.from_sub_account.key();
+        let to_vault_key = ctx.accounts.to_vault.key();
+        
+        // Update source sub-account and its parent vault
+        let from_sub_account = &mut ctx.accounts.from_sub_account;
+        from_sub_account.total_balance = from_sub_account.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        from_sub_account.available_balance = from_sub_account.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        
+        let from_vault = &mut ctx.accounts.from_vault;
+        from_vault.sub_account_balance = from_vault.sub_account_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        from_vault.total_transferred_out = from_vault.total_transferred_out
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        let from_sequence = next_sequence(&mut from_vault.sequence)?;
+        
+        // Update destination vault
+        let to_vault = &mut ctx.accounts.to_vault;
+        to_vault.total_balance = to_vault.total_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        to_vault.available_balance = to_vault.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        to_vault.total_transferred_in = to_vault.total_transferred_in
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        let to_sequence = next_sequence(&mut to_vault.sequence)?;
+        
+        ctx.accounts.integrator_stats.record_transfer(amount)?;
+        
+        // PDA seeds for signing
+        let seeds = &[
+            b"vault",
+            from_vault_owner.as_ref(),
+            &[from_vault_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.from_vault_token_account.to_account_info(),
+            ctx.accounts.to_vault_token_account.to_account_info(),
+            ctx.accounts.from_vault.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer_seeds,
+        )?;
+        
+        emit_event!(ctx, SubAccountTransferEvent {
+            from_vault: from_vault_key,
+            from_sub_account: from_sub_account_key,
+            to_vault: to_vault_key,
+            token_mint: ctx.accounts.from_vault.token_mint,
+            amount,
+            from_total_balance: ctx.accounts.from_sub_account.total_balance,
+            from_locked_balance: ctx.accounts.from_sub_account.locked_balance,
+            from_available_balance: ctx.accounts.from_sub_account.available_balance,
+            from_vault_sub_account_balance: ctx.accounts.from_vault.sub_account_balance,
+            to_total_balance: ctx.accounts.to_vault.total_balance,
+            to_locked_balance: ctx.accounts.to_vault.locked_balance,
+            to_available_balance: ctx.accounts.to_vault.available_balance,
+            caller: ctx.accounts.signer.key(),
+            from_sequence,
+            to_sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 24. Close an empty sub-account (owner only)
+    pub fn close_sub_account(ctx: Context<CloseSubAccount>) -> Result<()> {
+        let sub_account = &ctx.accounts.sub_account;
+        
+        require!(
+            sub_account.total_balance == 0,
+            VaultError::VaultNotEmpty
+        );
+        
+        emit_event!(ctx, SubAccountClosed {
+            vault: sub_account.vault,
+            sub_account: sub_account.key(),
+            token_mint: ctx.accounts.vault.token_mint,
+            id: sub_account.id,
+            sequence: next_sequence(&mut ctx.accounts.vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        // Sub-account will be closed and rent returned to user
+        Ok(())
+    }
+
+    /// 25. Initialize multi-collateral portfolio vault
+    pub fn initialize_portfolio_vault(ctx: Context<InitializePortfolioVault>) -> Result<()> {
+        let portfolio_vault = &mut ctx.accounts.portfolio_vault;
+        let clock = Clock::get()?;
+        
+        portfolio_vault.owner = ctx.accounts.user.key();
+        portfolio_vault.balances = Vec::new();
+        portfolio_vault.created_at = clock.unix_timestamp;
+        portfolio_vault.bump = ctx.bumps.portfolio_vault;
+        portfolio_vault.sequence = 0;
+        
+        emit_event!(ctx, PortfolioVaultInitialized {
+            user: ctx.accounts.user.key(),
+            portfolio_vault: portfolio_vault.key(),
+            sequence: next_sequence(&mut portfolio_vault.sequence)?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 26. Add a collateral mint (and its token account) to a portfolio vault
+    pub fn add_portfolio_mint(ctx: Context<AddPortfolioMint>) -> Result<()> {
+        check_mint_allowed(
+            &ctx.accounts.token_mint,
+            &ctx.accounts.mint_config,
+            ctx.accounts.vault_authority.denied_extensions,
+        )?;
+        
+        let token_mint = ctx.accounts.token_mint.key();
+        let portfolio_vault = &mut ctx.accounts.portfolio_vault;
+        
+        require!(
+            portfolio_vault.balances.len() < 8,
+            VaultError::TooManyMints
+        );
+        
+        require!(
+            !portfolio_vault.balances.iter().any(|b| b.mint == token_mint),
+            VaultError::MintAlreadyAdded
+        );
+        
+        portfolio_vault.balances.push(MintBalance {
+            mint: token_mint,
+            token_account: ctx.accounts.vault_token_account.key(),
+            total_balance: 0,
+            locked_balance: 0,
+            available_balance: 0,
+            total_deposited: 0,
+            total_withdrawn: 0,
+        });
+        
+        emit_event!(ctx, PortfolioMintAdded {
+            portfolio_vault: portfolio_vault.key(),
+            token_mint,
+            token_account: ctx.accounts.vault_token_account.key(),
+            sequence: next_sequence(&mut portfolio_vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 27. Deposit collateral of one mint into a portfolio vault
+    pub fn deposit_portfolio<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositPortfolio<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        ctx.accounts.mint_config.check_deposit(amount)?;
+        
+        let token_mint = ctx.accounts.token_mint.key();
+        let portfolio_key = ctx.accounts.portfolio_vault.key();
+        
+        // Update portfolio state BEFORE transfer (CEI pattern)
+        let balance = ctx.accounts.portfolio_vault.balance_mut(&token_mint)?;
+        require!(
+            balance.token_account == ctx.accounts.vault_token_account.key(),
+            VaultError::InvalidTokenAccount
+        );
+        balance.total_balance = balance.total_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        balance.available_balance = balance.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        balance.total_deposited = balance.total_deposited
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        
+        let (new_balance, locked_balance, available_balance) =
+            (balance.total_balance, balance.locked_balance, balance.available_balance);
+        let sequence = next_sequence(&mut ctx.accounts.portfolio_vault.sequence)?;
+        ctx.accounts.mint_config.check_vault_balance(new_balance)?;
+        ctx.accounts.mint_stats.record_deposit(amount, &ctx.accounts.mint_config)?;
+        
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            &[],
+        )?;
+        
+        emit_event!(ctx, PortfolioDepositEvent {
+            user: ctx.accounts.user.key(),
+            portfolio_vault: portfolio_key,
+            token_mint,
+            amount,
+            new_balance,
+            locked_balance,
+            available_balance,
+            sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 28. Withdraw collateral of one mint from a portfolio vault
+    pub fn withdraw_portfolio<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawPortfolio<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        let token_mint = ctx.accounts.token_mint.key();
+        let portfolio_owner = ctx.accounts.portfolio_vault.owner;
+        let portfolio_bump = ctx.accounts.portfolio_vault.bump;
+        let portfolio_key = ctx.accounts.portfolio_vault.key();
+        
+        // Update portfolio state BEFORE transfer (CEI pattern)
+        let balance = ctx.accounts.portfolio_vault.balance_mut(&token_mint)?;
+        require!(
+            balance.token_account == ctx.accounts.vault_token_account.key(),
+            VaultError::InvalidTokenAccount
+        );
+        require!(
+            balance.available_balance >= amount,
+            VaultError::InsufficientAvailableBalance
+        );
+        balance.total_balance = balance.total_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        balance.available_balance = balance.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        balance.total_withdrawn = balance.total_withdrawn
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        
+        let (new_balance, locked_balance, available_balance) =
+            (balance.total_balance, balance.locked_balance, balance.available_balance);
+        let sequence = next_sequence(&mut ctx.accounts.portfolio_vault.sequence)?;
+        ctx.accounts.mint_stats.record_withdrawal(amount)?;
+        
+        // PDA seeds for signing
+        let seeds = &[
+            b"portfolio",
+            portfolio_owner.as_ref(),
+            &[portfolio_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.portfolio_vault.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer_seeds,
+        )?;
+        
+        emit_event!(ctx, PortfolioWithdrawEvent {
+            user: ctx.accounts.user.key(),
+            portfolio_vault: portfolio_key,
+            token_mint,
+            amount,
+            new_balance,
+            locked_balance,
+            available_balance,
+            sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 29. Lock portfolio collateral of one mint (called by authorized programs via CPI)
+    ///
+    /// The mint's `MintStats` is passed as the only remaining account.
+    pub fn lock_portfolio_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LockPortfolioCollateral<'info>>,
+        token_mint: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        ctx.accounts.integrator_position.open(
+            ctx.accounts.signer.key(),
+            ctx.accounts.portfolio_vault.key(),
+            ctx.bumps.integrator_position,
+        );
+        let mint_stats = ctx.remaining_accounts
+            .first()
+            .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+        let event = lock_portfolio_leg(
+            &mut ctx.accounts.portfolio_vault,
+            mint_stats,
+            &mut ctx.accounts.integrator_stats,
+            &mut ctx.accounts.integrator_position,
+            ctx.accounts.signer.key(),
+            token_mint,
+            amount,
+            None,
+            None,
+        )?;
+        emit_event!(ctx, event);
+        
+        Ok(())
+    }
+
+    /// 30. Unlock portfolio collateral of one mint (called by authorized programs via CPI)
+    ///
+    /// The mint's `MintStats` is passed as the only remaining account.
+    pub fn unlock_portfolio_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnlockPortfolioCollateral<'info>>,
+        token_mint: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        let mint_stats = ctx.remaining_accounts
+            .first()
+            .ok_or(ErrorCode::AccountNotEnoughKeys)?;
+        let event = unlock_portfolio_leg(
+            &mut ctx.accounts.portfolio_vault,
+            mint_stats,
+            &mut ctx.accounts.integrator_stats,
+            &mut ctx.accounts.integrator_position,
+            ctx.accounts.signer.key(),
+            token_mint,
+            amount,
+        )?;
+        emit_event!(ctx, event);
+        
+        Ok(())
+    }
+
+    /// 31. Lock a basket of mints atomically (called by authorized programs via CPI)
+    ///
+    /// Each leg's `MintStats` is passed as a remaining account, in leg order.
+    pub fn lock_portfolio_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LockPortfolioCollateral<'info>>,
+        legs: Vec<BasketLeg>,
+    ) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(!legs.is_empty() && legs.len() <= 8, VaultError::InvalidBasket);
+        require!(
+            ctx.remaining_accounts.len() == legs.len(),
+            VaultError::InvalidBasket
+        );
+        
+        ctx.accounts.integrator_position.open(
+            ctx.accounts.signer.key(),
+            ctx.accounts.portfolio_vault.key(),
+            ctx.bumps.integrator_position,
+        );
+        for (leg, mint_stats) in legs.into_iter().zip(ctx.remaining_accounts) {
+            let event = lock_portfolio_leg(
+                &mut ctx.accounts.portfolio_vault,
+                mint_stats,
+                &mut ctx.accounts.integrator_stats,
+                &mut ctx.accounts.integrator_position,
+                ctx.accounts.signer.key(),
+                leg.mint,
+                leg.amount,
+                None,
+                None,
+            )?;
+            emit_event!(ctx, event);
+        }
+        
+        Ok(())
+    }
+
+    /// 32. Unlock a basket of mints atomically (called by authorized programs via CPI)
+    ///
+    /// Each leg's `MintStats` is passed as a remaining account, in leg order.
+    pub fn unlock_portfolio_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnlockPortfolioCollateral<'info>>,
+        legs: Vec<BasketLeg>,
+    ) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(!legs.is_empty() && legs.len() <= 8, VaultError::InvalidBasket);
+        require!(
+            ctx.remaining_accounts.len() == legs.len(),
+            VaultError::InvalidBasket
+        );
+        
+        for (leg, mint_stats) in legs.into_iter().zip(ctx.remaining_accounts) {
+            let event = unlock_portfolio_leg(
+                &mut ctx.accounts.portfolio_vault,
+                mint_stats,
+                &mut ctx.accounts.integrator_stats,
+                &mut ctx.accounts.integrator_position,
+                ctx.accounts.signer.key(),
+                leg.mint,
+                leg.amount,
+            )?;
+            emit_event!(ctx, event);
+        }
+        
+        Ok(())
+    }
+
+    /// 33. Initialize oracle valuation config for a mint (admin only)
+    pub fn initialize_oracle_config(
+        ctx: Context<InitializeOracleConfig>,
+        params: OracleConfigParams,
+    ) -> Result<()> {
+        params.validate()?;
+        
+        let oracle_config = &mut ctx.accounts.oracle_config;
+        oracle_config.token_mint = ctx.accounts.token_mint.key();
+        oracle_config.bump = ctx.bumps.oracle_config;
+        oracle_config.apply(&params);
+        
+        emit_event!(ctx, OracleConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            token_mint: oracle_config.token_mint,
+            oracle: params.oracle,
+            max_staleness: params.max_staleness,
+            max_confidence_bps: params.max_confidence_bps,
+            haircut_bps: params.haircut_bps,
+            sequence: next_sequence(&mut ctx.accounts.vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 34. Update oracle valuation config for a mint (admin only)
+    pub fn update_oracle_config(
+        ctx: Context<UpdateOracleConfig>,
+        params: OracleConfigParams,
+    ) -> Result<()> {
+        params.validate()?;
+        
+        let oracle_config = &mut ctx.accounts.oracle_config;
+        oracle_config.apply(&params);
+        
+        emit_event!(ctx, OracleConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            token_mint: oracle_config.token_mint,
+            oracle: params.oracle,
+            max_staleness: params.max_staleness,
+            max_confidence_bps: params.max_confidence_bps,
+            haircut_bps: params.haircut_bps,
+            sequence: next_sequence(&mut ctx.accounts.vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 35. Risk-adjusted value of a vault in quote units (view, returned via return data)
+    pub fn get_collateral_value(ctx: Context<GetCollateralValue>) -> Result<CollateralValue> {
+        let vault = &ctx.accounts.vault;
+        let oracle_config = &ctx.accounts.oracle_config;
+        let clock = Clock::get()?;
+        
+        let price = read_oracle_price(
+            &ctx.accounts.oracle.to_account_info(),
+            oracle_config,
+            clock.unix_timestamp,
+        )?;
+        let decimals = ctx.accounts.token_mint.decimals;
+        
+        Ok(CollateralValue {
+            vault: vault.key(),
+            token_mint: vault.token_mint,
+            total_balance: vault.total_balance,
+            available_balance: vault.available_balance,
+            price: price.price,
+            expo: price.expo,
+            haircut_bps: oracle_config.haircut_bps,
+            total_value: collateral_value(vault.total_balance, &price, decimals, oracle_config.haircut_bps)?,
+            available_value: collateral_value(vault.available_balance, &price, decimals, oracle_config.haircut_bps)?,
+            quote_decimals: QUOTE_DECIMALS,
+            timestamp: clock.unix_timestamp,
+        })
+    }
+
+    /// 36. Create a mock price feed (local testing only)
+    #[cfg(feature = "mock-oracle")]
+    pub fn initialize_mock_price_feed(ctx: Context<InitializeMockPriceFeed>) -> Result<()> {
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.authority = ctx.accounts.authority.key();
+        Ok(())
+    }
+
+    /// 37. Publish a mock price (local testing only, `publish_time` defaults to now)
+    #[cfg(feature = "mock-oracle")]
+    pub fn set_mock_price(
+        ctx: Context<SetMockPrice>,
+        price: i64,
+        conf: u64,
+        expo: i32,
+        publish_time: Option<i64>,
+    ) -> Result<()> {
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.price = OraclePrice {
+            price,
+            conf,
+            expo,
+            publish_time: match publish_time {
+                Some(publish_time) => publish_time,
+                None => Clock::get()?.unix_timestamp,
+            },
+        };
+        Ok(())
+    }
+
+    /// 38. Lock collateral worth `value` quote units at the oracle price (called by authorized programs via CPI)
+    pub fn lock_value(ctx: Context<LockValue>, value: u64) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(value > 0, VaultError::InvalidAmount);
+        
+        let oracle_config = &ctx.accounts.oracle_config;
+        let price = read_oracle_price(
+            &ctx.accounts.oracle.to_account_info(),
+            oracle_config,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let amount = token_amount_for_value(
+            value,
+            &price,
+            ctx.accounts.token_mint.decimals,
+            oracle_config.haircut_bps,
+        )?;
+        
+        ctx.accounts.integrator_position.open(
+            ctx.accounts.signer.key(),
+            ctx.accounts.vault.key(),
+            ctx.bumps.integrator_position,
+        );
+        let event = lock_vault_collateral(
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.mint_stats,
+            &mut ctx.accounts.integrator_stats,
+            &mut ctx.accounts.integrator_position,
+            ctx.accounts.signer.key(),
+            amount,
+            Some(price),
+            Some(value),
+        )?;
+        emit_event!(ctx, event);
+        
+        Ok(())
+    }
+
+    /// 39. Register a mint in the collateral registry (admin only)
+    pub fn initialize_mint_config(
+        ctx: Context<InitializeMintConfig>,
+        params: MintConfigParams,
+    ) -> Result<()> {
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.token_mint = ctx.accounts.token_mint.key();
+        mint_config.bump = ctx.bumps.mint_config;
+        mint_config.apply(&params);
+        
+        let mint_stats = &mut ctx.accounts.mint_stats;
+        mint_stats.token_mint = mint_config.token_mint;
+        mint_stats.total_balance = 0;
+        mint_stats.total_deposited = 0;
+        mint_stats.total_withdrawn = 0;
+        mint_stats.total_locked = 0;
+        mint_stats.vault_count = 0;
+        mint_stats.bump = ctx.bumps.mint_stats;
+        mint_stats.unreconciled = false;
+        
+        emit_event!(ctx, MintConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            token_mint: mint_config.token_mint,
+            approved: params.approved,
+            allowed_extensions: params.allowed_extensions,
+            allow_freeze_authority: params.allow_freeze_authority,
+            max_deposit_amount: params.max_deposit_amount,
+            max_vault_balance: params.max_vault_balance,
+            max_total_balance: params.max_total_balance,
+            sequence: next_sequence(&mut ctx.accounts.vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 40. Update a mint's registry entry (admin only)
+    pub fn update_mint_config(
+        ctx: Context<UpdateMintConfig>,
+        params: MintConfigParams,
+    ) -> Result<()> {
+        let mint_config = &mut ctx.accounts.mint_config;
+        mint_config.apply(&params);
+        
+        emit_event!(ctx, MintConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            token_mint: mint_config.token_mint,
+            approved: params.approved,
+            allowed_extensions: params.allowed_extensions,
+            allow_freeze_authority: params.allow_freeze_authority,
+            max_deposit_amount: params.max_deposit_amount,
+            max_vault_balance: params.max_vault_balance,
+            max_total_balance: params.max_total_balance,
+            sequence: next_sequence(&mut ctx.accounts.vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 41. Replace the Token-2022 extension deny-list (admin only)
+    pub fn set_denied_extensions(
+        ctx: Context<UpdateAuthority>,
+        denied_extensions: u64,
+    ) -> Result<()> {
+        let vault_authority = &mut ctx.accounts.vault_authority;
+        vault_authority.denied_extensions = denied_extensions;
+        
+        emit_event!(ctx, DeniedExtensionsUpdated {
+            admin: ctx.accounts.admin.key(),
+            denied_extensions,
+            sequence: next_sequence(&mut vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 42. Aggregate custody statistics for a mint (view, returned via return data)
+    pub fn get_mint_stats(ctx: Context<GetMintStats>) -> Result<MintStatsView> {
+        let mint_stats = &ctx.accounts.mint_stats;
+        
+        Ok(MintStatsView {
+            token_mint: mint_stats.token_mint,
+            total_balance: mint_stats.total_balance,
+            total_deposited: mint_stats.total_deposited,
+            total_withdrawn: mint_stats.total_withdrawn,
+            total_locked: mint_stats.total_locked,
+            vault_count: mint_stats.vault_count,
+            unreconciled: mint_stats.unreconciled,
+            timestamp: Clock::get()?.unix_timestamp,
+        })
+    }
+
+    /// 43. Create the exposure counters for an authorized program (admin only)
+    ///
+    /// Optional: the program's first lock, unlock or transfer creates them
+    /// uncapped. Run this before authorizing a program that should start capped;
+    /// once the counters exist, `set_integrator_cap` changes the cap.
+    pub fn initialize_integrator_stats(
+        ctx: Context<InitializeIntegratorStats>,
+        program: Pubkey,
+        max_locked: u64,
+    ) -> Result<()> {
+        let integrator_stats = &mut ctx.accounts.integrator_stats;
+        integrator_stats.program = program;
+        integrator_stats.locked_balance = 0;
+        integrator_stats.total_locked = 0;
+        integrator_stats.total_transferred = 0;
+        integrator_stats.max_locked = max_locked;
+        integrator_stats.bump = ctx.bumps.integrator_stats;
+        
+        emit_event!(ctx, IntegratorCapUpdated {
+            admin: ctx.accounts.admin.key(),
+            program,
+            max_locked,
+            sequence: next_sequence(&mut ctx.accounts.vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 44. Update an authorized program's lock cap (admin only)
+    pub fn set_integrator_cap(
+        ctx: Context<SetIntegratorCap>,
+        max_locked: u64,
+    ) -> Result<()> {
+        let integrator_stats = &mut ctx.accounts.integrator_stats;
+        integrator_stats.max_locked = max_locked;
+        
+        emit_event!(ctx, IntegratorCapUpdated {
+            admin: ctx.accounts.admin.key(),
+            program: integrator_stats.program,
+            max_locked,
+            sequence: next_sequence(&mut ctx.accounts.vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 45. Migrate a vault to the current account layout (idempotent, anyone can pay)
+    pub fn migrate_vault(ctx: Context<MigrateVault>) -> Result<()> {
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let current_space = 8 + CollateralVault::INIT_SPACE;
+        
+        {
+            let data = vault_info.try_borrow_data()?;
+            require!(
+                (data.len() == LEGACY_VAULT_SPACE || data.len() == current_space)
+                    && data[..8] == *CollateralVault::DISCRIMINATOR
+                    && data[8..40] == ctx.accounts.owner.key().to_bytes(),
+                VaultError::InvalidVaultLayout
+            );
+        }
+        
+        let from_version = if vault_info.data_len() < current_space {
+            0
+        } else {
+            CollateralVault::try_deserialize(&mut &vault_info.try_borrow_data()?[..])?.version
+        };
+        if from_version == VAULT_VERSION {
+            return Ok(());
+        }
+        
+        // Grow the account, topping up rent from the payer
+        if vault_info.data_len() < current_space {
+            let required = Rent::get()?.minimum_balance(current_space);
+            let top_up = required.saturating_sub(vault_info.lamports());
+            if top_up > 0 {
+                anchor_lang::system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.payer.to_account_info(),
+                            to: vault_info.clone(),
+                        },
+                    ),
+                    top_up,
+                )?;
+            }
+            vault_info.resize(current_space)?;
+        }
+        
+        // New bytes are zeroed, so an original vault reads as unpooled with no
+        // sub-account balance. Transfers weren't counted before version 2, and
+        // their history can't be recovered, so the counters start at zero and
+        // the vault is flagged as having a partial history
+        let mut data = vault_info.try_borrow_mut_data()?;
+        let mut vault = CollateralVault::try_deserialize(&mut &data[..])?;
+        vault.version = VAULT_VERSION;
+        vault.partial_history = from_version < 2;
+        if from_version < 2 {
+            vault.unattributed_locked = vault.locked_balance;
+        }
+        let sequence = next_sequence(&mut vault.sequence)?;
+        vault.try_serialize(&mut &mut data[..])?;
+        
+        emit_event!(ctx, VaultMigrated {
+            vault: vault_info.key(),
+            token_mint: vault.token_mint,
+            from_version,
+            to_version: VAULT_VERSION,
+            total_balance: vault.total_balance,
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+            sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 46. Initialize the zero-copy vault authority
+    ///
+    /// The `_zc` instructions (46-54) are an experiment measuring what zero-copy saves
+    /// over Borsh on the lock path, built only with the `zero-copy` feature so they
+    /// stay out of the deployed program. `VaultAuthority` and `CollateralVault` remain
+    /// the canonical accounts: zero-copy vaults have no pooled custody, sub-accounts,
+    /// transfers or migration, and the views and `assert_invariants` don't read them.
+    #[cfg(feature = "zero-copy")]
+    pub fn initialize_authority_zc(
+        ctx: Context<InitializeAuthorityZc>,
+        authorized_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        let mut vault_authority = ctx.accounts.vault_authority.load_init()?;
+        
+        require!(
+            authorized_programs.len() <= vault_authority.authorized_programs.len(),
+            VaultError::TooManyPrograms
+        );
+        
+        vault_authority.admin = ctx.accounts.admin.key();
+        vault_authority.authorized_programs[..authorized_programs.len()]
+            .copy_from_slice(&authorized_programs);
+        vault_authority.program_count = authorized_programs.len() as u8;
+        vault_authority.denied_extensions = DEFAULT_DENIED_EXTENSIONS;
+        vault_authority.bump = ctx.bumps.vault_authority;
+        
+        emit_event!(ctx, AuthorityInitialized {
+            admin: ctx.accounts.admin.key(),
+            authority: ctx.accounts.vault_authority.key(),
+            authorized_programs,
+            sequence: next_sequence(&mut vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 47. Add authorized program to the zero-copy authority (admin only)
+    #[cfg(feature = "zero-copy")]
+    pub fn add_authorized_program_zc(
+        ctx: Context<UpdateAuthorityZc>,
+        program: Pubkey,
+    ) -> Result<()> {
+        let mut vault_authority = ctx.accounts.vault_authority.load_mut()?;
+        let count = vault_authority.program_count as usize;
+        
+        require!(
+            count < vault_authority.authorized_programs.len(),
+            VaultError::TooManyPrograms
+        );
+        
+        require!(
+            !vault_authority.is_authorized(&program),
+            VaultError::ProgramAlreadyAuthorized
+        );
+        
+        vault_authority.authorized_programs[count] = program;
+        vault_authority.program_count += 1;
+        
+        emit_event!(ctx, ProgramAuthorized {
+            admin: ctx.accounts.admin.key(),
+            program,
+            sequence: next_sequence(&mut vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 48. Remove authorized program from the zero-copy authority (admin only)
+    #[cfg(feature = "zero-copy")]
+    pub fn remove_authorized_program_zc(
+        ctx: Context<UpdateAuthorityZc>,
+        program: Pubkey,
+    ) -> Result<()> {
+        let mut vault_authority = ctx.accounts.vault_authority.load_mut()?;
+        let count = vault_authority.program_count as usize;
+        
+        // Swap-remove keeps the used entries contiguous
+        let index = vault_authority.authorized_programs()
+            .iter()
+            .position(|p| *p == program)
+            .ok_or(VaultError::ProgramNotAuthorized)?;
+        vault_authority.authorized_programs[index] = vault_authority.authorized_programs[count - 1];
+        vault_authority.authorized_programs[count - 1] = Pubkey::default();
+        vault_authority.program_count -= 1;
+        
+        emit_event!(ctx, ProgramDeauthorized {
+            admin: ctx.accounts.admin.key(),
+            program,
+            sequence: next_sequence(&mut vault_authority.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 49. Initialize zero-copy user vault
+    #[cfg(feature = "zero-copy")]
+    pub fn initialize_vault_zc(ctx: Context<InitializeVaultZc>) -> Result<()> {
+        check_mint_allowed(
+            &ctx.accounts.token_mint,
+            &ctx.accounts.mint_config,
+            ctx.accounts.vault_authority.load()?.denied_extensions,
+        )?;
+        
+        let mut vault = ctx.accounts.vault.load_init()?;
+        let clock = Clock::get()?;
+        
+        vault.owner = ctx.accounts.user.key();
+        vault.token_account = ctx.accounts.vault_token_account.key();
+        vault.token_mint = ctx.accounts.token_mint.key();
+        vault.total_balance = 0;
+        vault.locked_balance = 0;
+        vault.available_balance = 0;
+        vault.total_deposited = 0;
+        vault.total_withdrawn = 0;
+        vault.created_at = clock.unix_timestamp;
+        vault.bump = ctx.bumps.vault;
+        vault.version = ZERO_COPY_VERSION;
+        
+        ctx.accounts.mint_stats.record_vault_opened()?;
+        
+        emit_event!(ctx, VaultInitialized {
+            user: ctx.accounts.user.key(),
+            vault: ctx.accounts.vault.key(),
+            token_mint: vault.token_mint,
+            sequence: next_sequence(&mut vault.sequence)?,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 50. Deposit collateral into a zero-copy vault
+    #[cfg(feature = "zero-copy")]
+    pub fn deposit_zc<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositZc<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        ctx.accounts.mint_config.check_deposit(amount)?;
+        
+        // Update vault state BEFORE transfer (CEI pattern); the borrow must end before the CPI
+        let (new_balance, locked_balance, available_balance, sequence) = {
+            let mut vault = ctx.accounts.vault.load_mut()?;
+            vault.total_balance = vault.total_balance
+                .checked_add(amount)
+                .ok_or(VaultError::Overflow)?;
+            vault.available_balance = vault.available_balance
+                .checked_add(amount)
+                .ok_or(VaultError::Overflow)?;
+            vault.total_deposited = vault.total_deposited
+                .checked_add(amount)
+                .ok_or(VaultError::Overflow)?;
+            (
+                vault.total_balance,
+                vault.locked_balance,
+                vault.available_balance,
+                next_sequence(&mut vault.sequence)?,
+            )
+        };
+        ctx.accounts.mint_config.check_vault_balance(new_balance)?;
+        ctx.accounts.mint_stats.record_deposit(amount, &ctx.accounts.mint_config)?;
+        
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            &[],
+        )?;
+        
+        emit_event!(ctx, DepositEvent {
+            user: ctx.accounts.user.key(),
+            vault: ctx.accounts.vault.key(),
+            token_mint: ctx.accounts.token_mint.key(),
+            amount,
+            new_balance,
+            locked_balance,
+            available_balance,
+            sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 51. Lock zero-copy vault collateral (called by authorized programs via CPI)
+    #[cfg(feature = "zero-copy")]
+    pub fn lock_collateral_zc(ctx: Context<LockCollateralZc>, amount: u64) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        
+        require!(
+            vault.available_balance >= amount,
+            VaultError::InsufficientAvailableBalance
+        );
+        
+        vault.available_balance = vault.available_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        vault.locked_balance = vault.locked_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        ctx.accounts.mint_stats.record_lock(amount)?;
+        ctx.accounts.integrator_position.open(
+            ctx.accounts.signer.key(),
+            ctx.accounts.vault.key(),
+            ctx.bumps.integrator_position,
+        );
+        ctx.accounts.integrator_stats.record_lock(
+            &mut ctx.accounts.integrator_position,
+            &vault.token_mint,
+            amount,
+        )?;
+        
+        emit_event!(ctx, LockEvent {
+            vault: ctx.accounts.vault.key(),
+            token_mint: vault.token_mint,
+            amount,
+            total_balance: vault.total_balance,
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+            caller: ctx.accounts.signer.key(),
+            price: None,
+            quote_value: None,
+            sequence: next_sequence(&mut vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 52. Unlock zero-copy vault collateral (called by authorized programs via CPI)
+    #[cfg(feature = "zero-copy")]
+    pub fn unlock_collateral_zc(ctx: Context<UnlockCollateralZc>, amount: u64) -> Result<()> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        
+        require!(
+            vault.locked_balance >= amount,
+            VaultError::InsufficientLockedBalance
+        );
+        
+        vault.locked_balance = vault.locked_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        vault.available_balance = vault.available_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        ctx.accounts.mint_stats.record_unlock(amount);
+        ctx.accounts.integrator_stats.record_unlock(
+            &mut ctx.accounts.integrator_position,
+            &vault.token_mint,
+            amount,
+        )?;
+        
+        emit_event!(ctx, UnlockEvent {
+            vault: ctx.accounts.vault.key(),
+            token_mint: vault.token_mint,
+            amount,
+            total_balance: vault.total_balance,
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+            caller: ctx.accounts.signer.key(),
+            sequence: next_sequence(&mut vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 53. Withdraw collateral from a zero-copy vault
+    #[cfg(feature = "zero-copy")]
+    pub fn withdraw_zc<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawZc<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        
+        // Update vault state BEFORE transfer (CEI pattern); the borrow must end before the CPI
+        let (vault_owner, vault_bump, new_balance, locked_balance, available_balance, sequence) = {
+            let mut vault = ctx.accounts.vault.load_mut()?;
+            
+            require!(
+                vault.available_balance >= amount,
+                VaultError::InsufficientAvailableBalance
+            );
+            
+            vault.total_balance = vault.total_balance
+                .checked_sub(amount)
+                .ok_or(VaultError::Underflow)?;
+            vault.available_balance = vault.available_balance
+                .checked_sub(amount)
+                .ok_or(VaultError::Underflow)?;
+            vault.total_withdrawn = vault.total_withdrawn
+                .checked_add(amount)
+                .ok_or(VaultError::Overflow)?;
+            (
+                vault.owner,
+                vault.bump,
+                vault.total_balance,
+                vault.locked_balance,
+                vault.available_balance,
+                next_sequence(&mut vault.sequence)?,
+            )
+        };
+        ctx.accounts.mint_stats.record_withdrawal(amount)?;
+        
+        // PDA seeds for signing
+        let seeds = &[
+            b"vault_zc",
+            vault_owner.as_ref(),
+            &[vault_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            ctx.accounts.vault_token_account.to_account_info(),
+            ctx.accounts.user_token_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            signer_seeds,
+        )?;
+        
+        emit_event!(ctx, WithdrawEvent {
+            user: ctx.accounts.user.key(),
+            vault: ctx.accounts.vault.key(),
+            token_mint: ctx.accounts.token_mint.key(),
+            amount,
+            new_balance,
+            locked_balance,
+            available_balance,
+            sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 54. Close zero-copy vault (only when balance is zero and no locked collateral)
+    #[cfg(feature = "zero-copy")]
+    pub fn close_vault_zc(ctx: Context<CloseVaultZc>) -> Result<()> {
+        let (token_mint, sequence) = {
+            let mut vault = ctx.accounts.vault.load_mut()?;
+            
+            require!(
+                vault.total_balance == 0,
+                VaultError::VaultNotEmpty
+            );
+            
+            require!(
+                vault.locked_balance == 0,
+                VaultError::HasLockedCollateral
+            );
+            
+            (vault.token_mint, next_sequence(&mut vault.sequence)?)
+        };
+        
+        ctx.accounts.mint_stats.record_vault_closed();
+        
+        emit_event!(ctx, VaultClosed {
+            user: ctx.accounts.user.key(),
+            vault: ctx.accounts.vault.key(),
+            token_mint,
+            sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// 55. Vault balances in a stable, versioned layout (view, returned via return data)
+    pub fn get_vault_balances(ctx: Context<GetVaultBalances>) -> Result<VaultBalances> {
+        let vault = &ctx.accounts.vault;
+        
+        Ok(VaultBalances {
+            version: VIEW_VERSION,
+            vault: vault.key(),
+            owner: vault.owner,
+            token_mint: vault.token_mint,
+            total_balance: vault.total_balance,
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+            sub_account_balance: vault.sub_account_balance,
+            total_deposited: vault.total_deposited,
+            total_withdrawn: vault.total_withdrawn,
+            pooled: vault.pooled,
+            total_transferred_in: vault.total_transferred_in,
+            total_transferred_out: vault.total_transferred_out,
+            total_seized: vault.total_seized,
+            total_fees_paid: vault.total_fees_paid,
+        })
+    }
+
+    /// 56. Locked collateral of a vault and the sub-accounts passed as remaining accounts (view)
+    pub fn get_lock_positions<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetVaultBalances<'info>>,
+    ) -> Result<LockPositions> {
+        let vault = &ctx.accounts.vault;
+        let mut sub_accounts = Vec::with_capacity(ctx.remaining_accounts.len());
+        
+        for info in ctx.remaining_accounts.iter() {
+            let sub_account = Account::<SubAccount>::try_from(info)?;
+            require!(
+                sub_account.vault == vault.key(),
+                VaultError::InvalidSubAccount
+            );
+            sub_accounts.push(SubAccountLock {
+                id: sub_account.id,
+                locked_balance: sub_account.locked_balance,
+                available_balance: sub_account.available_balance,
+            });
+        }
+        
+        Ok(LockPositions {
+            version: VIEW_VERSION,
+            vault: vault.key(),
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+            sub_accounts,
+        })
+    }
+
+    /// 57. Vault authority configuration in a stable, versioned layout (view)
+    pub fn get_authority_config(ctx: Context<GetAuthorityConfig>) -> Result<AuthorityConfig> {
+        let vault_authority = &ctx.accounts.vault_authority;
+        
+        Ok(AuthorityConfig {
+            version: VIEW_VERSION,
+            admin: vault_authority.admin,
+            authorized_programs: vault_authority.authorized_programs.clone(),
+            denied_extensions: vault_authority.denied_extensions,
+        })
+    }
+
+    /// 58. Check a vault's balance invariants (permissionless, for keepers and tests)
+    pub fn assert_invariants(ctx: Context<AssertInvariants>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        
+        let split_total = vault.locked_balance
+            .checked_add(vault.available_balance)
+            .ok_or(VaultError::Overflow)?;
+        require!(
+            vault.total_balance == split_total,
+            VaultError::BalanceSplitMismatch
+        );
+        
+        // Sub-account funds stay in the vault's token account. A pooled vault
+        // only sees the shared custody account, so this is a lower bound there
+        let held = vault.total_balance
+            .checked_add(vault.sub_account_balance)
+            .ok_or(VaultError::Overflow)?;
+        require!(
+            held <= ctx.accounts.vault_token_account.amount,
+            VaultError::CustodyShortfall
+        );
+        
+        // Lifetime flows must explain the balance. Vaults older than version 2,
+        // or migrated from before it, have no complete transfer history to check
+        if vault.version >= 2 && !vault.partial_history {
+            let inflow = vault.total_deposited
+                .checked_add(vault.total_transferred_in)
+                .ok_or(VaultError::Overflow)?;
+            let outflow = vault.total_withdrawn
+                .checked_add(vault.total_transferred_out)
+                .ok_or(VaultError::Overflow)?
+                .checked_add(vault.total_seized)
+                .ok_or(VaultError::Overflow)?
+                .checked_add(vault.total_fees_paid)
+                .ok_or(VaultError::Overflow)?;
+            require!(
+                inflow.checked_sub(outflow) == Some(held),
+                VaultError::LifetimeBalanceMismatch
+            );
+        }
+        
+        Ok(())
+    }
+
+    /// 59. Lock portfolio collateral worth `value` quote units, split across mints by weight
+    /// (called by authorized programs via CPI)
+    ///
+    /// Each leg is priced through its mint's oracle config; remaining accounts hold
+    /// `[token_mint, oracle_config, oracle, mint_stats]` per leg, in leg order. Returns
+    /// the token amount locked per mint, which `unlock_portfolio_basket` accepts as-is.
+    pub fn lock_portfolio_value<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LockPortfolioCollateral<'info>>,
+        value: u64,
+        legs: Vec<BasketWeight>,
+    ) -> Result<Vec<BasketLeg>> {
+        ctx.accounts.integrator_stats.open(ctx.accounts.signer.key(), ctx.bumps.integrator_stats);
+        
+        require!(value > 0, VaultError::InvalidAmount);
+        require!(!legs.is_empty() && legs.len() <= 8, VaultError::InvalidBasket);
+        require!(
+            legs.iter().all(|leg| leg.weight_bps > 0)
+                && legs.iter().map(|leg| leg.weight_bps as u32).sum::<u32>() == 10_000,
+            VaultError::InvalidBasket
+        );
+        require!(
+            ctx.remaining_accounts.len() == legs.len() * 4,
+            VaultError::InvalidBasket
+        );
+        
+        ctx.accounts.integrator_position.open(
+            ctx.accounts.signer.key(),
+            ctx.accounts.portfolio_vault.key(),
+            ctx.bumps.integrator_position,
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let mut unassigned = value;
+        let mut locked = Vec::with_capacity(legs.len());
+        
+        for (index, (leg, accounts)) in legs
+            .iter()
+            .zip(ctx.remaining_accounts.chunks(4))
+            .enumerate()
+        {
+            // The last leg takes the rounding remainder so the legs sum to `value`
+            let leg_value = if index + 1 == legs.len() {
+                unassigned
+            } else {
+                (value as u128 * leg.weight_bps as u128 / 10_000) as u64
+            };
+            unassigned = unassigned
+                .checked_sub(leg_value)
+                .ok_or(VaultError::Underflow)?;
+            
+            let (price, amount) = price_portfolio_leg(leg.mint, leg_value, &accounts[..3], now)?;
+            let event = lock_portfolio_leg(
+                &mut ctx.accounts.portfolio_vault,
+                &accounts[3],
+                &mut ctx.accounts.integrator_stats,
+                &mut ctx.accounts.integrator_position,
+                ctx.accounts.signer.key(),
+                leg.mint,
+                amount,
+                Some(price),
+                Some(leg_value),
+            )?;
+            emit_event!(ctx, event);
+            
+            locked.push(BasketLeg {
+                mint: leg.mint,
+                amount,
+            });
+        }
+        
+        Ok(locked)
+    }
+
+    /// 60. Close an empty portfolio vault (owner only)
+    pub fn close_portfolio_vault(ctx: Context<ClosePortfolioVault>) -> Result<()> {
+        let portfolio_vault = &mut ctx.accounts.portfolio_vault;
+        
+        require!(
+            portfolio_vault.balances.iter().all(|b| b.total_balance == 0),
+            VaultError::VaultNotEmpty
+        );
+        
+        emit_event!(ctx, PortfolioVaultClosed {
+            user: ctx.accounts.user.key(),
+            portfolio_vault: portfolio_vault.key(),
+            sequence: next_sequence(&mut portfolio_vault.sequence)?,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        // Portfolio account will be closed and rent returned to user
+        Ok(())
+    }
+
+    /// 61. Migrate the vault authority to the current account layout (idempotent, anyone can pay)
+    pub fn migrate_authority(ctx: Context<MigrateAuthority>) -> Result<()> {
+        let authority_info = ctx.accounts.vault_authority.to_account_info();
+        let current_space = 8 + VaultAuthority::INIT_SPACE;
+        
+        {
+            let data = authority_info.try_borrow_data()?;
+            require!(
+                (data.len() == LEGACY_AUTHORITY_SPACE || data.len() == current_space)
+                    && data[..8] == *VaultAuthority::DISCRIMINATOR,
+                VaultError::InvalidAuthorityLayout
+            );
+        }
+        
+        if authority_info.data_len() == current_space {
+            let vault_authority =
+                VaultAuthority::try_deserialize(&mut &authority_info.try_borrow_data()?[..])?;
+            if vault_authority.version == AUTHORITY_VERSION {
+                return Ok(());
+            }
+        }
+        
+        // Version 0 ends at `bump`, and admin instructions can't have written
+        // past it, since they require the current version
+        let (admin, authorized_programs, bump) = <(Pubkey, Vec<Pubkey>, u8)>::deserialize(
+            &mut &authority_info.try_borrow_data()?[8..],
+        )?;
+        
+        // Grow the account, topping up rent from the payer
+        let required = Rent::get()?.minimum_balance(current_space);
+        let top_up = required.saturating_sub(authority_info.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: authority_info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        authority_info.resize(current_space)?;
+        
+        // Vaults were created without a deny-list before version 1, so it starts
+        // at the default, as for a new authority
+        let mut vault_authority = VaultAuthority {
+            admin,
+            authorized_programs,
+            bump,
+            denied_extensions: DEFAULT_DENIED_EXTENSIONS,
+            sequence: 0,
+            version: AUTHORITY_VERSION,
+        };
+        let sequence = next_sequence(&mut vault_authority.sequence)?;
+        vault_authority.try_serialize(&mut &mut authority_info.try_borrow_mut_data()?[..])?;
+        
+        emit_event!(ctx, AuthorityMigrated {
+            authority: authority_info.key(),
+            from_version: 0,
+            to_version: AUTHORITY_VERSION,
+            denied_extensions: DEFAULT_DENIED_EXTENSIONS,
+            sequence,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+// ============ HELPERS ============
+
+/// Bit for `extension` in a `MintConfig::allowed_extensions` mask
+pub fn extension_bit(extension: ExtensionType) -> u64 {
+    1u64 << u16::from(extension)
+}
+
+/// Checks a mint's freeze authority and Token-2022 extensions against the
+/// authority deny-list and the mint's registry entry.
+fn check_mint_allowed(
+    mint: &InterfaceAccount<Mint>,
+    mint_config: &MintConfig,
+    denied_extensions: u64,
+) -> Result<()> {
+    require!(
+        mint_config.allow_freeze_authority || mint.freeze_authority.is_none(),
+        VaultError::FreezeAuthorityNotAllowed
+    );
+    
+    // Legacy SPL mints carry no extensions
+    let mint_info = mint.to_account_info();
+    if mint_info.owner != &spl_token_2022::ID {
+        return Ok(());
+    }
+    
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    
+    for extension in state.get_extension_types()? {
+        if denied_extensions & extension_bit(extension) != 0 {
+            check_denied_extension(&state, extension)?;
+        }
+        
+        require!(
+            mint_config.allowed_extensions & extension_bit(extension) != 0,
+            VaultError::ExtensionNotAllowed
+        );
+    }
+    
+    Ok(())
+}
+
+/// Rejects a deny-listed extension with its own error.
+///
+/// DefaultAccountState is only rejected when new accounts start frozen.
+fn check_denied_extension<S: BaseStateWithExtensions<spl_token_2022::state::Mint>>(
+    state: &S,
+    extension: ExtensionType,
+) -> Result<()> {
+    match extension {
+        ExtensionType::PermanentDelegate => err!(VaultError::PermanentDelegateNotAllowed),
+        ExtensionType::NonTransferable => err!(VaultError::NonTransferableNotAllowed),
+        ExtensionType::TransferHook => err!(VaultError::TransferHookNotAllowed),
+        ExtensionType::TransferFeeConfig => err!(VaultError::TransferFeeNotAllowed),
+        ExtensionType::Pausable => err!(VaultError::PausableNotAllowed),
+        ExtensionType::DefaultAccountState => {
+            let default_state = state.get_extension::<DefaultAccountState>()?;
+            require!(
+                default_state.state != AccountState::Frozen as u8,
+                VaultError::DefaultAccountStateFrozen
+            );
+            Ok(())
+        }
+        _ => err!(VaultError::ExtensionDenied),
+    }
+}
+
+/// `transfer_checked` CPI that forwards `remaining_accounts` as transfer-hook accounts.
+///
+/// For mints with the TransferHook extension the caller passes the hook program,
+/// its `ExtraAccountMetaList` PDA and the accounts it lists; the extra metas are
+/// resolved from the list and appended to the CPI. Other mints ignore them.
+#[allow(clippy::too_many_arguments)]
+fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    spl_token_2022::onchain::invoke_transfer_checked(
+        token_program.key,
+        from,
+        mint.to_account_info(),
+        to,
+        authority,
+        remaining_accounts,
+        amount,
+        mint.decimals,
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// `emit_cpi!` for handlers that emit through [`emit_event!`]: invokes this
+/// program with the event tag and data, signed by the event authority.
+#[cfg(feature = "event-cpi")]
+fn emit_cpi_event<E: anchor_lang::Event>(event_authority: &AccountInfo, bump: u8, event: &E) -> Result<()> {
+    let data = [anchor_lang::event::EVENT_IX_TAG_LE, &event.data()].concat();
+    let ix = anchor_lang::solana_program::instruction::Instruction::new_with_bytes(
+        crate::ID,
+        &data,
+        vec![AccountMeta::new_readonly(event_authority.key(), true)],
+    );
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        std::slice::from_ref(event_authority),
+        &[&[b"__event_authority", &[bump]]],
+    )?;
+    Ok(())
+}
+
+/// Advances an account's event sequence, returning the number for the next event.
+///
+/// Every event about a vault, portfolio or the authority carries the account's
+/// sequence, so indexers can order events within a slot and spot missed ones.
+fn next_sequence(sequence: &mut u64) -> Result<u64> {
+    *sequence = sequence
+        .checked_add(1)
+        .ok_or(VaultError::Overflow)?;
+    Ok(*sequence)
+}
+
+/// Moves `amount` of a vault's available balance to locked, returning the event to emit
+#[allow(clippy::too_many_arguments)]
+fn lock_vault_collateral(
+    vault: &mut Account<CollateralVault>,
+    mint_stats: &mut Account<MintStats>,
+    integrator_stats: &mut Account<IntegratorStats>,
+    integrator_position: &mut Account<IntegratorPosition>,
+    caller: Pubkey,
+    amount: u64,
+    price: Option<OraclePrice>,
+    quote_value: Option<u64>,
+) -> Result<LockEvent> {
+    require!(amount > 0, VaultError::InvalidAmount);
+    
+    require!(
+        vault.available_balance >= amount,
+        VaultError::InsufficientAvailableBalance
+    );
+    
+    // Update vault state
+    vault.available_balance = vault.available_balance
+        .checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    vault.locked_balance = vault.locked_balance
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    mint_stats.record_lock(amount)?;
+    integrator_stats.record_lock(integrator_position, &vault.token_mint, amount)?;
+    
+    Ok(LockEvent {
+        vault: vault.key(),
+        token_mint: vault.token_mint,
+        amount,
+        total_balance: vault.total_balance,
+        locked_balance: vault.locked_balance,
+        available_balance: vault.available_balance,
+        caller,
+        price,
+        quote_value,
+        sequence: next_sequence(&mut vault.sequence)?,
+        timestamp: Clock::get()?.unix_timestamp,
+    })
+}
+
+/// Reads the price from an oracle account and checks it against the mint's config.
+fn read_oracle_price(
+    oracle: &AccountInfo,
+    oracle_config: &OracleConfig,
+    now: i64,
+) -> Result<OraclePrice> {
+    require_keys_eq!(oracle.key(), oracle_config.oracle, VaultError::InvalidOracle);
+    
+    let price = parse_oracle_account(oracle)?;
+    
+    require!(price.price > 0, VaultError::InvalidOraclePrice);
+    
+    let age = now.saturating_sub(price.publish_time);
+    require!(
+        age >= 0 && (age as u64) <= oracle_config.max_staleness,
+        VaultError::StaleOraclePrice
+    );
+    
+    let confidence_bps = (price.conf as u128)
+        .checked_mul(10_000)
+        .ok_or(VaultError::Overflow)?
+        / price.price as u128;
+    require!(
+        confidence_bps <= oracle_config.max_confidence_bps as u128,
+        VaultError::OracleConfidenceTooWide
+    );
+    
+    Ok(price)
+}
+
+/// Decodes an oracle account into an `OraclePrice`.
+///
+/// Accepts a fully verified Pyth `PriceUpdateV2` owned by the Pyth receiver
+/// program. Builds with `mock-oracle` also accept this program's `MockPriceFeed`.
+fn parse_oracle_account(oracle: &AccountInfo) -> Result<OraclePrice> {
+    let data = oracle.try_borrow_data()?;
+    
+    #[cfg(feature = "mock-oracle")]
+    if *oracle.owner == crate::ID {
+        let feed = MockPriceFeed::try_deserialize(&mut &data[..])
+            .map_err(|_| error!(VaultError::InvalidOracle))?;
+        return Ok(feed.price);
+    }
+    
+    require_keys_eq!(*oracle.owner, PYTH_RECEIVER_ID, VaultError::InvalidOracle);
+    require!(
+        data.len() >= 8 && data[..8] == PythPriceUpdate::DISCRIMINATOR,
+        VaultError::InvalidOracle
+    );
+    let update = PythPriceUpdate::deserialize(&mut &data[8..])
+        .map_err(|_| error!(VaultError::InvalidOracle))?;
+    
+    // Partially verified updates were checked against fewer guardian signatures
+    require!(
+        update.verification_level == PythVerificationLevel::Full,
+        VaultError::InvalidOracle
+    );
+    
+    let message = update.price_message;
+    Ok(OraclePrice {
+        price: message.price,
+        conf: message.conf,
+        expo: message.exponent,
+        publish_time: message.publish_time,
+    })
+}
+
+/// Scales `value` by 10^`exponent`, dividing when the exponent is negative.
+fn scale_by_exponent(value: u128, exponent: i32) -> Result<u128> {
+    let factor = 10u128
+        .checked_pow(exponent.unsigned_abs())
+        .ok_or(VaultError::Overflow)?;
+    if exponent >= 0 {
+        Ok(value.checked_mul(factor).ok_or(VaultError::Overflow)?)
+    } else {
+        Ok(value / factor)
+    }
+}
+
+/// Haircut-adjusted value of `amount` token units in quote units (`QUOTE_DECIMALS`).
+fn collateral_value(
+    amount: u64,
+    price: &OraclePrice,
+    mint_decimals: u8,
+    haircut_bps: u16,
+) -> Result<u64> {
+    let raw = (amount as u128)
+        .checked_mul(price.price as u128)
+        .ok_or(VaultError::Overflow)?;
+    let exponent = price.expo + QUOTE_DECIMALS as i32 - mint_decimals as i32;
+    let value = scale_by_exponent(raw, exponent)?;
+    let adjusted = value
+        .checked_mul((10_000 - haircut_bps) as u128)
+        .ok_or(VaultError::Overflow)?
+        / 10_000;
+    
+    u64::try_from(adjusted).map_err(|_| error!(VaultError::Overflow))
+}
+
+/// Token units needed to cover `value` quote units after the haircut, rounded up.
+fn token_amount_for_value(
+    value: u64,
+    price: &OraclePrice,
+    mint_decimals: u8,
+    haircut_bps: u16,
+) -> Result<u64> {
+    let exponent = mint_decimals as i32 - price.expo - QUOTE_DECIMALS as i32;
+    let mut numerator = (value as u128)
+        .checked_mul(10_000)
+        .ok_or(VaultError::Overflow)?;
+    let mut denominator = (price.price as u128)
+        .checked_mul((10_000 - haircut_bps) as u128)
+        .ok_or(VaultError::Overflow)?;
+    
+    if exponent >= 0 {
+        numerator = scale_by_exponent(numerator, exponent)?;
+    } else {
+        denominator = scale_by_exponent(denominator, -exponent)?;
+    }
+    
+    let amount = numerator.div_ceil(denominator);
+    u64::try_from(amount).map_err(|_| error!(VaultError::Overflow))
+}
+
+/// Prices one leg of a value-weighted basket from its `[token_mint, oracle_config, oracle]`
+/// accounts, returning the oracle price and the token amount covering `value`
+fn price_portfolio_leg<'info>(
+    token_mint: Pubkey,
+    value: u64,
+    accounts: &'info [AccountInfo<'info>],
+    now: i64,
+) -> Result<(OraclePrice, u64)> {
+    let [mint_info, oracle_config_info, oracle_info] = accounts else {
+        return err!(VaultError::InvalidBasket);
+    };
+    require_keys_eq!(mint_info.key(), token_mint, VaultError::InvalidTokenMint);
+    
+    let mint = InterfaceAccount::<Mint>::try_from(mint_info)?;
+    let oracle_config = Account::<OracleConfig>::try_from(oracle_config_info)?;
+    require_keys_eq!(oracle_config.token_mint, token_mint, VaultError::InvalidTokenMint);
+    
+    let price = read_oracle_price(oracle_info, &oracle_config, now)?;
+    let amount = token_amount_for_value(
+        value,
+        &price,
+        mint.decimals,
+        oracle_config.haircut_bps,
+    )?;
+    
+    Ok((price, amount))
+}
+
+/// Loads a mint's `MintStats` passed as a remaining account, checking what a
+/// typed `seeds = [b"mint_stats", mint]` account would: owner, discriminator
+/// and the PDA address
+fn load_mint_stats<'info>(
+    info: &'info AccountInfo<'info>,
+    token_mint: &Pubkey,
+) -> Result<Account<'info, MintStats>> {
+    require!(info.is_writable, ErrorCode::ConstraintMut);
+    let mint_stats = Account::<MintStats>::try_from(info)?;
+    let address = Pubkey::create_program_address(
+        &[b"mint_stats", token_mint.as_ref(), &[mint_stats.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| ErrorCode::ConstraintSeeds)?;
+    require_keys_eq!(address, info.key(), ErrorCode::ConstraintSeeds);
+    require_keys_eq!(mint_stats.token_mint, *token_mint, VaultError::InvalidTokenMint);
+    Ok(mint_stats)
+}
+
+/// Locks `amount` of one portfolio mint, returning the event to emit
+#[allow(clippy::too_many_arguments)]
+fn lock_portfolio_leg<'info>(
+    portfolio_vault: &mut Account<PortfolioVault>,
+    mint_stats: &'info AccountInfo<'info>,
+    integrator_stats: &mut Account<IntegratorStats>,
+    integrator_position: &mut Account<IntegratorPosition>,
+    caller: Pubkey,
+    token_mint: Pubkey,
+    amount: u64,
+    price: Option<OraclePrice>,
+    quote_value: Option<u64>,
+) -> Result<PortfolioLockEvent> {
+    require!(amount > 0, VaultError::InvalidAmount);
+    
+    let portfolio_key = portfolio_vault.key();
+    let balance = portfolio_vault.balance_mut(&token_mint)?;
+    
+    require!(
+        balance.available_balance >= amount,
+        VaultError::InsufficientAvailableBalance
+    );
+    
+    balance.available_balance = balance.available_balance
+        .checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    balance.locked_balance = balance.locked_balance
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    let (total_balance, locked_balance, available_balance) =
+        (balance.total_balance, balance.locked_balance, balance.available_balance);
+    
+    let mut mint_stats = load_mint_stats(mint_stats, &token_mint)?;
+    mint_stats.record_lock(amount)?;
+    mint_stats.exit(&crate::ID)?;
+    integrator_stats.record_lock(integrator_position, &token_mint, amount)?;
+    
+    Ok(PortfolioLockEvent {
+        portfolio_vault: portfolio_key,
+        token_mint,
+        amount,
+        total_balance,
+        locked_balance,
+        available_balance,
+        caller,
+        price,
+        quote_value,
+        sequence: next_sequence(&mut portfolio_vault.sequence)?,
+        timestamp: Clock::get()?.unix_timestamp,
+    })
+}
+
+/// Unlocks `amount` of one portfolio mint, returning the event to emit
+#[allow(clippy::too_many_arguments)]
+fn unlock_portfolio_leg<'info>(
+    portfolio_vault: &mut Account<PortfolioVault>,
+    mint_stats: &'info AccountInfo<'info>,
+    integrator_stats: &mut Account<IntegratorStats>,
+    integrator_position: &mut Account<IntegratorPosition>,
+    caller: Pubkey,
+    token_mint: Pubkey,
+    amount: u64,
+) -> Result<PortfolioUnlockEvent> {
+    require!(amount > 0, VaultError::InvalidAmount);
+    
+    let portfolio_key = portfolio_vault.key();
+    let balance = portfolio_vault.balance_mut(&token_mint)?;
+    
+    require!(
+        balance.locked_balance >= amount,
+        VaultError::InsufficientLockedBalance
+    );
+    
+    balance.locked_balance = balance.locked_balance
+        .checked_sub(amount)
+        .ok_or(VaultError::Underflow)?;
+    balance.available_balance = balance.available_balance
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+    let (total_balance, locked_balance, available_balance) =
+        (balance.total_balance, balance.locked_balance, balance.available_balance);
+    
+    let mut mint_stats = load_mint_stats(mint_stats, &token_mint)?;
+    mint_stats.record_unlock(amount);
+    mint_stats.exit(&crate::ID)?;
+    integrator_stats.record_unlock(integrator_position, &token_mint, amount)?;
+    
+    Ok(PortfolioUnlockEvent {
+        portfolio_vault: portfolio_key,
+        token_mint,
+        amount,
+        total_balance,
+        locked_balance,
+        available_balance,
+        caller,
+        sequence: next_sequence(&mut portfolio_vault.sequence)?,
+        timestamp: Clock::get()?.unix_timestamp,
+    })
+}
+
+// ============ ACCOUNT STRUCTURES ============
+
+#[account]
+#[derive(InitSpace)]
+pub struct CollateralVault {
+    pub owner: Pubkey,              // 32 - Vault owner
+    pub token_account: Pubkey,      // 32 - Associated token account
+    pub total_balance: u64,         // 8 - Total balance in vault
+    pub locked_balance: u64,        // 8 - Collateral locked for positions
+    pub available_balance: u64,     // 8 - Available for withdrawal (total - locked)
+    pub total_deposited: u64,       // 8 - Lifetime deposits
+    pub total_withdrawn: u64,       // 8 - Lifetime withdrawals
+    pub created_at: i64,            // 8 - Unix timestamp of creation
+    pub bump: u8,                   // 1 - PDA bump seed
+    pub token_mint: Pubkey,         // 32 - Token mint address (USDT)
+    pub pooled: bool,               // 1 - Tokens held in the mint's custody pool
+    pub sub_account_balance: u64,   // 8 - Funds allocated to sub-accounts (not in total_balance)
+    pub version: u8,                // 1 - Layout version (`VAULT_VERSION`)
+    pub total_transferred_in: u64,  // 8 - Lifetime collateral received from other vaults
+    pub total_transferred_out: u64, // 8 - Lifetime collateral sent to other vaults
+    pub sequence: u64,              // 8 - Sequence number of the vault's latest event
+    pub partial_history: bool,      // 1 - Migrated from before version 2, transfers uncounted
+    pub total_seized: u64,          // 8 - Lifetime collateral seized from the vault
+    pub total_fees_paid: u64,       // 8 - Lifetime fees charged to the vault
+    pub unattributed_locked: u64,   // 8 - Locked before locks were charged to a program
+    pub reserved: [u8; 15],         // 15 - Spare bytes for future fields
+}
+
+/// Current `CollateralVault` layout version.
+///
+/// - 0: the original layout, ending at `token_mint`, with no version byte
+/// - 1: adds `pooled`, `sub_account_balance`, the version byte and reserved space
+/// - 2: adds the transfer, seizure and fee counters and `unattributed_locked`
+///   in what were reserved bytes
+///
+/// Every field past `token_mint` reads as zero on a vault that predates it, and
+/// `migrate_vault` grows older vaults to the current size before any other
+/// instruction can load them. The event sequence needs no new version: older
+/// vaults read it as zero and count on from there. Vaults migrated from before
+/// version 2 set `partial_history`, since their transfer counters start late,
+/// and carry their locks over as `unattributed_locked`, since no
+/// `IntegratorPosition` records which program made them.
+///
+/// No instruction seizes collateral or charges fees yet, so `total_seized` and
+/// `total_fees_paid` stay zero; they already count as outflows in
+/// `assert_invariants`, and one that does only has to update them.
+pub const VAULT_VERSION: u8 = 2;
+
+/// Size of a version-0 `CollateralVault` as first deployed: 8 discriminator + 145 data bytes
+pub const LEGACY_VAULT_SPACE: usize = 153;
+
+#[account]
+#[derive(InitSpace)]
+pub struct SubAccount {
+    pub vault: Pubkey,              // 32 - Parent vault
+    pub id: u8,                     // 1 - Sub-account index under the vault
+    pub total_balance: u64,         // 8 - Total balance allocated to this sub-account
+    pub locked_balance: u64,        // 8 - Collateral locked for this sub-account's positions
+    pub available_balance: u64,     // 8 - Free balance (total - locked)
+    pub created_at: i64,            // 8 - Unix timestamp of creation
+    pub bump: u8,                   // 1 - PDA bump seed
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CustodyPool {
+    pub token_mint: Pubkey,         // 32 - Mint held by this pool
+    pub custody_token_account: Pubkey, // 32 - Shared custody token account
+    pub total_balance: u64,         // 8 - Sum of pooled vault balances
+    pub vault_count: u32,           // 4 - Number of open pooled vaults
+    pub bump: u8,                   // 1 - PDA bump seed
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VaultAuthority {
+    pub admin: Pubkey,              // 32 - Authority admin
+    #[max_len(10)]
+    pub authorized_programs: Vec<Pubkey>, // 4 + (10 * 32) - Programs allowed to lock/unlock
+    pub bump: u8,                   // 1 - PDA bump seed
+    pub denied_extensions: u64,     // 8 - Bitmask of Token-2022 extensions rejected at vault creation
+    pub sequence: u64,              // 8 - Sequence number of the latest admin event
+    pub version: u8,                // 1 - Layout version (`AUTHORITY_VERSION`)
+}
+
+/// Current `VaultAuthority` layout:
+/// - 0: the original layout, ending at `bump`, with no version byte
+/// - 1: adds `denied_extensions`, `sequence` and the version byte
+///
+/// A version-0 authority with fewer than ten programs still deserializes, reading
+/// the new fields as zero, so vault creation and the admin instructions require
+/// version 1 rather than run with the deny-list off. Requiring it for every admin
+/// event also keeps `sequence` from counting in bytes the migration then drops:
+/// it starts at the migration event. Locks, unlocks and transfers only read
+/// `authorized_programs` and keep working until `migrate_authority` runs.
+pub const AUTHORITY_VERSION: u8 = 1;
+
+/// Size of a version-0 `VaultAuthority`: 8 discriminator + 357 data bytes
+pub const LEGACY_AUTHORITY_SPACE: usize = 365;
+
+/// Extensions that can break custody assumptions, denied by default
+pub const DEFAULT_DENIED_EXTENSIONS: u64 = (1 << ExtensionType::PermanentDelegate as u16)
+    | (1 << ExtensionType::NonTransferable as u16)
+    | (1 << ExtensionType::DefaultAccountState as u16)
+    | (1 << ExtensionType::TransferHook as u16)
+    | (1 << ExtensionType::TransferFeeConfig as u16)
+    | (1 << ExtensionType::Pausable as u16);
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct MintBalance {
+    pub mint: Pubkey,               // 32 - Collateral mint
+    pub token_account: Pubkey,      // 32 - Portfolio's token account for this mint
+    pub total_balance: u64,         // 8 - Total balance of this mint
+    pub locked_balance: u64,        // 8 - Collateral locked for positions
+    pub available_balance: u64,     // 8 - Available for withdrawal (total - locked)
+    pub total_deposited: u64,       // 8 - Lifetime deposits
+    pub total_withdrawn: u64,       // 8 - Lifetime withdrawals
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PortfolioVault {
+    pub owner: Pubkey,              // 32 - Vault owner
+    #[max_len(8)]
+    pub balances: Vec<MintBalance>, // 4 + (8 * 104) - Per-mint balances
+    pub created_at: i64,            // 8 - Unix timestamp of creation
+    pub bump: u8,                   // 1 - PDA bump seed
+    pub sequence: u64,              // 8 - Sequence number of the portfolio's latest event
+}
+
+impl PortfolioVault {
+    pub fn balance_mut(&mut self, mint: &Pubkey) -> Result<&mut MintBalance> {
+        self.balances
+            .iter_mut()
+            .find(|b| b.mint == *mint)
+            .ok_or_else(|| error!(VaultError::MintNotInPortfolio))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BasketLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Share of a value-weighted basket lock, in basis points of the locked value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BasketWeight {
+    pub mint: Pubkey,
+    pub weight_bps: u16,
+}
+
+/// Decimals of the quote unit used for collateral valuation (USD with 6 decimals)
+pub const QUOTE_DECIMALS: u8 = 6;
+
+/// Pyth Solana Receiver program, owner of `PriceUpdateV2` price accounts
+pub const PYTH_RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+#[account]
+#[derive(InitSpace)]
+pub struct OracleConfig {
+    pub token_mint: Pubkey,         // 32 - Mint being priced
+    pub oracle: Pubkey,             // 32 - Pyth price update account
+    pub max_staleness: u64,         // 8 - Max price age in seconds
+    pub max_confidence_bps: u16,    // 2 - Max confidence interval relative to price
+    pub haircut_bps: u16,           // 2 - Haircut applied to collateral value
+    pub bump: u8,                   // 1 - PDA bump seed
+}
+
+impl OracleConfig {
+    fn apply(&mut self, params: &OracleConfigParams) {
+        self.oracle = params.oracle;
+        self.max_staleness = params.max_staleness;
+        self.max_confidence_bps = params.max_confidence_bps;
+        self.haircut_bps = params.haircut_bps;
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OracleConfigParams {
+    pub oracle: Pubkey,
+    pub max_staleness: u64,
+    pub max_confidence_bps: u16,
+    pub haircut_bps: u16,
+}
+
+impl OracleConfigParams {
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.max_staleness > 0
+                && self.max_confidence_bps <= 10_000
+                && self.haircut_bps < 10_000,
+            VaultError::InvalidOracleConfig
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct OraclePrice {
+    pub price: i64,                 // 8 - Price mantissa
+    pub conf: u64,                  // 8 - Confidence interval (same exponent as price)
+    pub expo: i32,                  // 4 - Price exponent
+    pub publish_time: i64,          // 8 - Unix timestamp of publication
+}
+
+/// Pyth `PriceUpdateV2` account, as posted by the Pyth Solana Receiver
+/// (layout of `pyth-solana-receiver-sdk`, decoded without the dependency)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PythPriceUpdate {
+    pub write_authority: Pubkey,                    // 32 - Allowed to overwrite the update
+    pub verification_level: PythVerificationLevel,  // 1-2 - Wormhole signatures checked
+    pub price_message: PythPriceMessage,            // 84 - Price of one feed
+    pub posted_slot: u64,                           // 8 - Slot the update was posted in
+}
+
+impl PythPriceUpdate {
+    /// Anchor discriminator of `PriceUpdateV2`
+    pub const DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PythVerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PythPriceMessage {
+    pub feed_id: [u8; 32],          // 32 - Pyth price feed id
+    pub price: i64,                 // 8 - Price mantissa
+    pub conf: u64,                  // 8 - Confidence interval
+    pub exponent: i32,              // 4 - Price exponent
+    pub publish_time: i64,          // 8 - Unix timestamp of publication
+    pub prev_publish_time: i64,     // 8 - Publish time of the previous update
+    pub ema_price: i64,             // 8 - EMA price mantissa
+    pub ema_conf: u64,              // 8 - EMA confidence interval
+}
+
+#[cfg(feature = "mock-oracle")]
+#[account]
+#[derive(InitSpace)]
+pub struct MockPriceFeed {
+    pub price: OraclePrice,         // 28 - Latest published price
+    pub authority: Pubkey,          // 32 - Allowed to publish prices
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CollateralValue {
+    pub vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub total_balance: u64,
+    pub available_balance: u64,
+    pub price: i64,
+    pub expo: i32,
+    pub haircut_bps: u16,
+    pub total_value: u64,
+    pub available_value: u64,
+    pub quote_decimals: u8,
+    pub timestamp: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MintConfig {
+    pub token_mint: Pubkey,         // 32 - Registered mint
+    pub approved: bool,             // 1 - Accepted as collateral
+    pub allowed_extensions: u64,    // 8 - Bitmask of permitted Token-2022 extensions
+    pub allow_freeze_authority: bool, // 1 - Accept mints with a freeze authority
+    pub max_deposit_amount: u64,    // 8 - Max single deposit (0 = unlimited)
+    pub max_vault_balance: u64,     // 8 - Max balance held by one vault (0 = unlimited)
+    pub max_total_balance: u64,     // 8 - Per-mint TVL cap across all vaults (0 = unlimited)
+    pub bump: u8,                   // 1 - PDA bump seed
+}
+
+impl MintConfig {
+    fn apply(&mut self, params: &MintConfigParams) {
+        self.approved = params.approved;
+        self.allowed_extensions = params.allowed_extensions;
+        self.allow_freeze_authority = params.allow_freeze_authority;
+        self.max_deposit_amount = params.max_deposit_amount;
+        self.max_vault_balance = params.max_vault_balance;
+        self.max_total_balance = params.max_total_balance;
+    }
+    
+    fn check_deposit(&self, amount: u64) -> Result<()> {
+        require!(
+            self.max_deposit_amount == 0 || amount <= self.max_deposit_amount,
+            VaultError::DepositLimitExceeded
+        );
+        Ok(())
+    }
+    
+    /// Checks a vault's post-deposit balance against the per-vault cap
+    fn check_vault_balance(&self, balance: u64) -> Result<()> {
+        require!(
+            self.max_vault_balance == 0 || balance <= self.max_vault_balance,
+            VaultError::DepositCapExceeded
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MintConfigParams {
+    pub approved: bool,
+    pub allowed_extensions: u64,
+    pub allow_freeze_authority: bool,
+    pub max_deposit_amount: u64,
+    pub max_vault_balance: u64,
+    pub max_total_balance: u64,
+}
+
+/// Aggregate custody for one mint across every vault type
+#[account]
+#[derive(InitSpace)]
+pub struct MintStats {
+    pub token_mint: Pubkey,         // 32 - Tracked mint
+    pub total_balance: u64,         // 8 - Collateral currently held (TVL)
+    pub total_deposited: u64,       // 8 - Lifetime deposits
+    pub total_withdrawn: u64,       // 8 - Lifetime withdrawals
+    pub total_locked: u64,          // 8 - Collateral locked in vaults, sub-accounts and portfolios
+    pub vault_count: u32,           // 4 - Open `CollateralVault`s (standalone and pooled)
+    pub bump: u8,                   // 1 - PDA bump seed
+    pub unreconciled: bool,         // 1 - A counter floored at zero; figures undercount
+}
+
+impl MintStats {
+    /// Adds a deposit to the mint's TVL, enforcing the registry's TVL cap
+    fn record_deposit(&mut self, amount: u64, mint_config: &MintConfig) -> Result<()> {
+        self.total_balance = self.total_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        require!(
+            mint_config.max_total_balance == 0
+                || self.total_balance <= mint_config.max_total_balance,
+            VaultError::DepositCapExceeded
+        );
+        self.total_deposited = self.total_deposited
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+    
+    fn record_withdrawal(&mut self, amount: u64) -> Result<()> {
+        self.total_balance = self.floor_at_zero(self.total_balance.checked_sub(amount));
+        self.total_withdrawn = self.total_withdrawn
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+    
+    fn record_lock(&mut self, amount: u64) -> Result<()> {
+        self.total_locked = self.total_locked
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+    
+    fn record_unlock(&mut self, amount: u64) {
+        self.total_locked = self.floor_at_zero(self.total_locked.checked_sub(amount));
+    }
+    
+    fn record_vault_opened(&mut self) -> Result<()> {
+        self.vault_count = self.vault_count
+            .checked_add(1)
+            .ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+    
+    fn record_vault_closed(&mut self) {
+        self.vault_count = self.floor_at_zero(self.vault_count.checked_sub(1));
+    }
+    
+    /// Vaults opened before the mint was registered hold collateral these
+    /// counters never saw, so decrements floor at zero and flag the stats
+    /// instead of blocking the vault's withdrawal, unlock or close
+    fn floor_at_zero<T: Default>(&mut self, value: Option<T>) -> T {
+        value.unwrap_or_else(|| {
+            self.unreconciled = true;
+            T::default()
+        })
+    }
+}
+
+/// Snapshot of a mint's `MintStats` (view, returned via return data)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MintStatsView {
+    pub token_mint: Pubkey,
+    pub total_balance: u64,
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    pub total_locked: u64,
+    pub vault_count: u32,
+    pub unreconciled: bool,
+    pub timestamp: i64,
+}
+
+/// Aggregate exposure of one authorized program across all vaults.
+///
+/// The program's first lock, unlock or transfer creates it without a cap, paid
+/// by the program's signer; `initialize_integrator_stats` creates it up front
+/// with one.
+#[account]
+#[derive(InitSpace)]
+pub struct IntegratorStats {
+    pub program: Pubkey,            // 32 - Authorized program (CPI signer)
+    pub locked_balance: u64,        // 8 - Collateral currently locked by this program
+    pub total_locked: u64,          // 8 - Lifetime locked
+    pub total_transferred: u64,     // 8 - Lifetime transferred between vaults
+    pub max_locked: u64,            // 8 - Cap on `locked_balance` (0 = unlimited)
+    pub bump: u8,                   // 1 - PDA bump seed
+}
+
+impl IntegratorStats {
+    /// Stamps the program on counters created by `init_if_needed`; the seeds
+    /// already pin it, so this is a no-op on later calls
+    fn open(&mut self, program: Pubkey, bump: u8) {
+        self.program = program;
+        self.bump = bump;
+    }
+    
+    /// Charges the lock to the program's `position` on the holder as well
+    fn record_lock(
+        &mut self,
+        position: &mut IntegratorPosition,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        position.record_lock(mint, amount)?;
+        self.locked_balance = self.locked_balance
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        require!(
+            self.max_locked == 0 || self.locked_balance <= self.max_locked,
+            VaultError::IntegratorCapExceeded
+        );
+        self.total_locked = self.total_locked
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+    
+    /// Releases locks this program made on the holder. Every lock in
+    /// `locked_balance` is also in one of its positions, so neither can underflow
+    /// once the position covers the unlock
+    fn record_unlock(
+        &mut self,
+        position: &mut IntegratorPosition,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        position.record_unlock(mint, amount)?;
+        self.locked_balance = self.locked_balance
+            .checked_sub(amount)
+            .ok_or(VaultError::Underflow)?;
+        Ok(())
+    }
+    
+    fn record_transfer(&mut self, amount: u64) -> Result<()> {
+        self.total_transferred = self.total_transferred
+            .checked_add(amount)
+            .ok_or(VaultError::Overflow)?;
+        Ok(())
+    }
+}
+
+/// Collateral one authorized program has locked in one vault, sub-account or
+/// portfolio. An unlock is charged to the caller's position, so a program can
+/// only release what it locked itself.
+///
+/// Transfers move available collateral only and never touch a position.
+#[account]
+#[derive(InitSpace)]
+pub struct IntegratorPosition {
+    pub program: Pubkey,            // 32 - Authorized program (CPI signer)
+    pub holder: Pubkey,             // 32 - Vault, sub-account or portfolio holding the locks
+    #[max_len(8)]
+    pub locks: Vec<PositionLock>,   // 4 + (8 * 40) - Collateral locked per mint
+    pub bump: u8,                   // 1 - PDA bump seed
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PositionLock {
+    pub mint: Pubkey,               // 32 - Collateral mint
+    pub amount: u64,                // 8 - Amount of it locked by the program
+}
+
+impl IntegratorPosition {
+    /// Stamps the owner on a position created by `init_if_needed`; the seeds
+    /// already pin both keys, so this is a no-op on later calls
+    fn open(&mut self, program: Pubkey, holder: Pubkey, bump: u8) {
+        self.program = program;
+        self.holder = holder;
+        self.bump = bump;
+    }
+    
+    pub fn locked(&self, mint: &Pubkey) -> u64 {
+        self.locks
+            .iter()
+            .find(|l| l.mint == *mint)
+            .map_or(0, |l| l.amount)
+    }
+    
+    fn record_lock(&mut self, mint: &Pubkey, amount: u64) -> Result<()> {
+        match self.locks.iter_mut().find(|l| l.mint == *mint) {
+            Some(lock) => {
+                lock.amount = lock.amount
+                    .checked_add(amount)
+                    .ok_or(VaultError::Overflow)?;
+            }
+            None => {
+                // A portfolio holds at most 8 mints, and emptied entries are dropped
+                require!(self.locks.len() < 8, VaultError::Overflow);
+                self.locks.push(PositionLock { mint: *mint, amount });
+            }
+        }
+        Ok(())
+    }
+    
+    fn record_unlock(&mut self, mint: &Pubkey, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let index = self.locks
+            .iter()
+            .position(|l| l.mint == *mint)
+            .ok_or(VaultError::LockNotHeld)?;
+        let lock = &mut self.locks[index];
+        lock.amount = lock.amount
+            .checked_sub(amount)
+            .ok_or(VaultError::LockNotHeld)?;
+        if lock.amount == 0 {
+            self.locks.swap_remove(index);
+        }
+        Ok(())
+    }
+}
+
+/// Zero-copy `VaultAuthority`, read in place instead of Borsh-deserialized.
+/// Experimental: only the `_zc` instructions use it, with the `zero-copy` feature
+#[cfg(feature = "zero-copy")]
+#[account(zero_copy)]
+pub struct VaultAuthorityZc {
+    pub admin: Pubkey,                      // 32 - Authority admin
+    pub authorized_programs: [Pubkey; 10],  // 320 - Programs allowed to lock/unlock (first `program_count` used)
+    pub denied_extensions: u64,             // 8 - Bitmask of Token-2022 extensions rejected at vault creation
+    pub program_count: u8,                  // 1 - Used entries in `authorized_programs`
+    pub bump: u8,                           // 1 - PDA bump seed
+    pub padding: [u8; 6],                   // 6 - Alignment
+    pub sequence: u64,                      // 8 - Sequence number of the latest admin event
+}
+
+#[cfg(feature = "zero-copy")]
+impl VaultAuthorityZc {
+    pub fn authorized_programs(&self) -> &[Pubkey] {
+        &self.authorized_programs[..self.program_count as usize]
+    }
+    
+    pub fn is_authorized(&self, program: &Pubkey) -> bool {
+        self.authorized_programs().contains(program)
+    }
+}
+
+/// Zero-copy `CollateralVault` (no pooled custody, sub-accounts or transfers).
+/// Experimental: only the `_zc` instructions use it, with the `zero-copy` feature
+#[cfg(feature = "zero-copy")]
+#[account(zero_copy)]
+pub struct CollateralVaultZc {
+    pub owner: Pubkey,              // 32 - Vault owner
+    pub token_account: Pubkey,      // 32 - Associated token account
+    pub token_mint: Pubkey,         // 32 - Token mint address
+    pub total_balance: u64,         // 8 - Total balance in vault
+    pub locked_balance: u64,        // 8 - Collateral locked for positions
+    pub available_balance: u64,     // 8 - Available for withdrawal (total - locked)
+    pub total_deposited: u64,       // 8 - Lifetime deposits
+    pub total_withdrawn: u64,       // 8 - Lifetime withdrawals
+    pub created_at: i64,            // 8 - Unix timestamp of creation
+    pub bump: u8,                   // 1 - PDA bump seed
+    pub version: u8,                // 1 - Layout version (`ZERO_COPY_VERSION`)
+    pub padding: [u8; 6],           // 6 - Alignment
+    pub sequence: u64,              // 8 - Sequence number of the vault's latest event
+    pub reserved: [u8; 56],         // 56 - Spare bytes for future fields
+}
+
+/// Layout version of `CollateralVaultZc`, independent of `VAULT_VERSION`
+#[cfg(feature = "zero-copy")]
+pub const ZERO_COPY_VERSION: u8 = 1;
+
+/// Layout version of the view return structs; bumped on any field change.
+/// New fields are only ever appended so older decoders keep working.
+pub const VIEW_VERSION: u8 = 3;
+
+/// Returned by `get_vault_balances`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VaultBalances {
+    pub version: u8,
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub token_mint: Pubkey,
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub sub_account_balance: u64,
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    pub pooled: bool,
+    pub total_transferred_in: u64,
+    pub total_transferred_out: u64,
+    pub total_seized: u64,
+    pub total_fees_paid: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SubAccountLock {
+    pub id: u8,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+}
+
+/// Returned by `get_lock_positions`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LockPositions {
+    pub version: u8,
+    pub vault: Pubkey,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub sub_accounts: Vec<SubAccountLock>,
+}
+
+/// Returned by `get_authority_config`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AuthorityConfig {
+    pub version: u8,
+    pub admin: Pubkey,
+    pub authorized_programs: Vec<Pubkey>,
+    pub denied_extensions: u64,
+}
+
+// ============ CONTEXTS ============
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializeAuthority<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + VaultAuthority::INIT_SPACE,
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializeVault<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        seeds = [b"mint_config", token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.approved @ VaultError::MintNotApproved,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + CollateralVault::INIT_SPACE,
+        seeds = [b"vault", user.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+        constraint = !vault.pooled @ VaultError::PooledVault,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mint::token_program = token_program,
+        constraint = token_mint.key() == vault.token_mint @ VaultError::InvalidTokenMint,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"mint_config", token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.approved @ VaultError::MintNotApproved,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        mut,
+        token::mint = vault.token_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = vault.token_mint,
+        token::authority = vault,
+        token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct LockCollateral<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", vault.token_mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorPosition::INIT_SPACE,
+        seeds = [b"integrator_position", signer.key().as_ref(), vault.key().as_ref()],
+        bump,
+    )]
+    pub integrator_position: Account<'info, IntegratorPosition>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UnlockCollateral<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", vault.token_mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorPosition::INIT_SPACE,
+        seeds = [b"integrator_position", signer.key().as_ref(), vault.key().as_ref()],
+        bump,
+    )]
+    pub integrator_position: Account<'info, IntegratorPosition>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+        constraint = !vault.pooled @ VaultError::PooledVault,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mint::token_program = token_program,
+        constraint = token_mint.key() == vault.token_mint @ VaultError::InvalidTokenMint,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        mut,
+        token::mint = vault.token_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = vault.token_mint,
+        token::authority = vault,
+        token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct TransferCollateral<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", from_vault.owner.as_ref()],
+        bump = from_vault.bump,
+        constraint = !from_vault.pooled @ VaultError::PooledVault,
+    )]
+    pub from_vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", to_vault.owner.as_ref()],
+        bump = to_vault.bump,
+        constraint = !to_vault.pooled @ VaultError::PooledVault,
+        constraint = to_vault.token_mint == from_vault.token_mint @ VaultError::InvalidTokenMint,
+        constraint = to_vault.key() != from_vault.key() @ VaultError::SameVault,
+    )]
+    pub to_vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        mint::token_program = token_program,
+        constraint = token_mint.key() == from_vault.token_mint @ VaultError::InvalidTokenMint,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        address = from_vault.token_account,
+        token::mint = token_mint,
+        token::authority = from_vault,
+        token::token_program = token_program,
+    )]
+    pub from_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        address = to_vault.token_account,
+        token::mint = token_mint,
+        token::authority = to_vault,
+        token::token_program = token_program,
+    )]
+    pub to_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+        close = user
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        address = vault.token_account,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", vault.token_mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    /// Required when closing a pooled vault
+    #[account(
+        mut,
+        seeds = [b"custody_pool", vault.token_mint.as_ref()],
+        bump = custody_pool.bump,
+    )]
+    pub custody_pool: Option<Account<'info, CustodyPool>>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateAuthority<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.admin == admin.key() @ VaultError::Unauthorized,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializeCustodyPool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.admin == admin.key() @ VaultError::Unauthorized,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + CustodyPool::INIT_SPACE,
+        seeds = [b"custody_pool", token_mint.key().as_ref()],
+        bump
+    )]
+    pub custody_pool: Account<'info, CustodyPool>,
+    
+    #[account(
+        init,
+        payer = admin,
+        associated_token::mint = token_mint,
+        associated_token::authority = custody_pool,
+        associated_token::token_program = token_program,
+    )]
+    pub custody_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializePooledVault<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        seeds = [b"mint_config", token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.approved @ VaultError::MintNotApproved,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        mut,
+        seeds = [b"custody_pool", token_mint.key().as_ref()],
+        bump = custody_pool.bump,
+    )]
+    pub custody_pool: Account<'info, CustodyPool>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + CollateralVault::INIT_SPACE,
+        seeds = [b"vault", user.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct DepositPooled<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+        constraint = vault.pooled @ VaultError::NotPooledVault,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"custody_pool", vault.token_mint.as_ref()],
+        bump = custody_pool.bump,
+    )]
+    pub custody_pool: Account<'info, CustodyPool>,
+    
+    #[account(
+        mint::token_program = token_program,
+        constraint = token_mint.key() == vault.token_mint @ VaultError::InvalidTokenMint,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"mint_config", token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.approved @ VaultError::MintNotApproved,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        mut,
+        token::mint = vault.token_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        address = custody_pool.custody_token_account,
+    )]
+    pub custody_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct WithdrawPooled<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+        constraint = vault.pooled @ VaultError::NotPooledVault,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"custody_pool", vault.token_mint.as_ref()],
+        bump = custody_pool.bump,
+    )]
+    pub custody_pool: Account<'info, CustodyPool>,
+    
+    #[account(
+        mint::token_program = token_program,
+        constraint = token_mint.key() == vault.token_mint @ VaultError::InvalidTokenMint,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        mut,
+        token::mint = vault.token_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        address = custody_pool.custody_token_account,
+    )]
+    pub custody_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct TransferCollateralInternal<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", from_vault.owner.as_ref()],
+        bump = from_vault.bump,
+        constraint = from_vault.pooled @ VaultError::NotPooledVault,
+    )]
+    pub from_vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", to_vault.owner.as_ref()],
+        bump = to_vault.bump,
+        constraint = to_vault.pooled @ VaultError::NotPooledVault,
+        constraint = to_vault.token_mint == from_vault.token_mint @ VaultError::InvalidTokenMint,
+        constraint = to_vault.key() != from_vault.key() @ VaultError::SameVault,
+    )]
+    pub to_vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct VerifyCustodyPool<'info> {
+    #[account(
+        seeds = [b"custody_pool", custody_pool.token_mint.as_ref()],
+        bump = custody_pool.bump,
+    )]
+    pub custody_pool: Account<'info, CustodyPool>,
+    
+    #[account(
+        address = custody_pool.custody_token_account,
+    )]
+    pub custody_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+#[instruction(id: u8)]
+pub struct CreateSubAccount<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+        constraint = !vault.pooled @ VaultError::PooledVault,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + SubAccount::INIT_SPACE,
+        seeds = [b"sub_account", vault.key().as_ref(), &[id]],
+        bump
+    )]
+    pub sub_account: Account<'info, SubAccount>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ManageSubAccount<'info> {
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+        constraint = !vault.pooled @ VaultError::PooledVault,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"sub_account", vault.key().as_ref(), &[sub_account.id]],
+        bump = sub_account.bump,
+    )]
+    pub sub_account: Account<'info, SubAccount>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct MoveBetweenSubAccounts<'info> {
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"sub_account", vault.key().as_ref(), &[from_sub_account.id]],
+        bump = from_sub_account.bump,
+    )]
+    pub from_sub_account: Account<'info, SubAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"sub_account", vault.key().as_ref(), &[to_sub_account.id]],
+        bump = to_sub_account.bump,
+        constraint = to_sub_account.key() != from_sub_account.key() @ VaultError::SameVault,
+    )]
+    pub to_sub_account: Account<'info, SubAccount>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct LockSubAccountCollateral<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"sub_account", vault.key().as_ref(), &[sub_account.id]],
+        bump = sub_account.bump,
+    )]
+    pub sub_account: Account<'info, SubAccount>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", vault.token_mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorPosition::INIT_SPACE,
+        seeds = [b"integrator_position", signer.key().as_ref(), sub_account.key().as_ref()],
+        bump,
+    )]
+    pub integrator_position: Account<'info, IntegratorPosition>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UnlockSubAccountCollateral<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"sub_account", vault.key().as_ref(), &[sub_account.id]],
+        bump = sub_account.bump,
+    )]
+    pub sub_account: Account<'info, SubAccount>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", vault.token_mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        mut,
+        seeds = [b"integrator_position", signer.key().as_ref(), sub_account.key().as_ref()],
+        bump = integrator_position.bump,
+    )]
+    pub integrator_position: Account<'info, IntegratorPosition>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct TransferSubAccountCollateral<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", from_vault.owner.as_ref()],
+        bump = from_vault.bump,
+        constraint = !from_vault.pooled @ VaultError::PooledVault,
+    )]
+    pub from_vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"sub_account", from_vault.key().as_ref(), &[from_sub_account.id]],
+        bump = from_sub_account.bump,
+    )]
+    pub from_sub_account: Account<'info, SubAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", to_vault.owner.as_ref()],
+        bump = to_vault.bump,
+        constraint = !to_vault.pooled @ VaultError::PooledVault,
+        constraint = to_vault.token_mint == from_vault.token_mint @ VaultError::InvalidTokenMint,
+        constraint = to_vault.key() != from_vault.key() @ VaultError::SameVault,
+    )]
+    pub to_vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        mint::token_program = token_program,
+        constraint = token_mint.key() == from_vault.token_mint @ VaultError::InvalidTokenMint,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        address = from_vault.token_account,
+        token::mint = token_mint,
+        token::authority = from_vault,
+        token::token_program = token_program,
+    )]
+    pub from_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        address = to_vault.token_account,
+        token::mint = token_mint,
+        token::authority = to_vault,
+        token::token_program = token_program,
+    )]
+    pub to_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct CloseSubAccount<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", user.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        mut,
+        seeds = [b"sub_account", vault.key().as_ref(), &[sub_account.id]],
+        bump = sub_account.bump,
+        close = user
+    )]
+    pub sub_account: Account<'info, SubAccount>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializePortfolioVault<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + PortfolioVault::INIT_SPACE,
+        seeds = [b"portfolio", user.key().as_ref()],
+        bump
+    )]
+    pub portfolio_vault: Account<'info, PortfolioVault>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct AddPortfolioMint<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"portfolio", user.key().as_ref()],
+        bump = portfolio_vault.bump,
+        constraint = portfolio_vault.owner == user.key() @ VaultError::Unauthorized,
+    )]
+    pub portfolio_vault: Account<'info, PortfolioVault>,
+    
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        seeds = [b"mint_config", token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.approved @ VaultError::MintNotApproved,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = portfolio_vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct DepositPortfolio<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"portfolio", user.key().as_ref()],
+        bump = portfolio_vault.bump,
+        constraint = portfolio_vault.owner == user.key() @ VaultError::Unauthorized,
+    )]
+    pub portfolio_vault: Account<'info, PortfolioVault>,
+    
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"mint_config", token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.approved @ VaultError::MintNotApproved,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = portfolio_vault,
+        token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct WithdrawPortfolio<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"portfolio", user.key().as_ref()],
+        bump = portfolio_vault.bump,
+        constraint = portfolio_vault.owner == user.key() @ VaultError::Unauthorized,
+    )]
+    pub portfolio_vault: Account<'info, PortfolioVault>,
+    
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = user,
+        token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = portfolio_vault,
+        token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct LockPortfolioCollateral<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio_vault.owner.as_ref()],
+        bump = portfolio_vault.bump,
+    )]
+    pub portfolio_vault: Account<'info, PortfolioVault>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorPosition::INIT_SPACE,
+        seeds = [b"integrator_position", signer.key().as_ref(), portfolio_vault.key().as_ref()],
+        bump,
+    )]
+    pub integrator_position: Account<'info, IntegratorPosition>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UnlockPortfolioCollateral<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"portfolio", portfolio_vault.owner.as_ref()],
+        bump = portfolio_vault.bump,
+    )]
+    pub portfolio_vault: Account<'info, PortfolioVault>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        mut,
+        seeds = [b"integrator_position", signer.key().as_ref(), portfolio_vault.key().as_ref()],
+        bump = integrator_position.bump,
+    )]
+    pub integrator_position: Account<'info, IntegratorPosition>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct ClosePortfolioVault<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"portfolio", user.key().as_ref()],
+        bump = portfolio_vault.bump,
+        constraint = portfolio_vault.owner == user.key() @ VaultError::Unauthorized,
+        close = user
+    )]
+    pub portfolio_vault: Account<'info, PortfolioVault>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializeOracleConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.admin == admin.key() @ VaultError::Unauthorized,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + OracleConfig::INIT_SPACE,
+        seeds = [b"oracle_config", token_mint.key().as_ref()],
+        bump
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateOracleConfig<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.admin == admin.key() @ VaultError::Unauthorized,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        mut,
+        seeds = [b"oracle_config", oracle_config.token_mint.as_ref()],
+        bump = oracle_config.bump,
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+}
+
+#[derive(Accounts)]
+pub struct GetCollateralValue<'info> {
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        constraint = token_mint.key() == vault.token_mint @ VaultError::InvalidTokenMint,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"oracle_config", vault.token_mint.as_ref()],
+        bump = oracle_config.bump,
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+    
+    /// CHECK: address checked against `oracle_config.oracle`, layout parsed by `read_oracle_price`
+    pub oracle: UncheckedAccount<'info>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct LockValue<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+    
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", vault.token_mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorPosition::INIT_SPACE,
+        seeds = [b"integrator_position", signer.key().as_ref(), vault.key().as_ref()],
+        bump,
+    )]
+    pub integrator_position: Account<'info, IntegratorPosition>,
+    
+    #[account(
+        constraint = token_mint.key() == vault.token_mint @ VaultError::InvalidTokenMint,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"oracle_config", vault.token_mint.as_ref()],
+        bump = oracle_config.bump,
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+    
+    /// CHECK: address checked against `oracle_config.oracle`, layout parsed by `read_oracle_price`
+    pub oracle: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializeMintConfig<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub admin: Signer<'info>,
     
     #[account(
-        mint::token_program = token_program,
+        mut,
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.admin == admin.key() @ VaultError::Unauthorized,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
     )]
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
     pub token_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         init,
-        payer = user,
-        space = 8 + CollateralVault::INIT_SPACE,
-        seeds = [b"vault", user.key().as_ref()],
+        payer = admin,
+        space = 8 + MintConfig::INIT_SPACE,
+        seeds = [b"mint_config", token_mint.key().as_ref()],
         bump
     )]
-    pub vault: Account<'info, CollateralVault>,
+    pub mint_config: Account<'info, MintConfig>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + MintStats::INIT_SPACE,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UpdateMintConfig<'info> {
+    pub admin: Signer<'info>,
     
     #[account(
         mut,
-        token::mint = token_mint,
-        token::authority = user,
-        token::token_program = token_program,
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.admin == admin.key() @ VaultError::Unauthorized,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
     )]
-    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub vault_authority: Account<'info, VaultAuthority>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_config", mint_config.token_mint.as_ref()],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+// Named apart from the `program` account `#[event_cpi]` adds
+#[instruction(integrator: Pubkey)]
+pub struct InitializeIntegratorStats<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.admin == admin.key() @ VaultError::Unauthorized,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
+    )]
+    pub vault_authority: Account<'info, VaultAuthority>,
     
     #[account(
         init,
-        payer = user,
-        associated_token::mint = token_mint,
-        associated_token::authority = vault,
-        associated_token::token_program = token_program,
+        payer = admin,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", integrator.as_ref()],
+        bump
     )]
-    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub integrator_stats: Account<'info, IntegratorStats>,
     
-    pub token_program: Interface<'info, TokenInterface>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
-pub struct Deposit<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+pub struct SetIntegratorCap<'info> {
+    pub admin: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"vault", user.key().as_ref()],
-        bump = vault.bump,
-        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+        seeds = [b"vault_authority"],
+        bump = vault_authority.bump,
+        constraint = vault_authority.admin == admin.key() @ VaultError::Unauthorized,
+        constraint = vault_authority.version == AUTHORITY_VERSION 
+            @ VaultError::AuthorityNotMigrated,
     )]
-    pub vault: Account<'info, CollateralVault>,
+    pub vault_authority: Account<'info, VaultAuthority>,
     
     #[account(
-        mint::token_program = token_program,
-        constraint = token_mint.key() == vault.token_mint @ VaultError::InvalidTokenMint,
+        mut,
+        seeds = [b"integrator_stats", integrator_stats.program.as_ref()],
+        bump = integrator_stats.bump,
     )]
-    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub integrator_stats: Account<'info, IntegratorStats>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Only used to derive the vault address; matched against the stored owner
+    pub owner: UncheckedAccount<'info>,
     
+    /// CHECK: May still use a legacy layout that `Account<CollateralVault>` can't
+    /// deserialize; discriminator and owner are checked in `migrate_vault`
     #[account(
         mut,
-        token::mint = vault.token_mint,
-        token::authority = user,
-        token::token_program = token_program,
+        owner = crate::ID,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump,
     )]
-    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub vault: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct MigrateAuthority<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
     
+    /// CHECK: May still use the version-0 layout that `Account<VaultAuthority>` can't
+    /// always deserialize; the discriminator is checked in `migrate_authority`
     #[account(
         mut,
-        token::mint = vault.token_mint,
-        token::authority = vault,
-        token::token_program = token_program,
+        owner = crate::ID,
+        seeds = [b"vault_authority"],
+        bump,
     )]
-    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub vault_authority: UncheckedAccount<'info>,
     
-    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct LockCollateral<'info> {
-    pub signer: Signer<'info>,
+pub struct GetMintStats<'info> {
+    #[account(
+        seeds = [b"mint_stats", mint_stats.token_mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+}
+
+#[cfg(feature = "mock-oracle")]
+#[derive(Accounts)]
+pub struct InitializeMockPriceFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MockPriceFeed::INIT_SPACE,
+    )]
+    pub price_feed: Account<'info, MockPriceFeed>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(feature = "mock-oracle")]
+#[derive(Accounts)]
+pub struct SetMockPrice<'info> {
+    pub authority: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"vault", vault.owner.as_ref()],
-        bump = vault.bump,
+        has_one = authority @ VaultError::Unauthorized,
     )]
-    pub vault: Account<'info, CollateralVault>,
+    pub price_feed: Account<'info, MockPriceFeed>,
+}
+
+#[cfg(feature = "zero-copy")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializeAuthorityZc<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
     
     #[account(
-        seeds = [b"vault_authority"],
-        bump = vault_authority.bump,
-        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
-            @ VaultError::UnauthorizedProgram,
+        init,
+        payer = admin,
+        space = 8 + std::mem::size_of::<VaultAuthorityZc>(),
+        seeds = [b"vault_authority_zc"],
+        bump
     )]
-    pub vault_authority: Account<'info, VaultAuthority>,
+    pub vault_authority: AccountLoader<'info, VaultAuthorityZc>,
+    
+    pub system_program: Program<'info, System>,
 }
 
+#[cfg(feature = "zero-copy")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
-pub struct UnlockCollateral<'info> {
-    pub signer: Signer<'info>,
+pub struct UpdateAuthorityZc<'info> {
+    pub admin: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"vault", vault.owner.as_ref()],
-        bump = vault.bump,
+        seeds = [b"vault_authority_zc"],
+        bump = vault_authority.load()?.bump,
+        constraint = vault_authority.load()?.admin == admin.key() @ VaultError::Unauthorized,
     )]
-    pub vault: Account<'info, CollateralVault>,
+    pub vault_authority: AccountLoader<'info, VaultAuthorityZc>,
+}
+
+#[cfg(feature = "zero-copy")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct InitializeVaultZc<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
     
     #[account(
-        seeds = [b"vault_authority"],
-        bump = vault_authority.bump,
-        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
-            @ VaultError::UnauthorizedProgram,
+        mint::token_program = token_program,
     )]
-    pub vault_authority: Account<'info, VaultAuthority>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        seeds = [b"vault_authority_zc"],
+        bump = vault_authority.load()?.bump,
+    )]
+    pub vault_authority: AccountLoader<'info, VaultAuthorityZc>,
+    
+    #[account(
+        seeds = [b"mint_config", token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.approved @ VaultError::MintNotApproved,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<CollateralVaultZc>(),
+        seeds = [b"vault_zc", user.key().as_ref()],
+        bump
+    )]
+    pub vault: AccountLoader<'info, CollateralVaultZc>,
+    
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
+#[cfg(feature = "zero-copy")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
-pub struct Withdraw<'info> {
+pub struct DepositZc<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"vault", user.key().as_ref()],
-        bump = vault.bump,
-        constraint = vault.owner == user.key() @ VaultError::Unauthorized
+        seeds = [b"vault_zc", user.key().as_ref()],
+        bump = vault.load()?.bump,
     )]
-    pub vault: Account<'info, CollateralVault>,
+    pub vault: AccountLoader<'info, CollateralVaultZc>,
     
     #[account(
         mint::token_program = token_program,
-        constraint = token_mint.key() == vault.token_mint @ VaultError::InvalidTokenMint,
+        constraint = token_mint.key() == vault.load()?.token_mint @ VaultError::InvalidTokenMint,
     )]
     pub token_mint: InterfaceAccount<'info, Mint>,
     
+    #[account(
+        seeds = [b"mint_config", token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.approved @ VaultError::MintNotApproved,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    
     #[account(
         mut,
-        token::mint = vault.token_mint,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        mut,
+        token::mint = token_mint,
         token::authority = user,
         token::token_program = token_program,
     )]
@@ -561,96 +5302,206 @@ pub struct Withdraw<'info> {
     
     #[account(
         mut,
-        token::mint = vault.token_mint,
-        token::authority = vault,
-        token::token_program = token_program,
+        address = vault.load()?.token_account,
     )]
     pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
     
     pub token_program: Interface<'info, TokenInterface>,
 }
 
+#[cfg(feature = "zero-copy")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
-pub struct TransferCollateral<'info> {
+pub struct LockCollateralZc<'info> {
+    #[account(mut)]
     pub signer: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"vault", from_vault.owner.as_ref()],
-        bump = from_vault.bump,
+        seeds = [b"vault_zc", vault.load()?.owner.as_ref()],
+        bump = vault.load()?.bump,
     )]
-    pub from_vault: Account<'info, CollateralVault>,
+    pub vault: AccountLoader<'info, CollateralVaultZc>,
+    
+    #[account(
+        seeds = [b"vault_authority_zc"],
+        bump = vault_authority.load()?.bump,
+        constraint = vault_authority.load()?.is_authorized(&signer.key())
+            @ VaultError::UnauthorizedProgram,
+    )]
+    pub vault_authority: AccountLoader<'info, VaultAuthorityZc>,
     
     #[account(
         mut,
-        seeds = [b"vault", to_vault.owner.as_ref()],
-        bump = to_vault.bump,
+        seeds = [b"mint_stats", vault.load()?.token_mint.as_ref()],
+        bump = mint_stats.bump,
     )]
-    pub to_vault: Account<'info, CollateralVault>,
+    pub mint_stats: Account<'info, MintStats>,
     
     #[account(
-        seeds = [b"vault_authority"],
-        bump = vault_authority.bump,
-        constraint = vault_authority.authorized_programs.contains(&signer.key()) 
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorPosition::INIT_SPACE,
+        seeds = [b"integrator_position", signer.key().as_ref(), vault.key().as_ref()],
+        bump,
+    )]
+    pub integrator_position: Account<'info, IntegratorPosition>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(feature = "zero-copy")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct UnlockCollateralZc<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault_zc", vault.load()?.owner.as_ref()],
+        bump = vault.load()?.bump,
+    )]
+    pub vault: AccountLoader<'info, CollateralVaultZc>,
+    
+    #[account(
+        seeds = [b"vault_authority_zc"],
+        bump = vault_authority.load()?.bump,
+        constraint = vault_authority.load()?.is_authorized(&signer.key())
             @ VaultError::UnauthorizedProgram,
     )]
-    pub vault_authority: Account<'info, VaultAuthority>,
+    pub vault_authority: AccountLoader<'info, VaultAuthorityZc>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint_stats", vault.load()?.token_mint.as_ref()],
+        bump = mint_stats.bump,
+    )]
+    pub mint_stats: Account<'info, MintStats>,
+    
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + IntegratorStats::INIT_SPACE,
+        seeds = [b"integrator_stats", signer.key().as_ref()],
+        bump,
+    )]
+    pub integrator_stats: Account<'info, IntegratorStats>,
+    
+    #[account(
+        mut,
+        seeds = [b"integrator_position", signer.key().as_ref(), vault.key().as_ref()],
+        bump = integrator_position.bump,
+    )]
+    pub integrator_position: Account<'info, IntegratorPosition>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(feature = "zero-copy")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[derive(Accounts)]
+pub struct WithdrawZc<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault_zc", user.key().as_ref()],
+        bump = vault.load()?.bump,
+    )]
+    pub vault: AccountLoader<'info, CollateralVaultZc>,
     
     #[account(
         mint::token_program = token_program,
+        constraint = token_mint.key() == vault.load()?.token_mint @ VaultError::InvalidTokenMint,
     )]
     pub token_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
-        token::mint = token_mint,
-        token::authority = from_vault,
-        token::token_program = token_program,
+        seeds = [b"mint_stats", token_mint.key().as_ref()],
+        bump = mint_stats.bump,
     )]
-    pub from_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub mint_stats: Account<'info, MintStats>,
     
     #[account(
         mut,
         token::mint = token_mint,
-        token::authority = to_vault,
+        token::authority = user,
         token::token_program = token_program,
     )]
-    pub to_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        address = vault.load()?.token_account,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
     
     pub token_program: Interface<'info, TokenInterface>,
 }
 
+#[cfg(feature = "zero-copy")]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
 #[derive(Accounts)]
-pub struct CloseVault<'info> {
+pub struct CloseVaultZc<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"vault", user.key().as_ref()],
-        bump = vault.bump,
-        constraint = vault.owner == user.key() @ VaultError::Unauthorized,
+        seeds = [b"vault_zc", user.key().as_ref()],
+        bump = vault.load()?.bump,
         close = user
     )]
-    pub vault: Account<'info, CollateralVault>,
+    pub vault: AccountLoader<'info, CollateralVaultZc>,
     
     #[account(
         mut,
-        token::mint = vault.token_mint,
-        token::authority = vault,
+        seeds = [b"mint_stats", vault.load()?.token_mint.as_ref()],
+        bump = mint_stats.bump,
     )]
-    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub mint_stats: Account<'info, MintStats>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateAuthority<'info> {
-    pub admin: Signer<'info>,
+pub struct GetVaultBalances<'info> {
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
+}
+
+#[derive(Accounts)]
+pub struct AssertInvariants<'info> {
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, CollateralVault>,
     
     #[account(
-        mut,
+        address = vault.token_account,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct GetAuthorityConfig<'info> {
+    #[account(
         seeds = [b"vault_authority"],
         bump = vault_authority.bump,
-        constraint = vault_authority.admin == admin.key() @ VaultError::Unauthorized,
     )]
     pub vault_authority: Account<'info, VaultAuthority>,
 }
@@ -662,6 +5513,7 @@ pub struct VaultInitialized {
     pub user: Pubkey,
     pub vault: Pubkey,
     pub token_mint: Pubkey,
+    pub sequence: u64,
     pub timestamp: i64,
 }
 
@@ -669,8 +5521,12 @@ pub struct VaultInitialized {
 pub struct DepositEvent {
     pub user: Pubkey,
     pub vault: Pubkey,
+    pub token_mint: Pubkey,
     pub amount: u64,
     pub new_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub sequence: u64,
     pub timestamp: i64,
 }
 
@@ -678,28 +5534,40 @@ pub struct DepositEvent {
 pub struct WithdrawEvent {
     pub user: Pubkey,
     pub vault: Pubkey,
+    pub token_mint: Pubkey,
     pub amount: u64,
     pub new_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub sequence: u64,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct LockEvent {
     pub vault: Pubkey,
+    pub token_mint: Pubkey,
     pub amount: u64,
+    pub total_balance: u64,
     pub locked_balance: u64,
     pub available_balance: u64,
     pub caller: Pubkey,
+    pub price: Option<OraclePrice>,
+    pub quote_value: Option<u64>,
+    pub sequence: u64,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct UnlockEvent {
     pub vault: Pubkey,
+    pub token_mint: Pubkey,
     pub amount: u64,
+    pub total_balance: u64,
     pub locked_balance: u64,
     pub available_balance: u64,
     pub caller: Pubkey,
+    pub sequence: u64,
     pub timestamp: i64,
 }
 
@@ -707,8 +5575,17 @@ pub struct UnlockEvent {
 pub struct TransferEvent {
     pub from_vault: Pubkey,
     pub to_vault: Pubkey,
+    pub token_mint: Pubkey,
     pub amount: u64,
+    pub from_total_balance: u64,
+    pub from_locked_balance: u64,
+    pub from_available_balance: u64,
+    pub to_total_balance: u64,
+    pub to_locked_balance: u64,
+    pub to_available_balance: u64,
     pub caller: Pubkey,
+    pub from_sequence: u64,
+    pub to_sequence: u64,
     pub timestamp: i64,
 }
 
@@ -716,6 +5593,31 @@ pub struct TransferEvent {
 pub struct VaultClosed {
     pub user: Pubkey,
     pub vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultMigrated {
+    pub vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityMigrated {
+    pub authority: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub denied_extensions: u64,
+    pub sequence: u64,
     pub timestamp: i64,
 }
 
@@ -724,6 +5626,7 @@ pub struct AuthorityInitialized {
     pub admin: Pubkey,
     pub authority: Pubkey,
     pub authorized_programs: Vec<Pubkey>,
+    pub sequence: u64,
     pub timestamp: i64,
 }
 
@@ -731,6 +5634,7 @@ pub struct AuthorityInitialized {
 pub struct ProgramAuthorized {
     pub admin: Pubkey,
     pub program: Pubkey,
+    pub sequence: u64,
     pub timestamp: i64,
 }
 
@@ -738,6 +5642,267 @@ pub struct ProgramAuthorized {
 pub struct ProgramDeauthorized {
     pub admin: Pubkey,
     pub program: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CustodyPoolInitialized {
+    pub admin: Pubkey,
+    pub custody_pool: Pubkey,
+    pub token_mint: Pubkey,
+    pub custody_token_account: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CustodyPoolVerified {
+    pub custody_pool: Pubkey,
+    pub token_mint: Pubkey,
+    pub total_balance: u64,
+    pub vault_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubAccountCreated {
+    pub vault: Pubkey,
+    pub sub_account: Pubkey,
+    pub token_mint: Pubkey,
+    pub id: u8,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubAccountAllocated {
+    pub vault: Pubkey,
+    pub sub_account: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub sub_account_balance: u64,
+    pub sub_account_locked_balance: u64,
+    pub sub_account_available_balance: u64,
+    pub vault_total_balance: u64,
+    pub vault_locked_balance: u64,
+    pub vault_available_balance: u64,
+    pub vault_sub_account_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubAccountReleased {
+    pub vault: Pubkey,
+    pub sub_account: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub sub_account_balance: u64,
+    pub sub_account_locked_balance: u64,
+    pub sub_account_available_balance: u64,
+    pub vault_total_balance: u64,
+    pub vault_locked_balance: u64,
+    pub vault_available_balance: u64,
+    pub vault_sub_account_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubAccountMoved {
+    pub vault: Pubkey,
+    pub from_sub_account: Pubkey,
+    pub to_sub_account: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub from_total_balance: u64,
+    pub from_locked_balance: u64,
+    pub from_available_balance: u64,
+    pub to_total_balance: u64,
+    pub to_locked_balance: u64,
+    pub to_available_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubAccountLockEvent {
+    pub vault: Pubkey,
+    pub sub_account: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub caller: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubAccountUnlockEvent {
+    pub vault: Pubkey,
+    pub sub_account: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub caller: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubAccountTransferEvent {
+    pub from_vault: Pubkey,
+    pub from_sub_account: Pubkey,
+    pub to_vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub from_total_balance: u64,
+    pub from_locked_balance: u64,
+    pub from_available_balance: u64,
+    pub from_vault_sub_account_balance: u64,
+    pub to_total_balance: u64,
+    pub to_locked_balance: u64,
+    pub to_available_balance: u64,
+    pub caller: Pubkey,
+    pub from_sequence: u64,
+    pub to_sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubAccountClosed {
+    pub vault: Pubkey,
+    pub sub_account: Pubkey,
+    pub token_mint: Pubkey,
+    pub id: u8,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioVaultInitialized {
+    pub user: Pubkey,
+    pub portfolio_vault: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioVaultClosed {
+    pub user: Pubkey,
+    pub portfolio_vault: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioMintAdded {
+    pub portfolio_vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_account: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioDepositEvent {
+    pub user: Pubkey,
+    pub portfolio_vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub new_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioWithdrawEvent {
+    pub user: Pubkey,
+    pub portfolio_vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub new_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioLockEvent {
+    pub portfolio_vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub caller: Pubkey,
+    pub price: Option<OraclePrice>,
+    pub quote_value: Option<u64>,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PortfolioUnlockEvent {
+    pub portfolio_vault: Pubkey,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub caller: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleConfigUpdated {
+    pub admin: Pubkey,
+    pub token_mint: Pubkey,
+    pub oracle: Pubkey,
+    pub max_staleness: u64,
+    pub max_confidence_bps: u16,
+    pub haircut_bps: u16,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintConfigUpdated {
+    pub admin: Pubkey,
+    pub token_mint: Pubkey,
+    pub approved: bool,
+    pub allowed_extensions: u64,
+    pub allow_freeze_authority: bool,
+    pub max_deposit_amount: u64,
+    pub max_vault_balance: u64,
+    pub max_total_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeniedExtensionsUpdated {
+    pub admin: Pubkey,
+    pub denied_extensions: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct IntegratorCapUpdated {
+    pub admin: Pubkey,
+    pub program: Pubkey,
+    pub max_locked: u64,
+    pub sequence: u64,
     pub timestamp: i64,
 }
 
@@ -780,4 +5945,121 @@ pub enum VaultError {
     
     #[msg("Vault has locked collateral - unlock before closing")]
     HasLockedCollateral,
+    
+    #[msg("Vault uses pooled custody - use the pooled instruction")]
+    PooledVault,
+    
+    #[msg("Vault does not use pooled custody")]
+    NotPooledVault,
+    
+    #[msg("Source and destination vault must differ")]
+    SameVault,
+    
+    #[msg("Custody pool account required for pooled vault")]
+    MissingCustodyPool,
+    
+    #[msg("Pooled vault set does not match custody pool")]
+    PoolVaultSetMismatch,
+    
+    #[msg("Custody balance does not match pooled vault balances")]
+    CustodyBalanceMismatch,
+    
+    #[msg("Vault has funds in sub-accounts - release before closing")]
+    HasSubAccountBalance,
+    
+    #[msg("Too many mints - maximum 8 per portfolio")]
+    TooManyMints,
+    
+    #[msg("Mint already added to portfolio")]
+    MintAlreadyAdded,
+    
+    #[msg("Mint is not held by this portfolio")]
+    MintNotInPortfolio,
+    
+    #[msg("Token account does not match portfolio record")]
+    InvalidTokenAccount,
+    
+    #[msg("Invalid basket - 1 to 8 legs, with weights summing to 10000 bps")]
+    InvalidBasket,
+    
+    #[msg("Invalid oracle config - check staleness, confidence and haircut")]
+    InvalidOracleConfig,
+    
+    #[msg("Oracle account does not match config or has an invalid layout")]
+    InvalidOracle,
+    
+    #[msg("Oracle price must be positive")]
+    InvalidOraclePrice,
+    
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+    
+    #[msg("Oracle confidence interval too wide")]
+    OracleConfidenceTooWide,
+    
+    #[msg("Mint is not approved as collateral")]
+    MintNotApproved,
+    
+    #[msg("Mint has a freeze authority")]
+    FreezeAuthorityNotAllowed,
+    
+    #[msg("Mint has a Token-2022 extension not allowed by the registry")]
+    ExtensionNotAllowed,
+    
+    #[msg("Deposit exceeds the per-mint deposit limit")]
+    DepositLimitExceeded,
+    
+    #[msg("Mint has a permanent delegate")]
+    PermanentDelegateNotAllowed,
+    
+    #[msg("Mint is non-transferable")]
+    NonTransferableNotAllowed,
+    
+    #[msg("Mint creates token accounts frozen by default")]
+    DefaultAccountStateFrozen,
+    
+    #[msg("Mint has a transfer hook")]
+    TransferHookNotAllowed,
+    
+    #[msg("Mint charges transfer fees")]
+    TransferFeeNotAllowed,
+    
+    #[msg("Mint is pausable")]
+    PausableNotAllowed,
+    
+    #[msg("Mint has a Token-2022 extension on the deny-list")]
+    ExtensionDenied,
+    
+    #[msg("Deposit would exceed the vault or mint deposit cap")]
+    DepositCapExceeded,
+    
+    #[msg("Lock would exceed the integrator's exposure cap")]
+    IntegratorCapExceeded,
+    
+    #[msg("Account is not a CollateralVault in a known layout")]
+    InvalidVaultLayout,
+    
+    #[msg("Sub-account does not belong to this vault")]
+    InvalidSubAccount,
+    
+    #[msg("Vault total balance does not equal locked plus available")]
+    BalanceSplitMismatch,
+    
+    #[msg("Vault balances exceed its token account balance")]
+    CustodyShortfall,
+    
+    #[msg("Lifetime deposits, withdrawals and transfers do not match the vault balance")]
+    LifetimeBalanceMismatch,
+    
+    #[msg("Program is not in the authorized list")]
+    ProgramNotAuthorized,
+    
+    #[msg("Account is not a VaultAuthority in a known layout")]
+    InvalidAuthorityLayout,
+    
+    #[msg("Vault authority must be migrated with migrate_authority first")]
+    AuthorityNotMigrated,
+    
+    #[msg("Unlock exceeds the collateral this program locked there")]
+    LockNotHeld,
 }
\ No newline at end of file
@@ -0,0 +1,238 @@
+//! Vault authority, program allow-list, mint registry and integrator stats.
+
+use anchor_lang::prelude::*;
+use collateral_vault::{
+    AuthorityConfig, IntegratorStats, MintConfig, MintConfigParams, MintStats, MintStatsView,
+    VaultAuthority, VaultError, DEFAULT_DENIED_EXTENSIONS, VIEW_VERSION,
+};
+use collateral_vault_client::{accounts::decode_return_data, instructions as ix, pda};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::setup::{approved, token_programs, TestEnv};
+
+#[tokio::test]
+async fn initialize_authority_limits_program_count() {
+    let mut env = TestEnv::uninitialized().await;
+    let admin = env.admin.pubkey();
+    let programs: Vec<Pubkey> = (0..11).map(|_| Pubkey::new_unique()).collect();
+
+    env.expect_error(
+        &[ix::initialize_authority(&admin, programs.clone())],
+        &[],
+        VaultError::TooManyPrograms,
+    )
+    .await;
+    env.execute(&[ix::initialize_authority(&admin, programs[..10].to_vec())], &[])
+        .await;
+
+    let authority: VaultAuthority = env.account(&pda::vault_authority().0).await;
+    assert_eq!(authority.admin, admin);
+    assert_eq!(authority.authorized_programs, programs[..10]);
+    assert_eq!(authority.denied_extensions, DEFAULT_DENIED_EXTENSIONS);
+
+    env.expect_error(
+        &[ix::add_authorized_program(&admin, programs[10])],
+        &[],
+        VaultError::TooManyPrograms,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn add_and_remove_authorized_programs() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let integrator = env.integrator.pubkey();
+    let program = Pubkey::new_unique();
+
+    env.execute(&[ix::add_authorized_program(&admin, program)], &[])
+        .await;
+    env.expect_error(
+        &[ix::add_authorized_program(&admin, program)],
+        &[],
+        VaultError::ProgramAlreadyAuthorized,
+    )
+    .await;
+
+    let authority: VaultAuthority = env.account(&pda::vault_authority().0).await;
+    assert_eq!(authority.authorized_programs, vec![integrator, program]);
+
+    env.execute(&[ix::remove_authorized_program(&admin, integrator)], &[])
+        .await;
+    let authority: VaultAuthority = env.account(&pda::vault_authority().0).await;
+    assert_eq!(authority.authorized_programs, vec![program]);
+    env.expect_error(
+        &[ix::remove_authorized_program(&admin, integrator)],
+        &[],
+        VaultError::ProgramNotAuthorized,
+    )
+    .await;
+
+    let outcome = env.execute(&[ix::get_authority_config()], &[]).await;
+    let config: AuthorityConfig = decode_return_data(&outcome.return_data).unwrap();
+    assert_eq!(config.version, VIEW_VERSION);
+    assert_eq!(config.admin, admin);
+    assert_eq!(config.authorized_programs, vec![program]);
+    assert_eq!(config.denied_extensions, DEFAULT_DENIED_EXTENSIONS);
+}
+
+#[tokio::test]
+async fn admin_instructions_reject_other_signers() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let intruder = env.wallet().await;
+    let key = intruder.pubkey();
+    let integrator = env.integrator.pubkey();
+
+    let instructions = [
+        ix::add_authorized_program(&key, Pubkey::new_unique()),
+        ix::remove_authorized_program(&key, integrator),
+        ix::set_denied_extensions(&key, 0),
+        ix::update_mint_config(&key, &mint.address, approved()),
+        ix::initialize_integrator_stats(&key, Pubkey::new_unique(), 0),
+        ix::set_integrator_cap(&key, &integrator, 1),
+        ix::initialize_custody_pool(&key, &mint.address, &mint.token_program),
+    ];
+    for instruction in instructions {
+        env.expect_error(&[instruction], &[&intruder], VaultError::Unauthorized)
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn mint_registry_gates_vault_creation() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let mint = env.create_mint(&token_programs()[0], None).await;
+    env.register_mint(
+        &mint,
+        MintConfigParams {
+            approved: false,
+            ..approved()
+        },
+    )
+    .await;
+    let user = env.user(&mint, 100).await;
+    let owner = user.pubkey();
+
+    env.expect_error(
+        &[ix::initialize_vault(&owner, &mint.address, &mint.token_program)],
+        &[&user],
+        VaultError::MintNotApproved,
+    )
+    .await;
+
+    let params = MintConfigParams {
+        max_deposit_amount: 10,
+        ..approved()
+    };
+    env.execute(&[ix::update_mint_config(&admin, &mint.address, params)], &[])
+        .await;
+    let mint_config: MintConfig = env.account(&pda::mint_config(&mint.address).0).await;
+    assert!(mint_config.approved);
+    assert_eq!(mint_config.token_mint, mint.address);
+    assert_eq!(mint_config.max_deposit_amount, 10);
+
+    env.execute(
+        &[ix::initialize_vault(&owner, &mint.address, &mint.token_program)],
+        &[&user],
+    )
+    .await;
+
+    // Revoking approval blocks new deposits into existing vaults
+    let params = MintConfigParams {
+        approved: false,
+        ..approved()
+    };
+    env.execute(&[ix::update_mint_config(&admin, &mint.address, params)], &[])
+        .await;
+    env.expect_error(
+        &[ix::deposit(&owner, &mint.address, &mint.token_program, 10)],
+        &[&user],
+        VaultError::MintNotApproved,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn mint_stats_view() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    env.user_with_vault(&mint, 100).await;
+    env.user_with_vault(&mint, 50).await;
+
+    let outcome = env.execute(&[ix::get_mint_stats(&mint.address)], &[]).await;
+    let stats: MintStatsView = decode_return_data(&outcome.return_data).unwrap();
+    assert_eq!(stats.token_mint, mint.address);
+    assert_eq!(stats.total_balance, 150);
+    assert_eq!(stats.total_deposited, 150);
+    assert_eq!(stats.total_withdrawn, 0);
+    assert_eq!(stats.total_locked, 0);
+    assert_eq!(stats.vault_count, 2);
+    assert!(!stats.unreconciled);
+}
+
+#[tokio::test]
+async fn mint_stats_floor_at_zero_for_unseen_vaults() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+    env.execute(
+        &[ix::lock_collateral(&signer, &owner, &mint.address, 10)],
+        &[&integrator],
+    )
+    .await;
+
+    // Stats created after the vault: neither its balance, lock nor the vault is counted
+    let stats_address = pda::mint_stats(&mint.address).0;
+    env.patch_account::<MintStats>(&stats_address, |stats| {
+        stats.total_balance = 0;
+        stats.total_locked = 0;
+        stats.vault_count = 0;
+    })
+    .await;
+
+    env.execute(
+        &[ix::unlock_collateral(&signer, &owner, &mint.address, 10)],
+        &[&integrator],
+    )
+    .await;
+    env.execute(
+        &[
+            ix::withdraw(&owner, &mint.address, &mint.token_program, 100),
+            ix::close_vault(&owner, &mint.address, &mint.token_program, false),
+        ],
+        &[&user],
+    )
+    .await;
+
+    let stats: MintStats = env.account(&stats_address).await;
+    assert_eq!(stats.total_balance, 0);
+    assert_eq!(stats.total_withdrawn, 100);
+    assert_eq!(stats.total_locked, 0);
+    assert_eq!(stats.vault_count, 0);
+    assert!(stats.unreconciled);
+}
+
+#[tokio::test]
+async fn integrator_stats_are_per_program() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let program = Keypair::new().pubkey();
+
+    env.execute(&[ix::initialize_integrator_stats(&admin, program, 500)], &[])
+        .await;
+    env.execute(&[ix::set_integrator_cap(&admin, &program, 750)], &[])
+        .await;
+
+    let stats: IntegratorStats = env.account(&pda::integrator_stats(&program).0).await;
+    assert_eq!(stats.program, program);
+    assert_eq!(stats.max_locked, 750);
+    assert_eq!(stats.locked_balance, 0);
+    assert_eq!(stats.total_locked, 0);
+    assert_eq!(stats.total_transferred, 0);
+}
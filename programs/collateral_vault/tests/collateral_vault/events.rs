@@ -0,0 +1,214 @@
+//! Event emission and client-side decoding, through the logs by default and
+//! through event self-CPIs when built with `event-cpi`, and the sequence
+//! numbers events carry.
+
+use anchor_lang::prelude::Pubkey;
+use collateral_vault::{CollateralVault, VaultAuthority};
+use collateral_vault_client::{events_from_logs, instructions as ix, pda, VaultEvent};
+use solana_signer::Signer;
+
+use crate::setup::{token_programs, TestEnv};
+
+fn names(events: &[VaultEvent]) -> Vec<&'static str> {
+    events.iter().map(VaultEvent::name).collect()
+}
+
+/// Sequence of each single-vault or admin event
+fn sequences(events: &[VaultEvent]) -> Vec<u64> {
+    events
+        .iter()
+        .map(|event| match event {
+            VaultEvent::VaultInitialized(e) => e.sequence,
+            VaultEvent::DepositEvent(e) => e.sequence,
+            VaultEvent::WithdrawEvent(e) => e.sequence,
+            VaultEvent::LockEvent(e) => e.sequence,
+            VaultEvent::UnlockEvent(e) => e.sequence,
+            VaultEvent::ProgramAuthorized(e) => e.sequence,
+            VaultEvent::ProgramDeauthorized(e) => e.sequence,
+            VaultEvent::DeniedExtensionsUpdated(e) => e.sequence,
+            other => panic!("no single sequence on {}", other.name()),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn vault_lifecycle_events_decode() {
+    for token_program in token_programs() {
+        let mut env = TestEnv::new().await;
+        let mint = env.approved_mint(&token_program).await;
+        let user = env.user(&mint, 1_000).await;
+        let owner = user.pubkey();
+        let vault = pda::vault(&owner).0;
+        let other = env.user_with_vault(&mint, 0).await;
+        let integrator = env.integrator.insecure_clone();
+        let signer = integrator.pubkey();
+
+        let outcome = env
+            .execute(
+                &[
+                    ix::initialize_vault(&owner, &mint.address, &token_program),
+                    ix::deposit(&owner, &mint.address, &token_program, 600),
+                ],
+                &[&user],
+            )
+            .await;
+        assert_eq!(names(&outcome.events), ["VaultInitialized", "DepositEvent"]);
+        assert_eq!(sequences(&outcome.events), [1, 2]);
+        match &outcome.events[1] {
+            VaultEvent::DepositEvent(event) => {
+                assert_eq!(event.vault, vault);
+                assert_eq!(event.user, owner);
+                assert_eq!(event.token_mint, mint.address);
+                assert_eq!(event.amount, 600);
+                assert_eq!(event.new_balance, 600);
+                assert_eq!(event.locked_balance, 0);
+                assert_eq!(event.available_balance, 600);
+            }
+            _ => unreachable!(),
+        }
+
+        let outcome = env
+            .execute(
+                &[
+                    ix::lock_collateral(&signer, &owner, &mint.address, 250),
+                    ix::unlock_collateral(&signer, &owner, &mint.address, 50),
+                    ix::transfer_collateral(
+                        &signer,
+                        &owner,
+                        &other.pubkey(),
+                        &mint.address,
+                        &token_program,
+                        100,
+                    ),
+                ],
+                &[&integrator],
+            )
+            .await;
+        assert_eq!(
+            names(&outcome.events),
+            ["LockEvent", "UnlockEvent", "TransferEvent"]
+        );
+        match &outcome.events[0] {
+            VaultEvent::LockEvent(event) => {
+                assert_eq!(event.vault, vault);
+                assert_eq!(event.caller, signer);
+                assert_eq!(event.total_balance, 600);
+                assert_eq!(event.locked_balance, 250);
+                assert_eq!(event.available_balance, 350);
+                assert!(event.price.is_none());
+            }
+            _ => unreachable!(),
+        }
+        match &outcome.events[1] {
+            VaultEvent::UnlockEvent(event) => {
+                assert_eq!(event.locked_balance, 200);
+                assert_eq!(event.available_balance, 400);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(sequences(&outcome.events[..2]), [3, 4]);
+        match &outcome.events[2] {
+            VaultEvent::TransferEvent(event) => {
+                assert_eq!(event.from_vault, vault);
+                assert_eq!(event.to_vault, pda::vault(&other.pubkey()).0);
+                assert_eq!(event.token_mint, mint.address);
+                assert_eq!(event.amount, 100);
+                assert_eq!(
+                    (
+                        event.from_total_balance,
+                        event.from_locked_balance,
+                        event.from_available_balance
+                    ),
+                    (500, 200, 300)
+                );
+                assert_eq!(
+                    (
+                        event.to_total_balance,
+                        event.to_locked_balance,
+                        event.to_available_balance
+                    ),
+                    (100, 0, 100)
+                );
+                assert_eq!(event.from_sequence, 5);
+                // The destination was only initialized
+                assert_eq!(event.to_sequence, 2);
+            }
+            _ => unreachable!(),
+        }
+
+        let outcome = env
+            .execute(
+                &[ix::withdraw(&owner, &mint.address, &token_program, 150)],
+                &[&user],
+            )
+            .await;
+        assert_eq!(names(&outcome.events), ["WithdrawEvent"]);
+        assert_eq!(sequences(&outcome.events), [6]);
+        let account: CollateralVault = env.account(&vault).await;
+        assert_eq!(account.sequence, 6);
+        match &outcome.events[0] {
+            VaultEvent::WithdrawEvent(event) => {
+                assert_eq!(event.amount, 150);
+                assert_eq!(event.new_balance, 350);
+                assert_eq!(event.locked_balance, 200);
+                assert_eq!(event.available_balance, 150);
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn events_travel_by_one_channel() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[0]).await;
+    let user = env.user(&mint, 100).await;
+    let owner = user.pubkey();
+
+    let outcome = env
+        .execute(
+            &[
+                ix::initialize_vault(&owner, &mint.address, &mint.token_program),
+                ix::deposit(&owner, &mint.address, &mint.token_program, 100),
+            ],
+            &[&user],
+        )
+        .await;
+    let logged = events_from_logs(&outcome.logs);
+
+    // Under event-cpi nothing is logged as Program data, so truncation can't drop events
+    if cfg!(feature = "event-cpi") {
+        assert!(logged.is_empty());
+        assert_eq!(outcome.events.len(), 2);
+    } else {
+        assert_eq!(names(&logged), ["VaultInitialized", "DepositEvent"]);
+    }
+}
+
+#[tokio::test]
+async fn admin_events_share_the_authority_sequence() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let program = Pubkey::new_unique();
+    let authority: VaultAuthority = env.account(&pda::vault_authority().0).await;
+    let before = authority.sequence;
+    assert!(before > 0);
+
+    let outcome = env
+        .execute(
+            &[
+                ix::add_authorized_program(&admin, program),
+                ix::remove_authorized_program(&admin, program),
+                ix::set_denied_extensions(&admin, authority.denied_extensions),
+            ],
+            &[],
+        )
+        .await;
+    assert_eq!(
+        sequences(&outcome.events),
+        [before + 1, before + 2, before + 3]
+    );
+
+    let authority: VaultAuthority = env.account(&pda::vault_authority().0).await;
+    assert_eq!(authority.sequence, before + 3);
+}
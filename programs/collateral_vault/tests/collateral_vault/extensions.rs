@@ -0,0 +1,143 @@
+//! Freeze authority and Token-2022 extension checks at vault creation.
+
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use collateral_vault::{
+    extension_bit, MintConfigParams, VaultAuthority, VaultError, DEFAULT_DENIED_EXTENSIONS,
+};
+use collateral_vault_client::{instructions as ix, pda};
+use solana_signer::Signer;
+
+use crate::setup::{approved, token_programs, MintExtension, TestEnv, TestMint};
+
+/// Registers `mint` with `params` and tries to open a vault for a fresh user
+async fn open_vault(env: &mut TestEnv, mint: &TestMint, params: MintConfigParams) -> bool {
+    env.register_mint(mint, params).await;
+    let user = env.user(mint, 0).await;
+    let instruction = ix::initialize_vault(&user.pubkey(), &mint.address, &mint.token_program);
+    env.process(&[instruction], &[&user]).await.is_ok()
+}
+
+async fn expect_rejected(
+    env: &mut TestEnv,
+    mint: &TestMint,
+    params: MintConfigParams,
+    expected: VaultError,
+) {
+    env.register_mint(mint, params).await;
+    let user = env.user(mint, 0).await;
+    env.expect_error(
+        &[ix::initialize_vault(&user.pubkey(), &mint.address, &mint.token_program)],
+        &[&user],
+        expected,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn freeze_authority_requires_opt_in() {
+    for token_program in token_programs() {
+        let mut env = TestEnv::new().await;
+        let freeze_authority = env.admin.pubkey();
+
+        let mint = env.create_mint(&token_program, Some(&freeze_authority)).await;
+        expect_rejected(
+            &mut env,
+            &mint,
+            approved(),
+            VaultError::FreezeAuthorityNotAllowed,
+        )
+        .await;
+
+        let mint = env.create_mint(&token_program, Some(&freeze_authority)).await;
+        let params = MintConfigParams {
+            allow_freeze_authority: true,
+            ..approved()
+        };
+        assert!(open_vault(&mut env, &mint, params).await);
+    }
+}
+
+#[tokio::test]
+async fn denied_extensions_have_specific_errors() {
+    let cases = [
+        (
+            MintExtension::PermanentDelegate,
+            VaultError::PermanentDelegateNotAllowed,
+        ),
+        (
+            MintExtension::NonTransferable,
+            VaultError::NonTransferableNotAllowed,
+        ),
+        (
+            MintExtension::DefaultAccountStateFrozen,
+            VaultError::DefaultAccountStateFrozen,
+        ),
+        (MintExtension::TransferHook, VaultError::TransferHookNotAllowed),
+        (MintExtension::TransferFee, VaultError::TransferFeeNotAllowed),
+        (MintExtension::Pausable, VaultError::PausableNotAllowed),
+    ];
+
+    let mut env = TestEnv::new().await;
+    for (extension, expected) in cases {
+        let mint = env.create_mint_with_extension(extension).await;
+        // Allowing the extension in the registry doesn't override the deny-list
+        let params = MintConfigParams {
+            allowed_extensions: extension_bit(extension.extension_type()),
+            ..approved()
+        };
+        expect_rejected(&mut env, &mint, params, expected).await;
+    }
+}
+
+#[tokio::test]
+async fn extensions_must_be_allowed_per_mint() {
+    let mut env = TestEnv::new().await;
+
+    let mint = env
+        .create_mint_with_extension(MintExtension::MintCloseAuthority)
+        .await;
+    expect_rejected(&mut env, &mint, approved(), VaultError::ExtensionNotAllowed).await;
+
+    let mint = env
+        .create_mint_with_extension(MintExtension::MintCloseAuthority)
+        .await;
+    let params = MintConfigParams {
+        allowed_extensions: extension_bit(ExtensionType::MintCloseAuthority),
+        ..approved()
+    };
+    assert!(open_vault(&mut env, &mint, params).await);
+}
+
+#[tokio::test]
+async fn deny_list_is_configurable() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+
+    // Extensions without a dedicated error fall back to `ExtensionDenied`
+    let denied = DEFAULT_DENIED_EXTENSIONS | extension_bit(ExtensionType::MintCloseAuthority);
+    env.execute(&[ix::set_denied_extensions(&admin, denied)], &[])
+        .await;
+    let mint = env
+        .create_mint_with_extension(MintExtension::MintCloseAuthority)
+        .await;
+    let params = MintConfigParams {
+        allowed_extensions: extension_bit(ExtensionType::MintCloseAuthority),
+        ..approved()
+    };
+    expect_rejected(&mut env, &mint, params, VaultError::ExtensionDenied).await;
+
+    // With an empty deny-list the registry's allow-list alone decides
+    env.execute(&[ix::set_denied_extensions(&admin, 0)], &[])
+        .await;
+    let mint = env
+        .create_mint_with_extension(MintExtension::PermanentDelegate)
+        .await;
+    let params = MintConfigParams {
+        allowed_extensions: extension_bit(ExtensionType::PermanentDelegate),
+        ..approved()
+    };
+    assert!(open_vault(&mut env, &mint, params).await);
+
+    let authority: VaultAuthority = env.account(&pda::vault_authority().0).await;
+    assert_eq!(authority.denied_extensions, 0);
+}
@@ -0,0 +1,206 @@
+//! `example_integrator` driving the vault through `collateral_vault_interface`
+//! CPI wrappers, signed by its PDA.
+
+use anchor_lang::{prelude::*, solana_program::instruction::Instruction, InstructionData};
+use collateral_vault::{CollateralVault, IntegratorPosition, IntegratorStats, VaultError};
+use collateral_vault_client::{instructions as ix, pda};
+use example_integrator::{IntegratorError, INTEGRATOR_SEED};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_system_interface::instruction as system_instruction;
+
+use crate::setup::{custom_error_code, token_programs, TestEnv, TestMint, LAMPORTS_PER_SOL};
+
+/// PDA the integrator signs its vault CPIs with
+fn integrator() -> Pubkey {
+    Pubkey::find_program_address(&[INTEGRATOR_SEED], &example_integrator::ID).0
+}
+
+fn integrator_instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: example_integrator::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn position_accounts(owner: &Pubkey, mint: &TestMint) -> example_integrator::accounts::Position {
+    let vault = pda::vault(owner).0;
+    example_integrator::accounts::Position {
+        integrator: integrator(),
+        vault,
+        vault_authority: pda::vault_authority().0,
+        mint_stats: pda::mint_stats(&mint.address).0,
+        integrator_stats: pda::integrator_stats(&integrator()).0,
+        integrator_position: pda::integrator_position(&integrator(), &vault).0,
+        vault_program: collateral_vault::ID,
+        system_program: system_program::ID,
+        #[cfg(feature = "event-cpi")]
+        vault_event_authority: pda::event_authority().0,
+    }
+}
+
+fn open_position(owner: &Pubkey, mint: &TestMint, amount: u64) -> Instruction {
+    integrator_instruction(
+        position_accounts(owner, mint),
+        example_integrator::instruction::OpenPosition { amount },
+    )
+}
+
+fn close_position(owner: &Pubkey, mint: &TestMint, amount: u64) -> Instruction {
+    integrator_instruction(
+        position_accounts(owner, mint),
+        example_integrator::instruction::ClosePosition { amount },
+    )
+}
+
+fn settle(from: &Pubkey, to: &Pubkey, mint: &TestMint, amount: u64) -> Instruction {
+    let from_vault = pda::vault(from).0;
+    let to_vault = pda::vault(to).0;
+    integrator_instruction(
+        example_integrator::accounts::Settle {
+            integrator: integrator(),
+            from_vault,
+            to_vault,
+            vault_authority: pda::vault_authority().0,
+            integrator_stats: pda::integrator_stats(&integrator()).0,
+            token_mint: mint.address,
+            from_vault_token_account: ix::vault_token_account(
+                &from_vault,
+                &mint.address,
+                &mint.token_program,
+            ),
+            to_vault_token_account: ix::vault_token_account(
+                &to_vault,
+                &mint.address,
+                &mint.token_program,
+            ),
+            token_program: mint.token_program,
+            vault_program: collateral_vault::ID,
+            system_program: system_program::ID,
+            #[cfg(feature = "event-cpi")]
+            vault_event_authority: pda::event_authority().0,
+        },
+        example_integrator::instruction::Settle { amount },
+    )
+}
+
+/// Authorizes the integrator PDA and funds it, since it pays for its stats and
+/// positions on first use
+async fn authorize_integrator(env: &mut TestEnv) {
+    let admin = env.admin.pubkey();
+    env.execute(
+        &[
+            ix::add_authorized_program(&admin, integrator()),
+            system_instruction::transfer(&admin, &integrator(), LAMPORTS_PER_SOL),
+        ],
+        &[],
+    )
+    .await;
+}
+
+fn vault_address(owner: &Keypair) -> Pubkey {
+    pda::vault(&owner.pubkey()).0
+}
+
+async fn vault(env: &mut TestEnv, owner: &Keypair) -> CollateralVault {
+    env.account(&vault_address(owner)).await
+}
+
+#[tokio::test]
+async fn positions_lock_and_release_margin() {
+    for token_program in token_programs() {
+        let mut env = TestEnv::with_example_integrator().await;
+        authorize_integrator(&mut env).await;
+        let mint = env.approved_mint(&token_program).await;
+        let user = env.user_with_vault(&mint, 100).await;
+        let owner = user.pubkey();
+
+        env.execute(&[open_position(&owner, &mint, 60)], &[]).await;
+
+        let vault_state = vault(&mut env, &user).await;
+        assert_eq!(vault_state.locked_balance, 60);
+        assert_eq!(vault_state.available_balance, 40);
+        let stats: IntegratorStats = env.account(&pda::integrator_stats(&integrator()).0).await;
+        assert_eq!(stats.program, integrator());
+        assert_eq!(stats.locked_balance, 60);
+        let position: IntegratorPosition = env
+            .account(&pda::integrator_position(&integrator(), &vault_address(&user)).0)
+            .await;
+        assert_eq!(position.locks.len(), 1);
+        assert_eq!(position.locks[0].mint, mint.address);
+        assert_eq!(position.locks[0].amount, 60);
+
+        env.execute(&[close_position(&owner, &mint, 60)], &[]).await;
+
+        let vault_state = vault(&mut env, &user).await;
+        assert_eq!(vault_state.locked_balance, 0);
+        assert_eq!(vault_state.available_balance, 100);
+        let stats: IntegratorStats = env.account(&pda::integrator_stats(&integrator()).0).await;
+        assert_eq!(stats.locked_balance, 0);
+        assert_eq!(stats.total_locked, 60);
+    }
+}
+
+#[tokio::test]
+async fn positions_are_checked_against_available_margin() {
+    let mut env = TestEnv::with_example_integrator().await;
+    authorize_integrator(&mut env).await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+
+    // Rejected by the integrator from the `get_vault_balances` view, before any lock
+    let err = env
+        .process(&[open_position(&user.pubkey(), &mint, 101)], &[])
+        .await
+        .err()
+        .unwrap_or_else(|| panic!("expected InsufficientMargin, transaction succeeded"));
+    assert_eq!(
+        custom_error_code(&err),
+        Some(u32::from(IntegratorError::InsufficientMargin)),
+        "got {err}",
+    );
+    assert_eq!(vault(&mut env, &user).await.locked_balance, 0);
+}
+
+#[tokio::test]
+async fn unauthorized_integrator_is_rejected() {
+    let mut env = TestEnv::with_example_integrator().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let admin = env.admin.pubkey();
+    env.execute(
+        &[system_instruction::transfer(&admin, &integrator(), LAMPORTS_PER_SOL)],
+        &[],
+    )
+    .await;
+
+    env.expect_error(
+        &[open_position(&user.pubkey(), &mint, 10)],
+        &[],
+        VaultError::UnauthorizedProgram,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn settle_transfers_between_vaults() {
+    for token_program in token_programs() {
+        let mut env = TestEnv::with_example_integrator().await;
+        authorize_integrator(&mut env).await;
+        let mint = env.approved_mint(&token_program).await;
+        let from = env.user_with_vault(&mint, 100).await;
+        let to = env.user_with_vault(&mint, 10).await;
+        let to_token_account =
+            ix::vault_token_account(&vault_address(&to), &mint.address, &token_program);
+
+        env.execute(&[settle(&from.pubkey(), &to.pubkey(), &mint, 25)], &[])
+            .await;
+
+        assert_eq!(vault(&mut env, &from).await.total_balance, 75);
+        assert_eq!(vault(&mut env, &to).await.total_balance, 35);
+        assert_eq!(env.token_balance(&to_token_account).await, 35);
+        let stats: IntegratorStats = env.account(&pda::integrator_stats(&integrator()).0).await;
+        assert_eq!(stats.total_transferred, 25);
+    }
+}
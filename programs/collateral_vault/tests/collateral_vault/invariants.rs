@@ -0,0 +1,327 @@
+//! Random deposit, lock, unlock, withdraw, transfer and close sequences across
+//! several vaults, checked against the accounting and token conservation
+//! invariants after every step.
+//!
+//! Each step is also replayed on a plain model of the vaults, so an instruction
+//! that succeeds when it should fail (or the reverse) is reported with the
+//! shrunk sequence that triggered it.
+
+use anchor_lang::prelude::*;
+use collateral_vault::CollateralVault;
+use collateral_vault_client::{instructions as ix, pda};
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestRunner};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::setup::{token_programs, TestEnv, TestMint};
+
+const VAULTS: usize = 4;
+/// Tokens minted to each wallet up front; nothing is minted afterwards
+const FUNDING: u64 = 1_000;
+const CASES: u32 = 24;
+const MAX_STEPS: usize = 40;
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Deposit { vault: usize, amount: u64 },
+    Withdraw { vault: usize, amount: u64 },
+    Lock { vault: usize, amount: u64 },
+    Unlock { vault: usize, amount: u64 },
+    Transfer { from: usize, to: usize, amount: u64 },
+    Close { vault: usize },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let vault = 0..VAULTS;
+    // Includes zero and amounts above any balance so the failure paths get hit
+    let amount = prop_oneof![Just(0u64), 1..=FUNDING / 2, FUNDING..=FUNDING * 2];
+    prop_oneof![
+        3 => (vault.clone(), amount.clone())
+            .prop_map(|(vault, amount)| Op::Deposit { vault, amount }),
+        2 => (vault.clone(), amount.clone())
+            .prop_map(|(vault, amount)| Op::Withdraw { vault, amount }),
+        2 => (vault.clone(), amount.clone())
+            .prop_map(|(vault, amount)| Op::Lock { vault, amount }),
+        2 => (vault.clone(), amount.clone())
+            .prop_map(|(vault, amount)| Op::Unlock { vault, amount }),
+        2 => (vault.clone(), vault.clone(), amount)
+            .prop_map(|(from, to, amount)| Op::Transfer { from, to, amount }),
+        1 => vault.prop_map(|vault| Op::Close { vault }),
+    ]
+}
+
+/// Expected state of one wallet and its vault
+#[derive(Clone, Copy, Debug)]
+struct Expected {
+    open: bool,
+    wallet: u64,
+    locked: u64,
+    available: u64,
+    deposited: u64,
+    withdrawn: u64,
+    transferred_in: u64,
+    transferred_out: u64,
+}
+
+impl Expected {
+    fn total(&self) -> u64 {
+        self.locked + self.available
+    }
+}
+
+/// Applies `op` to the model, returning whether the program should accept it
+fn apply(model: &mut [Expected; VAULTS], op: Op) -> bool {
+    match op {
+        Op::Deposit { vault, amount } => {
+            let v = &mut model[vault];
+            if !v.open || amount == 0 || v.wallet < amount {
+                return false;
+            }
+            v.wallet -= amount;
+            v.available += amount;
+            v.deposited += amount;
+        }
+        Op::Withdraw { vault, amount } => {
+            let v = &mut model[vault];
+            if !v.open || amount == 0 || v.available < amount {
+                return false;
+            }
+            v.available -= amount;
+            v.wallet += amount;
+            v.withdrawn += amount;
+        }
+        Op::Lock { vault, amount } => {
+            let v = &mut model[vault];
+            if !v.open || amount == 0 || v.available < amount {
+                return false;
+            }
+            v.available -= amount;
+            v.locked += amount;
+        }
+        Op::Unlock { vault, amount } => {
+            let v = &mut model[vault];
+            if !v.open || amount == 0 || v.locked < amount {
+                return false;
+            }
+            v.locked -= amount;
+            v.available += amount;
+        }
+        Op::Transfer { from, to, amount } => {
+            if from == to
+                || !model[from].open
+                || !model[to].open
+                || amount == 0
+                || model[from].available < amount
+            {
+                return false;
+            }
+            model[from].available -= amount;
+            model[from].transferred_out += amount;
+            model[to].available += amount;
+            model[to].transferred_in += amount;
+        }
+        Op::Close { vault } => {
+            let v = &mut model[vault];
+            if !v.open || v.total() != 0 {
+                return false;
+            }
+            v.open = false;
+        }
+    }
+    true
+}
+
+struct Harness {
+    env: TestEnv,
+    mint: TestMint,
+    users: Vec<Keypair>,
+    model: [Expected; VAULTS],
+}
+
+impl Harness {
+    async fn new(token_program: Pubkey) -> Self {
+        let mut env = TestEnv::new().await;
+        let mint = env.approved_mint(&token_program).await;
+        let mut users = Vec::with_capacity(VAULTS);
+        for _ in 0..VAULTS {
+            let user = env.user(&mint, FUNDING).await;
+            env.execute(
+                &[ix::initialize_vault(
+                    &user.pubkey(),
+                    &mint.address,
+                    &mint.token_program,
+                )],
+                &[&user],
+            )
+            .await;
+            users.push(user);
+        }
+
+        let model = [Expected {
+            open: true,
+            wallet: FUNDING,
+            locked: 0,
+            available: 0,
+            deposited: 0,
+            withdrawn: 0,
+            transferred_in: 0,
+            transferred_out: 0,
+        }; VAULTS];
+        Self {
+            env,
+            mint,
+            users,
+            model,
+        }
+    }
+
+    async fn step(&mut self, op: Op) {
+        let mint = self.mint.address;
+        let token_program = self.mint.token_program;
+        let integrator = self.env.integrator.insecure_clone();
+        let signer = integrator.pubkey();
+        let owner = |vault: usize| self.users[vault].pubkey();
+
+        let (instruction, user) = match op {
+            Op::Deposit { vault, amount } => (
+                ix::deposit(&owner(vault), &mint, &token_program, amount),
+                Some(vault),
+            ),
+            Op::Withdraw { vault, amount } => (
+                ix::withdraw(&owner(vault), &mint, &token_program, amount),
+                Some(vault),
+            ),
+            Op::Lock { vault, amount } => (
+                ix::lock_collateral(&signer, &owner(vault), &mint, amount),
+                None,
+            ),
+            Op::Unlock { vault, amount } => (
+                ix::unlock_collateral(&signer, &owner(vault), &mint, amount),
+                None,
+            ),
+            Op::Transfer { from, to, amount } => (
+                ix::transfer_collateral(
+                    &signer,
+                    &owner(from),
+                    &owner(to),
+                    &mint,
+                    &token_program,
+                    amount,
+                ),
+                None,
+            ),
+            Op::Close { vault } => (
+                ix::close_vault(&owner(vault), &mint, &token_program, false),
+                Some(vault),
+            ),
+        };
+        let signers = match user {
+            Some(vault) => vec![self.users[vault].insecure_clone()],
+            None => vec![integrator],
+        };
+        let signers: Vec<&Keypair> = signers.iter().collect();
+
+        let expected = apply(&mut self.model, op);
+        let result = self.env.process(&[instruction], &signers).await;
+        assert_eq!(
+            result.is_ok(),
+            expected,
+            "{op:?} should {}: {:?}",
+            if expected { "succeed" } else { "fail" },
+            result.as_ref().err()
+        );
+    }
+
+    async fn check_invariants(&mut self) {
+        let mut circulating = 0;
+        for (user, expected) in self.users.iter().zip(self.model) {
+            let owner = user.pubkey();
+            let vault_address = pda::vault(&owner).0;
+            let vault_token_account =
+                ix::vault_token_account(&vault_address, &self.mint.address, &self.mint.token_program);
+            let user_token_account =
+                ix::user_token_account(&owner, &self.mint.address, &self.mint.token_program);
+
+            let custody = self.env.token_balance(&vault_token_account).await;
+            let wallet = self.env.token_balance(&user_token_account).await;
+            assert_eq!(wallet, expected.wallet, "wallet of vault {vault_address}");
+            circulating += custody + wallet;
+
+            if !expected.open {
+                assert!(!self.env.exists(&vault_address).await, "{vault_address} closed");
+                assert_eq!(custody, 0, "closed vault {vault_address} left tokens behind");
+                continue;
+            }
+
+            let vault: CollateralVault = self.env.account(&vault_address).await;
+            assert_eq!(
+                vault.total_balance,
+                vault.locked_balance + vault.available_balance,
+                "total != locked + available for {vault_address}"
+            );
+            assert_eq!(vault.total_balance, custody, "{vault_address} custody");
+            assert_eq!(vault.locked_balance, expected.locked, "{vault_address} locked");
+            assert_eq!(vault.available_balance, expected.available, "{vault_address} available");
+            assert_eq!(vault.total_deposited, expected.deposited, "{vault_address} deposited");
+            assert_eq!(vault.total_withdrawn, expected.withdrawn, "{vault_address} withdrawn");
+            assert_eq!(
+                vault.total_transferred_in, expected.transferred_in,
+                "{vault_address} transferred in"
+            );
+            assert_eq!(
+                vault.total_transferred_out, expected.transferred_out,
+                "{vault_address} transferred out"
+            );
+            assert_eq!((vault.total_seized, vault.total_fees_paid), (0, 0), "{vault_address}");
+            assert_eq!(
+                vault.total_balance + vault.total_withdrawn + vault.total_transferred_out,
+                vault.total_deposited + vault.total_transferred_in,
+                "{vault_address} balance doesn't follow from its history"
+            );
+
+            // The on-chain canary agrees
+            let check = ix::assert_invariants(
+                &owner,
+                &self.mint.address,
+                &self.mint.token_program,
+                false,
+            );
+            self.env.execute(&[check], &[]).await;
+        }
+
+        // Transfers only move tokens between the accounts above
+        assert_eq!(circulating, FUNDING * VAULTS as u64, "tokens created or destroyed");
+    }
+}
+
+async fn run(token_program: Pubkey, ops: Vec<Op>) {
+    let mut harness = Harness::new(token_program).await;
+    harness.check_invariants().await;
+    for op in ops {
+        harness.step(op).await;
+        harness.check_invariants().await;
+    }
+}
+
+#[test]
+fn random_sequences_preserve_invariants() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut runner = TestRunner::new(Config {
+        cases: CASES,
+        ..Config::default()
+    });
+    let sequences = (
+        prop::sample::select(token_programs().to_vec()),
+        prop::collection::vec(op(), 1..MAX_STEPS),
+    );
+    runner
+        .run(&sequences, |(token_program, ops)| {
+            runtime.block_on(run(token_program, ops));
+            Ok(())
+        })
+        .unwrap();
+}
@@ -0,0 +1,25 @@
+//! In-process integration tests for the collateral vault program.
+//!
+//! Runs the compiled program on `solana-program-test` with the SPL Token,
+//! Token-2022 and ATA programs bundled by the bank, so `cargo test` needs no
+//! validator or network. Under `cargo test-sbf`, `collateral_vault.so` is
+//! loaded from `SBF_OUT_DIR`; a plain `cargo test` builds it first with
+//! `cargo build-sbf`, so the Solana CLI has to be installed either way. The same
+//! goes for `example_integrator.so`, which `integrator` loads next to it.
+//!
+//! There is no native fallback: Anchor routes CPIs, logs and return data
+//! through crates that do nothing off-chain.
+
+mod admin;
+mod events;
+mod extensions;
+mod integrator;
+mod invariants;
+mod migration;
+mod oracle;
+mod pooled;
+mod portfolio;
+mod setup;
+mod sub_accounts;
+mod vault;
+mod zero_copy;
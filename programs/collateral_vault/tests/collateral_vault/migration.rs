@@ -0,0 +1,407 @@
+//! Upgrading version-0 and version-1 vaults, and the version-0 vault authority,
+//! to the current account layouts.
+
+use anchor_lang::prelude::*;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use collateral_vault::{
+    CollateralVault, IntegratorPosition, IntegratorStats, VaultAuthority, VaultError,
+    AUTHORITY_VERSION, DEFAULT_DENIED_EXTENSIONS, ID, LEGACY_AUTHORITY_SPACE, LEGACY_VAULT_SPACE,
+    VAULT_VERSION,
+};
+use collateral_vault_client::{instructions as ix, pda};
+use solana_account::{Account, AccountSharedData};
+use solana_signer::Signer;
+
+use crate::setup::{token_programs, TestEnv};
+
+/// Writes raw vault data at `owner`'s vault PDA
+fn write_legacy_vault(env: &mut TestEnv, owner: &Pubkey, data: Vec<u8>) {
+    env.context.set_account(
+        &pda::vault(owner).0,
+        &AccountSharedData::from(Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: ID,
+            executable: false,
+            rent_epoch: 0,
+        }),
+    );
+}
+
+/// `CollateralVault` as first deployed, before the layout was versioned
+#[derive(AnchorSerialize)]
+struct BaselineVault {
+    owner: Pubkey,
+    token_account: Pubkey,
+    total_balance: u64,
+    locked_balance: u64,
+    available_balance: u64,
+    total_deposited: u64,
+    total_withdrawn: u64,
+    created_at: i64,
+    bump: u8,
+    token_mint: Pubkey,
+}
+
+impl BaselineVault {
+    fn new(owner: &Pubkey, token_account: Pubkey, token_mint: Pubkey) -> Self {
+        Self {
+            owner: *owner,
+            token_account,
+            total_balance: 500_000_000,
+            locked_balance: 200_000_000,
+            available_balance: 300_000_000,
+            total_deposited: 600_000_000,
+            total_withdrawn: 100_000_000,
+            created_at: 1_700_000_000,
+            bump: pda::vault(owner).1,
+            token_mint,
+        }
+    }
+
+    fn data(&self) -> Vec<u8> {
+        let mut data = CollateralVault::DISCRIMINATOR.to_vec();
+        self.serialize(&mut data).unwrap();
+        data
+    }
+}
+
+fn vault_data(owner: &Pubkey) -> CollateralVault {
+    CollateralVault {
+        owner: *owner,
+        token_account: Pubkey::new_unique(),
+        total_balance: 500,
+        locked_balance: 200,
+        available_balance: 300,
+        total_deposited: 700,
+        total_withdrawn: 200,
+        created_at: 1_700_000_000,
+        bump: pda::vault(owner).1,
+        token_mint: Pubkey::new_unique(),
+        pooled: false,
+        sub_account_balance: 0,
+        version: 0,
+        total_transferred_in: 0,
+        total_transferred_out: 0,
+        sequence: 0,
+        partial_history: false,
+        total_seized: 0,
+        total_fees_paid: 0,
+        unattributed_locked: 0,
+        reserved: [0; 15],
+    }
+}
+
+/// Version-0 vault: no version byte or reserved space
+fn legacy_vault_data(owner: &Pubkey) -> Vec<u8> {
+    BaselineVault::new(owner, Pubkey::new_unique(), Pubkey::new_unique()).data()
+}
+
+/// The validator fixture behind the TypeScript migration test
+#[test]
+fn legacy_fixture_matches_the_baseline_layout() {
+    let fixture: serde_json::Value = serde_json::from_str(include_str!(
+        "../../../../tests/fixtures/legacy_vault.json"
+    ))
+    .unwrap();
+    let account = &fixture["account"];
+    let data = STANDARD
+        .decode(account["data"][0].as_str().unwrap())
+        .unwrap();
+
+    let owner = pubkey!("49mWUrG3VMfcbjVnxhQVgjHAnxdoc3RyMni7iV88MT6g");
+    let baseline = BaselineVault::new(
+        &owner,
+        pubkey!("DFK64ZQ2VURHNAhTyb9uChV4ixgXXfPwUx1AjGrGZmWL"),
+        pubkey!("HcPcEGTkrpZN7Mmn9NGh7vG29g6zw4A96uQCQZGhBJJv"),
+    );
+    assert_eq!(data, baseline.data());
+    assert_eq!(data.len(), LEGACY_VAULT_SPACE);
+    assert_eq!(account["space"], LEGACY_VAULT_SPACE);
+    assert_eq!(
+        account["lamports"],
+        Rent::default().minimum_balance(LEGACY_VAULT_SPACE)
+    );
+    assert_eq!(account["owner"], ID.to_string());
+    assert_eq!(fixture["pubkey"], pda::vault(&owner).0.to_string());
+}
+
+#[tokio::test]
+async fn legacy_vault_is_migrated_once() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let owner = Pubkey::new_unique();
+    let vault_address = pda::vault(&owner).0;
+    write_legacy_vault(&mut env, &owner, legacy_vault_data(&owner));
+
+    env.execute(&[ix::migrate_vault(&admin, &owner)], &[]).await;
+
+    let account = env.raw_account(&vault_address).await.unwrap();
+    assert_eq!(account.data.len(), 8 + CollateralVault::INIT_SPACE);
+    let vault: CollateralVault = env.account(&vault_address).await;
+    assert_eq!(vault.version, VAULT_VERSION);
+    assert_eq!(vault.owner, owner);
+    assert_eq!(vault.total_balance, 500_000_000);
+    assert_eq!(vault.locked_balance, 200_000_000);
+    assert_eq!(vault.available_balance, 300_000_000);
+    assert_eq!(vault.total_transferred_in, 0);
+    assert_eq!(vault.total_transferred_out, 0);
+    // The migration event is the vault's first sequenced event
+    assert_eq!(vault.sequence, 1);
+    assert!(vault.partial_history);
+    assert_eq!(vault.total_seized, 0);
+    assert_eq!(vault.total_fees_paid, 0);
+    // No program can be charged for locks made before the migration
+    assert_eq!(vault.unattributed_locked, 200_000_000);
+    assert_eq!(vault.reserved, [0; 15]);
+
+    // A second run is a no-op
+    env.execute(&[ix::migrate_vault(&admin, &owner)], &[]).await;
+    let migrated = env.raw_account(&vault_address).await.unwrap();
+    assert_eq!(migrated.data, account.data);
+    assert_eq!(migrated.lamports, account.lamports);
+}
+
+#[tokio::test]
+async fn legacy_locks_stay_unlockable() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let owner = Pubkey::new_unique();
+    let vault_address = pda::vault(&owner).0;
+    let baseline = BaselineVault::new(&owner, Pubkey::new_unique(), mint.address);
+    write_legacy_vault(&mut env, &owner, baseline.data());
+    env.execute(&[ix::migrate_vault(&admin, &owner)], &[]).await;
+
+    // Locks from before positions are released without charging any program
+    env.execute(
+        &[ix::unlock_collateral(&signer, &owner, &mint.address, 150_000_000)],
+        &[&integrator],
+    )
+    .await;
+    let vault: CollateralVault = env.account(&vault_address).await;
+    assert_eq!(vault.unattributed_locked, 50_000_000);
+    let stats: IntegratorStats = env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!(stats.locked_balance, 0);
+
+    // An unlock takes the program's own locks first, then what is left of the legacy ones
+    env.execute(
+        &[ix::lock_collateral(&signer, &owner, &mint.address, 100_000_000)],
+        &[&integrator],
+    )
+    .await;
+    env.execute(
+        &[ix::unlock_collateral(&signer, &owner, &mint.address, 150_000_000)],
+        &[&integrator],
+    )
+    .await;
+    let vault: CollateralVault = env.account(&vault_address).await;
+    assert_eq!((vault.locked_balance, vault.unattributed_locked), (0, 0));
+    let stats: IntegratorStats = env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!((stats.locked_balance, stats.total_locked), (0, 100_000_000));
+    let position: IntegratorPosition = env
+        .account(&pda::integrator_position(&signer, &vault_address).0)
+        .await;
+    assert!(position.locks.is_empty());
+}
+
+#[tokio::test]
+async fn version_one_vault_starts_transfer_counters_at_zero() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+
+    // Version 1 didn't count transfers, so the 150 received here isn't guessed at
+    let owner = Pubkey::new_unique();
+    let vault = CollateralVault {
+        total_balance: 650,
+        locked_balance: 0,
+        available_balance: 650,
+        version: 1,
+        ..vault_data(&owner)
+    };
+    let mut data = Vec::new();
+    vault.try_serialize(&mut data).unwrap();
+    write_legacy_vault(&mut env, &owner, data);
+
+    env.execute(&[ix::migrate_vault(&admin, &owner)], &[]).await;
+
+    let vault: CollateralVault = env.account(&pda::vault(&owner).0).await;
+    assert_eq!(vault.version, VAULT_VERSION);
+    assert_eq!(vault.total_transferred_in, 0);
+    assert_eq!(vault.total_transferred_out, 0);
+}
+
+#[tokio::test]
+async fn migration_rejects_foreign_layouts() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+
+    let owner = Pubkey::new_unique();
+    write_legacy_vault(&mut env, &owner, vec![7; LEGACY_VAULT_SPACE]);
+    env.expect_error(
+        &[ix::migrate_vault(&admin, &owner)],
+        &[],
+        VaultError::InvalidVaultLayout,
+    )
+    .await;
+
+    // Neither a version-0 nor a current-size vault
+    for len in [LEGACY_VAULT_SPACE - 1, LEGACY_VAULT_SPACE + 9] {
+        let owner = Pubkey::new_unique();
+        let mut data = legacy_vault_data(&owner);
+        data.resize(len, 0);
+        write_legacy_vault(&mut env, &owner, data);
+        env.expect_error(
+            &[ix::migrate_vault(&admin, &owner)],
+            &[],
+            VaultError::InvalidVaultLayout,
+        )
+        .await;
+    }
+}
+
+/// `VaultAuthority` as first deployed, before the deny-list and admin sequence
+#[derive(AnchorSerialize)]
+struct BaselineAuthority {
+    admin: Pubkey,
+    authorized_programs: Vec<Pubkey>,
+    bump: u8,
+}
+
+/// Replaces the vault authority with a version-0 one, allocated at its original size
+fn write_legacy_authority(env: &mut TestEnv, authorized_programs: Vec<Pubkey>) {
+    let mut data = VaultAuthority::DISCRIMINATOR.to_vec();
+    BaselineAuthority {
+        admin: env.admin.pubkey(),
+        authorized_programs,
+        bump: pda::vault_authority().1,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    data.resize(LEGACY_AUTHORITY_SPACE, 0);
+    env.context.set_account(
+        &pda::vault_authority().0,
+        &AccountSharedData::from(Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: ID,
+            executable: false,
+            rent_epoch: 0,
+        }),
+    );
+}
+
+#[tokio::test]
+async fn legacy_authority_is_migrated_once() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let newcomer = env.user(&mint, 0).await;
+    write_legacy_authority(&mut env, vec![integrator.pubkey()]);
+
+    // Integrators keep locking, but nothing reads the deny-list until migration
+    env.execute(
+        &[ix::lock_collateral(&integrator.pubkey(), &user.pubkey(), &mint.address, 10)],
+        &[&integrator],
+    )
+    .await;
+    let create = || ix::initialize_vault(&newcomer.pubkey(), &mint.address, &mint.token_program);
+    env.expect_error(&[create()], &[&newcomer], VaultError::AuthorityNotMigrated)
+        .await;
+    env.expect_error(
+        &[ix::add_authorized_program(&admin, Pubkey::new_unique())],
+        &[],
+        VaultError::AuthorityNotMigrated,
+    )
+    .await;
+
+    env.execute(&[ix::migrate_authority(&admin)], &[]).await;
+
+    let address = pda::vault_authority().0;
+    let account = env.raw_account(&address).await.unwrap();
+    assert_eq!(account.data.len(), 8 + VaultAuthority::INIT_SPACE);
+    let authority: VaultAuthority = env.account(&address).await;
+    assert_eq!(authority.version, AUTHORITY_VERSION);
+    assert_eq!(authority.admin, admin);
+    assert_eq!(authority.authorized_programs, vec![integrator.pubkey()]);
+    assert_eq!(authority.bump, pda::vault_authority().1);
+    assert_eq!(authority.denied_extensions, DEFAULT_DENIED_EXTENSIONS);
+    // The migration event is the authority's first sequenced event
+    assert_eq!(authority.sequence, 1);
+
+    // A second run is a no-op
+    env.execute(&[ix::migrate_authority(&admin)], &[]).await;
+    let migrated = env.raw_account(&address).await.unwrap();
+    assert_eq!(migrated.data, account.data);
+    assert_eq!(migrated.lamports, account.lamports);
+
+    env.execute(&[create()], &[&newcomer]).await;
+}
+
+#[tokio::test]
+async fn admin_sequence_starts_at_the_migration() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let integrator = env.integrator.pubkey();
+    write_legacy_authority(&mut env, vec![integrator]);
+
+    // Sequenced admin events wait for the migration, so none are numbered twice
+    env.expect_error(
+        &[ix::set_integrator_cap(&admin, &integrator, 1_000)],
+        &[],
+        VaultError::AuthorityNotMigrated,
+    )
+    .await;
+
+    env.execute(
+        &[
+            ix::migrate_authority(&admin),
+            ix::set_integrator_cap(&admin, &integrator, 1_000),
+        ],
+        &[],
+    )
+    .await;
+    let authority: VaultAuthority = env.account(&pda::vault_authority().0).await;
+    assert_eq!(authority.sequence, 2);
+}
+
+#[tokio::test]
+async fn full_legacy_authority_is_migrated() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+
+    // Ten programs fill the original allocation, leaving no room for the new fields
+    let programs: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+    write_legacy_authority(&mut env, programs.clone());
+
+    env.execute(&[ix::migrate_authority(&admin)], &[]).await;
+
+    let authority: VaultAuthority = env.account(&pda::vault_authority().0).await;
+    assert_eq!(authority.authorized_programs, programs);
+    assert_eq!(authority.denied_extensions, DEFAULT_DENIED_EXTENSIONS);
+    env.execute(&[ix::remove_authorized_program(&admin, programs[0])], &[])
+        .await;
+    env.execute(&[ix::add_authorized_program(&admin, Pubkey::new_unique())], &[])
+        .await;
+}
+
+#[tokio::test]
+async fn authority_migration_rejects_foreign_layouts() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let address = pda::vault_authority().0;
+
+    let mut account = env.raw_account(&address).await.unwrap();
+    account.data.push(0);
+    env.context.set_account(&address, &AccountSharedData::from(account));
+    env.expect_error(
+        &[ix::migrate_authority(&admin)],
+        &[],
+        VaultError::InvalidAuthorityLayout,
+    )
+    .await;
+}
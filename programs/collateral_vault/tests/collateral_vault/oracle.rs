@@ -0,0 +1,431 @@
+//! Oracle valuation and value-denominated locks.
+//!
+//! Pyth `PriceUpdateV2` accounts are written straight into the bank. The mock
+//! feed only exists when the program is built with `mock-oracle`; the tests
+//! that publish mock prices fail against a program built without it.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use collateral_vault::{
+    BasketLeg, BasketWeight, CollateralValue, CollateralVault, OracleConfigParams, PortfolioVault,
+    PythPriceMessage, PythPriceUpdate, PythVerificationLevel, VaultError, PYTH_RECEIVER_ID,
+};
+use collateral_vault_client::{accounts::decode_return_data, instructions as ix, pda};
+use solana_account::{Account, AccountSharedData};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::setup::{custom_error_code, token_programs, TestEnv, TestMint};
+
+/// One token at 6 decimals
+const ONE_TOKEN: u64 = 1_000_000;
+/// $2.00 with a one cent confidence interval
+const PRICE: i64 = 200;
+const CONF: u64 = 1;
+const EXPO: i32 = -2;
+
+struct Oracle {
+    env: TestEnv,
+    mint: TestMint,
+    feed: Keypair,
+    user: Keypair,
+}
+
+impl Oracle {
+    /// Vault holding one token, priced by a fresh mock feed at $2.00 with a 10% haircut
+    async fn new() -> Self {
+        let mut env = TestEnv::new().await;
+        let admin = env.admin.pubkey();
+        let feed = Keypair::new();
+
+        if let Err(err) = env
+            .process(
+                &[ix::initialize_mock_price_feed(&admin, &feed.pubkey())],
+                &[&feed],
+            )
+            .await
+        {
+            assert_ne!(
+                custom_error_code(&err),
+                Some(ErrorCode::InstructionFallbackNotFound as u32),
+                "program built without mock-oracle, rebuild with `--features mock-oracle`"
+            );
+            panic!("mock price feed: {err}");
+        }
+
+        let mint = env.approved_mint(&token_programs()[1]).await;
+        env.execute(
+            &[ix::initialize_oracle_config(
+                &admin,
+                &mint.address,
+                params(feed.pubkey()),
+            )],
+            &[],
+        )
+        .await;
+        let user = env.user_with_vault(&mint, ONE_TOKEN).await;
+
+        let mut oracle = Self {
+            env,
+            mint,
+            feed,
+            user,
+        };
+        oracle.set_price(PRICE, CONF, None).await;
+        oracle
+    }
+
+    async fn set_price(&mut self, price: i64, conf: u64, publish_time: Option<i64>) {
+        let admin = self.env.admin.pubkey();
+        self.env
+            .execute(
+                &[ix::set_mock_price(
+                    &admin,
+                    &self.feed.pubkey(),
+                    price,
+                    conf,
+                    EXPO,
+                    publish_time,
+                )],
+                &[],
+            )
+            .await;
+    }
+
+    fn value_ix(&self) -> Instruction {
+        ix::get_collateral_value(&self.user.pubkey(), &self.mint.address, &self.feed.pubkey())
+    }
+}
+
+fn params(oracle: Pubkey) -> OracleConfigParams {
+    OracleConfigParams {
+        oracle,
+        max_staleness: 60,
+        max_confidence_bps: 100,
+        haircut_bps: 1_000,
+    }
+}
+
+/// `PriceUpdateV2` account at $2.00, published at `publish_time`
+fn pyth_account(
+    owner: Pubkey,
+    verification_level: PythVerificationLevel,
+    publish_time: i64,
+) -> AccountSharedData {
+    let update = PythPriceUpdate {
+        write_authority: Pubkey::new_unique(),
+        verification_level,
+        price_message: PythPriceMessage {
+            feed_id: [7; 32],
+            price: PRICE,
+            conf: CONF,
+            exponent: EXPO,
+            publish_time,
+            prev_publish_time: publish_time - 1,
+            ema_price: PRICE,
+            ema_conf: CONF,
+        },
+        posted_slot: 1,
+    };
+    let mut data = PythPriceUpdate::DISCRIMINATOR.to_vec();
+    update.serialize(&mut data).unwrap();
+
+    AccountSharedData::from(Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    })
+}
+
+#[tokio::test]
+async fn pyth_price_updates_are_read() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, ONE_TOKEN).await;
+    let feed = Pubkey::new_unique();
+    env.execute(
+        &[ix::initialize_oracle_config(&admin, &mint.address, params(feed))],
+        &[],
+    )
+    .await;
+    let now = env.unix_timestamp().await;
+    let owner = user.pubkey();
+    let value_ix = || ix::get_collateral_value(&owner, &mint.address, &feed);
+
+    env.context.set_account(
+        &feed,
+        &pyth_account(PYTH_RECEIVER_ID, PythVerificationLevel::Full, now),
+    );
+    let outcome = env.execute(&[value_ix()], &[]).await;
+    let value: CollateralValue = decode_return_data(&outcome.return_data).unwrap();
+    assert_eq!(value.price, PRICE);
+    assert_eq!(value.expo, EXPO);
+    assert_eq!(value.total_value, 1_800_000);
+
+    // Partially verified updates and look-alikes owned by another program
+    let rejected = [
+        pyth_account(
+            PYTH_RECEIVER_ID,
+            PythVerificationLevel::Partial { num_signatures: 5 },
+            now,
+        ),
+        pyth_account(Pubkey::new_unique(), PythVerificationLevel::Full, now),
+    ];
+    for account in rejected {
+        env.context.set_account(&feed, &account);
+        env.expect_error(&[value_ix()], &[], VaultError::InvalidOracle)
+            .await;
+    }
+
+    env.context.set_account(
+        &feed,
+        &pyth_account(PYTH_RECEIVER_ID, PythVerificationLevel::Full, now - 61),
+    );
+    env.expect_error(&[value_ix()], &[], VaultError::StaleOraclePrice)
+        .await;
+}
+
+#[tokio::test]
+async fn collateral_value_applies_price_and_haircut() {
+    let mut oracle = Oracle::new().await;
+
+    let outcome = oracle.env.execute(&[oracle.value_ix()], &[]).await;
+    let value: CollateralValue = decode_return_data(&outcome.return_data).unwrap();
+    assert_eq!(value.vault, pda::vault(&oracle.user.pubkey()).0);
+    assert_eq!(value.total_balance, ONE_TOKEN);
+    assert_eq!(value.price, PRICE);
+    assert_eq!(value.expo, EXPO);
+    assert_eq!(value.haircut_bps, 1_000);
+    assert_eq!(value.total_value, 1_800_000);
+    assert_eq!(value.available_value, 1_800_000);
+}
+
+#[tokio::test]
+async fn lock_value_converts_at_the_oracle_price() {
+    let mut oracle = Oracle::new().await;
+    let integrator = oracle.env.integrator.insecure_clone();
+    let owner = oracle.user.pubkey();
+    let lock = |value| {
+        ix::lock_value(
+            &integrator.pubkey(),
+            &owner,
+            &oracle.mint.address,
+            &oracle.feed.pubkey(),
+            value,
+        )
+    };
+    let (zero, too_much, half) = (lock(0), lock(1_800_001), lock(900_000));
+
+    oracle
+        .env
+        .expect_error(&[zero], &[&integrator], VaultError::InvalidAmount)
+        .await;
+    oracle
+        .env
+        .expect_error(
+            &[too_much],
+            &[&integrator],
+            VaultError::InsufficientAvailableBalance,
+        )
+        .await;
+
+    // $0.90 after a 10% haircut needs $1.00 of collateral, i.e. half a token
+    oracle.env.execute(&[half], &[&integrator]).await;
+    let vault: CollateralVault = oracle.env.account(&pda::vault(&owner).0).await;
+    assert_eq!(vault.locked_balance, ONE_TOKEN / 2);
+    assert_eq!(vault.available_balance, ONE_TOKEN / 2);
+}
+
+#[tokio::test]
+async fn portfolio_value_lock_splits_by_weight() {
+    let mut oracle = Oracle::new().await;
+    let admin = oracle.env.admin.pubkey();
+    let feed = oracle.feed.pubkey();
+    let integrator = oracle.env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+
+    // Portfolio holding one token of each mint, both priced by the same feed
+    let legacy = oracle.env.approved_mint(&token_programs()[0]).await;
+    oracle
+        .env
+        .execute(
+            &[ix::initialize_oracle_config(&admin, &legacy.address, params(feed))],
+            &[],
+        )
+        .await;
+    let user = oracle.env.wallet().await;
+    let owner = user.pubkey();
+    let mut instructions = vec![ix::initialize_portfolio_vault(&owner)];
+    for mint in [&oracle.mint, &legacy] {
+        oracle.env.fund(&owner, mint, ONE_TOKEN).await;
+        instructions.push(ix::add_portfolio_mint(&owner, &mint.address, &mint.token_program));
+        instructions.push(ix::deposit_portfolio(
+            &owner,
+            &mint.address,
+            &mint.token_program,
+            ONE_TOKEN,
+        ));
+    }
+    oracle.env.execute(&instructions, &[&user]).await;
+
+    let weight = |mint: &TestMint, weight_bps| {
+        (
+            BasketWeight {
+                mint: mint.address,
+                weight_bps,
+            },
+            feed,
+        )
+    };
+    let lock = |legs: &[(BasketWeight, Pubkey)], value| {
+        ix::lock_portfolio_value(&signer, &owner, value, legs)
+    };
+
+    let unbalanced = lock(&[weight(&oracle.mint, 5_000), weight(&legacy, 4_000)], 900_000);
+    let zero_weight = lock(&[weight(&oracle.mint, 10_000), weight(&legacy, 0)], 900_000);
+    for invalid in [unbalanced, zero_weight] {
+        oracle
+            .env
+            .expect_error(&[invalid], &[&integrator], VaultError::InvalidBasket)
+            .await;
+    }
+    let mut wrong_feed = lock(&[weight(&oracle.mint, 10_000)], 900_000);
+    wrong_feed.accounts.last_mut().unwrap().pubkey = Pubkey::new_unique();
+    oracle
+        .env
+        .expect_error(&[wrong_feed], &[&integrator], VaultError::InvalidOracle)
+        .await;
+
+    // $1.80 split evenly: each $0.90 leg needs half a token at $2.00 less 10%
+    let outcome = oracle
+        .env
+        .execute(
+            &[lock(&[weight(&oracle.mint, 5_000), weight(&legacy, 5_000)], 1_800_000)],
+            &[&integrator],
+        )
+        .await;
+    let locked: Vec<BasketLeg> = decode_return_data(&outcome.return_data).unwrap();
+    assert_eq!(locked.len(), 2);
+    assert!(locked.iter().all(|leg| leg.amount == ONE_TOKEN / 2));
+    assert_eq!(locked[1].mint, legacy.address);
+
+    let portfolio_address = pda::portfolio(&owner).0;
+    let state: PortfolioVault = oracle.env.account(&portfolio_address).await;
+    assert!(state.balances.iter().all(|b| b.locked_balance == ONE_TOKEN / 2));
+
+    // The returned amounts unlock the basket exactly
+    oracle
+        .env
+        .execute(
+            &[ix::unlock_portfolio_basket(&signer, &owner, locked)],
+            &[&integrator],
+        )
+        .await;
+    let state: PortfolioVault = oracle.env.account(&portfolio_address).await;
+    assert!(state.balances.iter().all(|b| b.locked_balance == 0));
+}
+
+#[tokio::test]
+async fn unusable_prices_are_rejected() {
+    let mut oracle = Oracle::new().await;
+
+    let wrong_feed = ix::get_collateral_value(
+        &oracle.user.pubkey(),
+        &oracle.mint.address,
+        &Pubkey::new_unique(),
+    );
+    oracle
+        .env
+        .expect_error(&[wrong_feed], &[], VaultError::InvalidOracle)
+        .await;
+
+    oracle.set_price(0, 0, None).await;
+    let value = oracle.value_ix();
+    oracle
+        .env
+        .expect_error(&[value], &[], VaultError::InvalidOraclePrice)
+        .await;
+
+    let now = oracle.env.unix_timestamp().await;
+    oracle.set_price(PRICE, CONF, Some(now - 61)).await;
+    let value = oracle.value_ix();
+    oracle
+        .env
+        .expect_error(&[value], &[], VaultError::StaleOraclePrice)
+        .await;
+
+    // 3 cents on $2.00 is 150 bps, over the 100 bps limit
+    oracle.set_price(PRICE, 3, None).await;
+    let value = oracle.value_ix();
+    oracle
+        .env
+        .expect_error(&[value], &[], VaultError::OracleConfidenceTooWide)
+        .await;
+}
+
+#[tokio::test]
+async fn oracle_config_is_validated() {
+    let mut oracle = Oracle::new().await;
+    let admin = oracle.env.admin.pubkey();
+    let feed = oracle.feed.pubkey();
+    let mint = oracle.env.approved_mint(&token_programs()[0]).await;
+
+    let invalid = [
+        OracleConfigParams {
+            max_staleness: 0,
+            ..params(feed)
+        },
+        OracleConfigParams {
+            max_confidence_bps: 10_001,
+            ..params(feed)
+        },
+        OracleConfigParams {
+            haircut_bps: 10_000,
+            ..params(feed)
+        },
+    ];
+    for invalid_params in invalid {
+        oracle
+            .env
+            .expect_error(
+                &[ix::initialize_oracle_config(&admin, &mint.address, invalid_params.clone())],
+                &[],
+                VaultError::InvalidOracleConfig,
+            )
+            .await;
+        oracle
+            .env
+            .expect_error(
+                &[ix::update_oracle_config(&admin, &oracle.mint.address, invalid_params)],
+                &[],
+                VaultError::InvalidOracleConfig,
+            )
+            .await;
+    }
+
+    let intruder = oracle.env.wallet().await;
+    oracle
+        .env
+        .expect_error(
+            &[ix::update_oracle_config(
+                &intruder.pubkey(),
+                &oracle.mint.address,
+                params(feed),
+            )],
+            &[&intruder],
+            VaultError::Unauthorized,
+        )
+        .await;
+    oracle
+        .env
+        .expect_error(
+            &[ix::set_mock_price(&intruder.pubkey(), &feed, PRICE, CONF, EXPO, None)],
+            &[&intruder],
+            VaultError::Unauthorized,
+        )
+        .await;
+}
@@ -0,0 +1,335 @@
+//! Pooled vaults backed by a per-mint custody account.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use collateral_vault::{CollateralVault, CustodyPool, IntegratorStats, SubAccount, VaultError, ID};
+use collateral_vault_client::{instructions as ix, pda};
+use solana_account::{Account, AccountSharedData};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::setup::{token_programs, TestEnv, TestMint};
+
+const INTERNAL_TRANSFER_BUDGET: u64 = 25_000;
+
+async fn pooled_mint(env: &mut TestEnv, token_program: &Pubkey) -> TestMint {
+    let mint = env.approved_mint(token_program).await;
+    let admin = env.admin.pubkey();
+    env.execute(
+        &[ix::initialize_custody_pool(&admin, &mint.address, token_program)],
+        &[],
+    )
+    .await;
+    mint
+}
+
+/// Funded wallet with a pooled vault holding `deposit` of `mint`
+async fn pooled_user(env: &mut TestEnv, mint: &TestMint, deposit: u64) -> Keypair {
+    let user = env.user(mint, deposit).await;
+    let owner = user.pubkey();
+
+    let mut instructions = vec![ix::initialize_pooled_vault(
+        &owner,
+        &mint.address,
+        &mint.token_program,
+    )];
+    if deposit > 0 {
+        instructions.push(ix::deposit_pooled(
+            &owner,
+            &mint.address,
+            &mint.token_program,
+            deposit,
+        ));
+    }
+    env.execute(&instructions, &[&user]).await;
+    user
+}
+
+fn custody_token_account(mint: &TestMint) -> Pubkey {
+    ix::vault_token_account(
+        &pda::custody_pool(&mint.address).0,
+        &mint.address,
+        &mint.token_program,
+    )
+}
+
+async fn custody_pool(env: &mut TestEnv, mint: &TestMint) -> CustodyPool {
+    env.account(&pda::custody_pool(&mint.address).0).await
+}
+
+async fn vault(env: &mut TestEnv, owner: &Keypair) -> CollateralVault {
+    env.account(&pda::vault(&owner.pubkey()).0).await
+}
+
+#[tokio::test]
+async fn pooled_deposit_and_withdraw_use_custody_account() {
+    for token_program in token_programs() {
+        let mut env = TestEnv::new().await;
+        let mint = pooled_mint(&mut env, &token_program).await;
+        let user = pooled_user(&mut env, &mint, 500).await;
+        let owner = user.pubkey();
+
+        let vault_state = vault(&mut env, &user).await;
+        assert!(vault_state.pooled);
+        assert_eq!(vault_state.token_account, custody_token_account(&mint));
+        assert_eq!(vault_state.total_balance, 500);
+
+        env.expect_error(
+            &[ix::withdraw_pooled(&owner, &mint.address, &token_program, 501)],
+            &[&user],
+            VaultError::InsufficientAvailableBalance,
+        )
+        .await;
+        env.execute(
+            &[ix::withdraw_pooled(&owner, &mint.address, &token_program, 200)],
+            &[&user],
+        )
+        .await;
+
+        let vault_state = vault(&mut env, &user).await;
+        assert_eq!(vault_state.total_balance, 300);
+        assert_eq!(vault_state.total_withdrawn, 200);
+        let pool = custody_pool(&mut env, &mint).await;
+        assert_eq!(pool.total_balance, 300);
+        assert_eq!(pool.vault_count, 1);
+        assert_eq!(env.token_balance(&custody_token_account(&mint)).await, 300);
+        let user_token_account = ix::user_token_account(&owner, &mint.address, &token_program);
+        assert_eq!(env.token_balance(&user_token_account).await, 200);
+
+        env.execute(
+            &[ix::verify_custody_pool(&mint.address, &token_program, &[owner])],
+            &[],
+        )
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn pooled_and_standalone_paths_are_separate() {
+    let mut env = TestEnv::new().await;
+    let mint = pooled_mint(&mut env, &token_programs()[1]).await;
+    let pooled = pooled_user(&mut env, &mint, 100).await;
+    let standalone = env.user_with_vault(&mint, 100).await;
+    let integrator = env.integrator.insecure_clone();
+
+    // Give the pooled vault the token account a standalone vault would have,
+    // so the account constraints are what rejects the call
+    env.fund(&pda::vault(&pooled.pubkey()).0, &mint, 0).await;
+    env.fund(&pooled.pubkey(), &mint, 10).await;
+
+    env.expect_error(
+        &[ix::deposit(&pooled.pubkey(), &mint.address, &mint.token_program, 10)],
+        &[&pooled],
+        VaultError::PooledVault,
+    )
+    .await;
+    env.expect_error(
+        &[ix::transfer_collateral(
+            &integrator.pubkey(),
+            &pooled.pubkey(),
+            &standalone.pubkey(),
+            &mint.address,
+            &mint.token_program,
+            10,
+        )],
+        &[&integrator],
+        VaultError::PooledVault,
+    )
+    .await;
+
+    env.fund(&standalone.pubkey(), &mint, 10).await;
+    env.expect_error(
+        &[ix::deposit_pooled(
+            &standalone.pubkey(),
+            &mint.address,
+            &mint.token_program,
+            10,
+        )],
+        &[&standalone],
+        VaultError::NotPooledVault,
+    )
+    .await;
+    env.expect_error(
+        &[ix::transfer_collateral_internal(
+            &integrator.pubkey(),
+            &pooled.pubkey(),
+            &standalone.pubkey(),
+            10,
+        )],
+        &[&integrator],
+        VaultError::NotPooledVault,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn internal_transfer_updates_ledgers_only() {
+    let mut env = TestEnv::new().await;
+    let mint = pooled_mint(&mut env, &token_programs()[0]).await;
+    let from = pooled_user(&mut env, &mint, 100).await;
+    let to = pooled_user(&mut env, &mint, 0).await;
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+
+    env.expect_error(
+        &[ix::transfer_collateral_internal(&signer, &from.pubkey(), &from.pubkey(), 10)],
+        &[&integrator],
+        VaultError::SameVault,
+    )
+    .await;
+    env.expect_error(
+        &[ix::transfer_collateral_internal(&signer, &from.pubkey(), &to.pubkey(), 0)],
+        &[&integrator],
+        VaultError::InvalidAmount,
+    )
+    .await;
+
+    let outcome = env
+        .execute(
+            &[ix::transfer_collateral_internal(&signer, &from.pubkey(), &to.pubkey(), 40)],
+            &[&integrator],
+        )
+        .await;
+    env.assert_compute_units(
+        &outcome,
+        INTERNAL_TRANSFER_BUDGET,
+        "transfer_collateral_internal",
+    );
+
+    let from_state = vault(&mut env, &from).await;
+    let to_state = vault(&mut env, &to).await;
+    assert_eq!(from_state.total_balance, 60);
+    assert_eq!(from_state.total_transferred_out, 40);
+    assert_eq!(to_state.available_balance, 40);
+    assert_eq!(to_state.total_transferred_in, 40);
+    let integrator_stats: IntegratorStats =
+        env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!(integrator_stats.total_transferred, 40);
+    assert_eq!(custody_pool(&mut env, &mint).await.total_balance, 100);
+    assert_eq!(env.token_balance(&custody_token_account(&mint)).await, 100);
+
+    env.execute(
+        &[ix::verify_custody_pool(
+            &mint.address,
+            &mint.token_program,
+            &[from.pubkey(), to.pubkey()],
+        )],
+        &[],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn verify_custody_pool_detects_mismatches() {
+    let mut env = TestEnv::new().await;
+    let mint = pooled_mint(&mut env, &token_programs()[1]).await;
+    let first = pooled_user(&mut env, &mint, 100).await;
+    let second = pooled_user(&mut env, &mint, 50).await;
+    let standalone = env.user_with_vault(&mint, 0).await;
+
+    let verify = |owners: &[Pubkey]| {
+        ix::verify_custody_pool(&mint.address, &mint.token_program, owners)
+    };
+
+    for owners in [
+        vec![first.pubkey()],
+        vec![first.pubkey(), first.pubkey()],
+        vec![first.pubkey(), standalone.pubkey()],
+    ] {
+        env.expect_error(&[verify(&owners)], &[], VaultError::PoolVaultSetMismatch)
+            .await;
+    }
+
+    // Tokens sent straight to the custody account aren't owed to any vault
+    let donor = env.user(&mint, 5).await;
+    let donor_token_account =
+        ix::user_token_account(&donor.pubkey(), &mint.address, &mint.token_program);
+    env.transfer_tokens(
+        &mint,
+        &donor_token_account,
+        &custody_token_account(&mint),
+        &donor,
+        5,
+    )
+    .await;
+    env.expect_error(
+        &[verify(&[first.pubkey(), second.pubkey()])],
+        &[],
+        VaultError::CustodyBalanceMismatch,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn closing_a_pooled_vault_requires_its_custody_pool() {
+    let mut env = TestEnv::new().await;
+    let mint = pooled_mint(&mut env, &token_programs()[0]).await;
+    let user = pooled_user(&mut env, &mint, 0).await;
+    let owner = user.pubkey();
+
+    let mut close = ix::close_vault(&owner, &mint.address, &mint.token_program, true);
+    close.accounts[4] = AccountMeta::new_readonly(collateral_vault::ID, false);
+    env.expect_error(&[close], &[&user], VaultError::MissingCustodyPool)
+        .await;
+
+    env.execute(
+        &[ix::close_vault(&owner, &mint.address, &mint.token_program, true)],
+        &[&user],
+    )
+    .await;
+    assert!(!env.exists(&pda::vault(&owner).0).await);
+    assert_eq!(custody_pool(&mut env, &mint).await.vault_count, 0);
+}
+
+#[tokio::test]
+async fn pooled_vaults_cannot_use_sub_accounts() {
+    let mut env = TestEnv::new().await;
+    let mint = pooled_mint(&mut env, &token_programs()[0]).await;
+    let user = pooled_user(&mut env, &mint, 100).await;
+    let owner = user.pubkey();
+    let vault_address = pda::vault(&owner).0;
+
+    env.expect_error(
+        &[ix::create_sub_account(&owner, 0)],
+        &[&user],
+        VaultError::PooledVault,
+    )
+    .await;
+
+    // Moving pooled funds into a sub-account would take them out of the
+    // balances verify_custody_pool sums while the custody account still holds them
+    let (address, bump) = pda::sub_account(&vault_address, 0);
+    let sub_account = SubAccount {
+        vault: vault_address,
+        id: 0,
+        total_balance: 0,
+        locked_balance: 0,
+        available_balance: 0,
+        created_at: 0,
+        bump,
+    };
+    let mut data = Vec::new();
+    sub_account.try_serialize(&mut data).unwrap();
+    env.context.set_account(
+        &address,
+        &AccountSharedData::from(Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: ID,
+            executable: false,
+            rent_epoch: 0,
+        }),
+    );
+    env.expect_error(
+        &[ix::allocate_to_sub_account(&owner, 0, 50)],
+        &[&user],
+        VaultError::PooledVault,
+    )
+    .await;
+
+    env.execute(
+        &[ix::verify_custody_pool(&mint.address, &mint.token_program, &[owner])],
+        &[],
+    )
+    .await;
+}
@@ -0,0 +1,378 @@
+//! Multi-collateral portfolio vaults mixing legacy SPL and Token-2022 mints.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token_2022::spl_token_2022::{self, state::Account as TokenAccount};
+use collateral_vault::{
+    BasketLeg, IntegratorPosition, IntegratorStats, MintBalance, MintStats, PortfolioVault,
+    VaultError,
+};
+use collateral_vault_client::{instructions as ix, pda};
+use solana_account::AccountSharedData;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_system_interface::instruction as system_instruction;
+
+use crate::setup::{token_programs, TestEnv, TestMint};
+
+/// Portfolio holding `deposit` of one legacy SPL mint and one Token-2022 mint
+async fn portfolio_user(env: &mut TestEnv, deposit: u64) -> (Keypair, [TestMint; 2]) {
+    let [legacy, token_2022] = token_programs();
+    let mints = [
+        env.approved_mint(&legacy).await,
+        env.approved_mint(&token_2022).await,
+    ];
+    let user = env.wallet().await;
+    let owner = user.pubkey();
+
+    let mut instructions = vec![ix::initialize_portfolio_vault(&owner)];
+    for mint in &mints {
+        env.fund(&owner, mint, deposit).await;
+        instructions.push(ix::add_portfolio_mint(
+            &owner,
+            &mint.address,
+            &mint.token_program,
+        ));
+        if deposit > 0 {
+            instructions.push(ix::deposit_portfolio(
+                &owner,
+                &mint.address,
+                &mint.token_program,
+                deposit,
+            ));
+        }
+    }
+    env.execute(&instructions, &[&user]).await;
+    (user, mints)
+}
+
+async fn portfolio(env: &mut TestEnv, owner: &Keypair) -> PortfolioVault {
+    env.account(&pda::portfolio(&owner.pubkey()).0).await
+}
+
+fn leg(mint: &TestMint, amount: u64) -> BasketLeg {
+    BasketLeg {
+        mint: mint.address,
+        amount,
+    }
+}
+
+fn empty_balance(mint: Pubkey) -> MintBalance {
+    MintBalance {
+        mint,
+        token_account: Pubkey::new_unique(),
+        total_balance: 0,
+        locked_balance: 0,
+        available_balance: 0,
+        total_deposited: 0,
+        total_withdrawn: 0,
+    }
+}
+
+#[tokio::test]
+async fn deposit_and_withdraw_per_mint() {
+    let mut env = TestEnv::new().await;
+    let (user, mints) = portfolio_user(&mut env, 100).await;
+    let owner = user.pubkey();
+    let portfolio_address = pda::portfolio(&owner).0;
+
+    let state = portfolio(&mut env, &user).await;
+    assert_eq!(state.owner, owner);
+    assert_eq!(state.balances.len(), 2);
+    for (balance, mint) in state.balances.iter().zip(&mints) {
+        assert_eq!(balance.mint, mint.address);
+        assert_eq!(
+            balance.token_account,
+            ix::vault_token_account(&portfolio_address, &mint.address, &mint.token_program)
+        );
+        assert_eq!(balance.total_balance, 100);
+    }
+
+    let [legacy, token_2022] = &mints;
+    env.expect_error(
+        &[ix::withdraw_portfolio(
+            &owner,
+            &legacy.address,
+            &legacy.token_program,
+            101,
+        )],
+        &[&user],
+        VaultError::InsufficientAvailableBalance,
+    )
+    .await;
+    env.execute(
+        &[ix::withdraw_portfolio(
+            &owner,
+            &token_2022.address,
+            &token_2022.token_program,
+            30,
+        )],
+        &[&user],
+    )
+    .await;
+
+    let state = portfolio(&mut env, &user).await;
+    assert_eq!(state.balances[0].total_balance, 100);
+    assert_eq!(state.balances[1].total_balance, 70);
+    assert_eq!(state.balances[1].total_withdrawn, 30);
+    let user_token_account =
+        ix::user_token_account(&owner, &token_2022.address, &token_2022.token_program);
+    assert_eq!(env.token_balance(&user_token_account).await, 30);
+}
+
+#[tokio::test]
+async fn single_leg_and_basket_locks() {
+    let mut env = TestEnv::new().await;
+    let (user, mints) = portfolio_user(&mut env, 100).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+    let [legacy, token_2022] = &mints;
+
+    env.execute(
+        &[ix::lock_portfolio_collateral(&signer, &owner, legacy.address, 40)],
+        &[&integrator],
+    )
+    .await;
+    env.expect_error(
+        &[ix::unlock_portfolio_collateral(&signer, &owner, legacy.address, 41)],
+        &[&integrator],
+        VaultError::InsufficientLockedBalance,
+    )
+    .await;
+
+    // A failing leg reverts the whole basket
+    env.expect_error(
+        &[ix::lock_portfolio_basket(
+            &signer,
+            &owner,
+            vec![leg(token_2022, 50), leg(legacy, 61)],
+        )],
+        &[&integrator],
+        VaultError::InsufficientAvailableBalance,
+    )
+    .await;
+    assert_eq!(portfolio(&mut env, &user).await.balances[1].locked_balance, 0);
+
+    env.execute(
+        &[ix::lock_portfolio_basket(
+            &signer,
+            &owner,
+            vec![leg(token_2022, 50), leg(legacy, 60)],
+        )],
+        &[&integrator],
+    )
+    .await;
+    let state = portfolio(&mut env, &user).await;
+    assert_eq!(state.balances[0].locked_balance, 100);
+    assert_eq!(state.balances[0].available_balance, 0);
+    assert_eq!(state.balances[1].locked_balance, 50);
+    for (mint, locked) in [(legacy, 100), (token_2022, 50)] {
+        let stats: MintStats = env.account(&pda::mint_stats(&mint.address).0).await;
+        assert_eq!(stats.total_locked, locked);
+    }
+    let integrator_stats: IntegratorStats =
+        env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!(integrator_stats.locked_balance, 150);
+    // The position splits the program's locks by mint
+    let position: IntegratorPosition = env
+        .account(&pda::integrator_position(&signer, &pda::portfolio(&owner).0).0)
+        .await;
+    assert_eq!(position.locked(&legacy.address), 100);
+    assert_eq!(position.locked(&token_2022.address), 50);
+
+    env.execute(
+        &[ix::unlock_portfolio_basket(
+            &signer,
+            &owner,
+            vec![leg(token_2022, 50), leg(legacy, 100)],
+        )],
+        &[&integrator],
+    )
+    .await;
+    let state = portfolio(&mut env, &user).await;
+    assert!(state.balances.iter().all(|b| b.locked_balance == 0));
+    for mint in &mints {
+        let stats: MintStats = env.account(&pda::mint_stats(&mint.address).0).await;
+        assert_eq!(stats.total_locked, 0);
+    }
+    let integrator_stats: IntegratorStats =
+        env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!(integrator_stats.locked_balance, 0);
+    assert_eq!(integrator_stats.total_locked, 150);
+}
+
+#[tokio::test]
+async fn basket_and_mint_validation() {
+    let mut env = TestEnv::new().await;
+    let (user, mints) = portfolio_user(&mut env, 10).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+
+    let oversized = vec![leg(&mints[0], 1); 9];
+    for legs in [Vec::new(), oversized] {
+        env.expect_error(
+            &[ix::lock_portfolio_basket(&signer, &owner, legs.clone())],
+            &[&integrator],
+            VaultError::InvalidBasket,
+        )
+        .await;
+        env.expect_error(
+            &[ix::unlock_portfolio_basket(&signer, &owner, legs)],
+            &[&integrator],
+            VaultError::InvalidBasket,
+        )
+        .await;
+    }
+
+    env.expect_error(
+        &[ix::lock_portfolio_collateral(&signer, &owner, Pubkey::new_unique(), 1)],
+        &[&integrator],
+        VaultError::MintNotInPortfolio,
+    )
+    .await;
+
+    // A copy of the mint's stats owned by the program but away from its PDA
+    let mint = mints[0].address;
+    let stats_address = pda::mint_stats(&mint).0;
+    let stats_copy = Pubkey::new_unique();
+    let account = env.raw_account(&stats_address).await.unwrap();
+    env.context.set_account(&stats_copy, &AccountSharedData::from(account));
+    for mut instruction in [
+        ix::lock_portfolio_collateral(&signer, &owner, mint, 1),
+        ix::lock_portfolio_basket(&signer, &owner, vec![leg(&mints[0], 1)]),
+    ] {
+        let meta = instruction
+            .accounts
+            .iter_mut()
+            .find(|meta| meta.pubkey == stats_address)
+            .unwrap();
+        meta.pubkey = stats_copy;
+        env.expect_anchor_error(&[instruction], &[&integrator], ErrorCode::ConstraintSeeds)
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn portfolio_mint_list_is_bounded_and_unique() {
+    let mut env = TestEnv::new().await;
+    let (user, _) = portfolio_user(&mut env, 0).await;
+    let owner = user.pubkey();
+    let portfolio_address = pda::portfolio(&owner).0;
+
+    // Re-adding a mint fails on the token account `init` before the handler
+    // runs, so the handler's own checks are exercised with patched entries
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    env.patch_account::<PortfolioVault>(&portfolio_address, |portfolio| {
+        portfolio.balances.push(empty_balance(mint.address))
+    })
+    .await;
+    env.expect_error(
+        &[ix::add_portfolio_mint(&owner, &mint.address, &mint.token_program)],
+        &[&user],
+        VaultError::MintAlreadyAdded,
+    )
+    .await;
+
+    let mint = env.approved_mint(&token_programs()[0]).await;
+    env.patch_account::<PortfolioVault>(&portfolio_address, |portfolio| {
+        while portfolio.balances.len() < 8 {
+            portfolio.balances.push(empty_balance(Pubkey::new_unique()));
+        }
+    })
+    .await;
+    env.expect_error(
+        &[ix::add_portfolio_mint(&owner, &mint.address, &mint.token_program)],
+        &[&user],
+        VaultError::TooManyMints,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn deposits_must_use_the_recorded_token_account() {
+    let mut env = TestEnv::new().await;
+    let (user, mints) = portfolio_user(&mut env, 10).await;
+    let owner = user.pubkey();
+    let mint = &mints[0];
+    env.fund(&owner, mint, 10).await;
+
+    // Second token account owned by the portfolio PDA, but not its ATA
+    let stray = Keypair::new();
+    let admin = env.admin.pubkey();
+    env.execute(
+        &[
+            system_instruction::create_account(
+                &admin,
+                &stray.pubkey(),
+                Rent::default().minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                &mint.token_program,
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &mint.token_program,
+                &stray.pubkey(),
+                &mint.address,
+                &pda::portfolio(&owner).0,
+            )
+            .unwrap(),
+        ],
+        &[&stray],
+    )
+    .await;
+
+    let mut deposit = ix::deposit_portfolio(&owner, &mint.address, &mint.token_program, 10);
+    deposit.accounts[6].pubkey = stray.pubkey();
+    env.expect_error(&[deposit], &[&user], VaultError::InvalidTokenAccount)
+        .await;
+
+    let mut withdraw = ix::withdraw_portfolio(&owner, &mint.address, &mint.token_program, 10);
+    withdraw.accounts[5].pubkey = stray.pubkey();
+    env.expect_error(&[withdraw], &[&user], VaultError::InvalidTokenAccount)
+        .await;
+}
+
+#[tokio::test]
+async fn only_empty_portfolios_close() {
+    let mut env = TestEnv::new().await;
+    let (user, mints) = portfolio_user(&mut env, 10).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+    let [legacy, token_2022] = &mints;
+
+    env.execute(
+        &[ix::withdraw_portfolio(
+            &owner,
+            &token_2022.address,
+            &token_2022.token_program,
+            10,
+        )],
+        &[&user],
+    )
+    .await;
+    env.execute(
+        &[ix::lock_portfolio_collateral(&signer, &owner, legacy.address, 10)],
+        &[&integrator],
+    )
+    .await;
+    env.expect_error(
+        &[ix::close_portfolio_vault(&owner)],
+        &[&user],
+        VaultError::VaultNotEmpty,
+    )
+    .await;
+
+    env.execute(
+        &[
+            ix::unlock_portfolio_collateral(&signer, &owner, legacy.address, 10),
+            ix::withdraw_portfolio(&owner, &legacy.address, &legacy.token_program, 10),
+        ],
+        &[&integrator, &user],
+    )
+    .await;
+    env.execute(&[ix::close_portfolio_vault(&owner)], &[&user])
+        .await;
+    assert!(!env.exists(&pda::portfolio(&owner).0).await);
+}
@@ -0,0 +1,647 @@
+//! Test bank, keypairs, mints and assertion helpers shared by every module.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::OnceLock,
+};
+
+use anchor_lang::{
+    error::ErrorCode,
+    prelude::*,
+    solana_program::{instruction::Instruction, program_option::COption, program_pack::Pack},
+    AccountDeserialize, ZeroCopy,
+};
+use anchor_spl::{
+    associated_token::spl_associated_token_account::instruction::create_associated_token_account,
+    token::spl_token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            default_account_state::DefaultAccountState, mint_close_authority::MintCloseAuthority,
+            non_transferable::NonTransferable, pausable::PausableConfig,
+            permanent_delegate::PermanentDelegate, transfer_fee::TransferFeeConfig,
+            transfer_hook::TransferHook, BaseStateWithExtensionsMut,
+            ExtensionType, StateWithExtensions, StateWithExtensionsMut,
+        },
+        state::{Account as TokenAccount, AccountState, Mint},
+    },
+};
+use collateral_vault::{MintConfigParams, VaultError, ID};
+use collateral_vault_client::{instructions as ix, transaction_vault_error, VaultEvent};
+use solana_account::{Account, AccountSharedData};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_keypair::Keypair;
+use solana_program_test::{read_file, ProgramTest, ProgramTestContext};
+use solana_signer::Signer;
+use solana_system_interface::instruction as system_instruction;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+pub const DECIMALS: u8 = 6;
+
+/// `collateral_vault.so` from `SBF_OUT_DIR` under `cargo test-sbf`, otherwise built
+/// once per run by `cargo build-sbf` with the features these tests expect
+///
+/// Event CPIs are switched on from the test side, with `--features
+/// collateral_vault_client/event-cpi,example_integrator/event-cpi`, which also
+/// turns on this crate's `event-cpi`. The program's feature alone doesn't reach
+/// the client or the integrator.
+pub fn compiled_program() -> &'static Path {
+    static PROGRAM: OnceLock<PathBuf> = OnceLock::new();
+    PROGRAM.get_or_init(|| compiled("collateral_vault", &["mock-oracle", "zero-copy"]))
+}
+
+/// `example_integrator.so`, found or built like [`compiled_program`]. `cargo
+/// test-sbf` only builds this package, so it's usually built here.
+pub fn compiled_integrator() -> &'static Path {
+    static PROGRAM: OnceLock<PathBuf> = OnceLock::new();
+    PROGRAM.get_or_init(|| compiled("example_integrator", &[]))
+}
+
+fn compiled(program: &str, features: &[&str]) -> PathBuf {
+    let file_name = format!("{program}.so");
+    if let Some(out_dir) = std::env::var_os("SBF_OUT_DIR") {
+        let path = PathBuf::from(out_dir).join(&file_name);
+        if path.exists() {
+            return path;
+        }
+    }
+
+    let mut features = features.to_vec();
+    if cfg!(feature = "event-cpi") {
+        features.push("event-cpi");
+    }
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out_dir = manifest_dir.join("../../target/test-sbf");
+    let status = Command::new(std::env::var_os("CARGO").unwrap_or("cargo".into()))
+        .arg("build-sbf")
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("..").join(program).join("Cargo.toml"))
+        .args(["--features", &features.join(","), "--sbf-out-dir"])
+        .arg(&out_dir)
+        .status()
+        .unwrap_or_else(|err| panic!("running `cargo build-sbf`: {err}"));
+    assert!(
+        status.success(),
+        "`cargo build-sbf` failed; install the Solana CLI or point SBF_OUT_DIR at a build"
+    );
+    out_dir.join(file_name)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TestMint {
+    pub address: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// Token-2022 extensions that `check_mint_allowed` inspects
+#[derive(Clone, Copy, Debug)]
+pub enum MintExtension {
+    PermanentDelegate,
+    NonTransferable,
+    DefaultAccountStateFrozen,
+    TransferHook,
+    TransferFee,
+    Pausable,
+    MintCloseAuthority,
+}
+
+impl MintExtension {
+    pub fn extension_type(self) -> ExtensionType {
+        match self {
+            MintExtension::PermanentDelegate => ExtensionType::PermanentDelegate,
+            MintExtension::NonTransferable => ExtensionType::NonTransferable,
+            MintExtension::DefaultAccountStateFrozen => ExtensionType::DefaultAccountState,
+            MintExtension::TransferHook => ExtensionType::TransferHook,
+            MintExtension::TransferFee => ExtensionType::TransferFeeConfig,
+            MintExtension::Pausable => ExtensionType::Pausable,
+            MintExtension::MintCloseAuthority => ExtensionType::MintCloseAuthority,
+        }
+    }
+}
+
+/// Logs, compute units, return data and vault events of a successful transaction
+pub struct Outcome {
+    pub logs: Vec<String>,
+    pub compute_units: u64,
+    pub return_data: Vec<u8>,
+    /// Decoded from event self-CPIs with `event-cpi`, from the logs otherwise
+    pub events: Vec<VaultEvent>,
+}
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    /// Bank payer, vault authority admin and mint authority of every test mint
+    pub admin: Keypair,
+    /// Authorized "program" that signs lock, unlock and transfer instructions directly
+    pub integrator: Keypair,
+    nonce: u32,
+}
+
+impl TestEnv {
+    /// Bank with the vault authority initialized and `integrator` authorized and
+    /// funded, since it pays for its integrator positions
+    pub async fn new() -> Self {
+        Self::uninitialized().await.initialized().await
+    }
+
+    /// Like [`TestEnv::new`], with `example_integrator` loaded as well. Its
+    /// signing PDA isn't authorized.
+    pub async fn with_example_integrator() -> Self {
+        Self::start(&[
+            (ID, compiled_program()),
+            (example_integrator::ID, compiled_integrator()),
+        ])
+        .await
+        .initialized()
+        .await
+    }
+
+    /// Bank with the program loaded and nothing initialized
+    pub async fn uninitialized() -> Self {
+        Self::start(&[(ID, compiled_program())]).await
+    }
+
+    async fn start(programs: &[(Pubkey, &Path)]) -> Self {
+        let mut program_test = ProgramTest::default();
+        for (address, path) in programs {
+            let data = read_file(path);
+            program_test.add_account(
+                *address,
+                Account {
+                    lamports: Rent::default().minimum_balance(data.len()).max(1),
+                    data,
+                    owner: solana_sdk_ids::bpf_loader::ID,
+                    executable: true,
+                    rent_epoch: 0,
+                },
+            );
+        }
+
+        let context = program_test.start_with_context().await;
+        let admin = context.payer.insecure_clone();
+
+        Self {
+            context,
+            admin,
+            integrator: Keypair::new(),
+            nonce: 0,
+        }
+    }
+
+    async fn initialized(mut self) -> Self {
+        let admin = self.admin.pubkey();
+        let integrator = self.integrator.pubkey();
+
+        self.execute(&[ix::initialize_authority(&admin, vec![integrator])], &[])
+            .await;
+        self.execute(
+            &[system_instruction::transfer(&admin, &integrator, 10 * LAMPORTS_PER_SOL)],
+            &[],
+        )
+        .await;
+        self.execute(&[ix::initialize_integrator_stats(&admin, integrator, 0)], &[])
+            .await;
+        self
+    }
+
+    /// Sends `instructions` paid by the admin, signed by the admin and `signers`
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> std::result::Result<Outcome, TransactionError> {
+        // A distinct compute budget keeps repeated transactions from being deduplicated
+        self.nonce += 1;
+        let mut all_instructions =
+            vec![ComputeBudgetInstruction::set_compute_unit_limit(1_400_000 - self.nonce)];
+        all_instructions.extend_from_slice(instructions);
+
+        let mut all_signers = vec![&self.admin];
+        all_signers.extend(
+            signers
+                .iter()
+                .filter(|signer| signer.pubkey() != self.admin.pubkey()),
+        );
+
+        let transaction = Transaction::new_signed_with_payer(
+            &all_instructions,
+            Some(&self.admin.pubkey()),
+            &all_signers,
+            self.context.last_blockhash,
+        );
+        #[cfg(feature = "event-cpi")]
+        let cpi_events = self.simulated_cpi_events(&transaction).await;
+        let result = self
+            .context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .expect("banks client");
+
+        result.result?;
+        let metadata = result.metadata.expect("transaction metadata");
+        #[cfg(not(feature = "event-cpi"))]
+        let events = collateral_vault_client::events_from_logs(&metadata.log_messages);
+        #[cfg(feature = "event-cpi")]
+        let events = cpi_events;
+        Ok(Outcome {
+            logs: metadata.log_messages,
+            compute_units: metadata.compute_units_consumed,
+            return_data: metadata
+                .return_data
+                .map(|return_data| return_data.data)
+                .unwrap_or_default(),
+            events,
+        })
+    }
+
+    /// Events of the self-CPIs `transaction` makes. Banks only report inner
+    /// instructions for simulations, so this runs it once without committing.
+    #[cfg(feature = "event-cpi")]
+    async fn simulated_cpi_events(&mut self, transaction: &Transaction) -> Vec<VaultEvent> {
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(transaction.clone())
+            .await
+            .expect("banks client");
+        let account_keys = &transaction.message.account_keys;
+
+        simulation
+            .simulation_details
+            .and_then(|details| details.inner_instructions)
+            .unwrap_or_default()
+            .iter()
+            .flatten()
+            .map(|inner| &inner.instruction)
+            .filter(|instruction| account_keys[instruction.program_id_index as usize] == ID)
+            .filter_map(|instruction| collateral_vault_client::decode_event_cpi(&instruction.data))
+            .collect()
+    }
+
+    pub async fn execute(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Outcome {
+        match self.process(instructions, signers).await {
+            Ok(outcome) => outcome,
+            Err(err) => panic!("transaction failed: {err}"),
+        }
+    }
+
+    /// Asserts that the transaction fails with `expected`
+    pub async fn expect_error(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        expected: VaultError,
+    ) {
+        let err = self
+            .process(instructions, signers)
+            .await
+            .err()
+            .unwrap_or_else(|| panic!("expected {expected:?}, transaction succeeded"));
+        assert_eq!(
+            transaction_vault_error(&err).map(u32::from),
+            Some(u32::from(expected)),
+            "expected {expected:?}, got {err}",
+        );
+    }
+
+    /// Asserts that the transaction fails with an Anchor framework error
+    pub async fn expect_anchor_error(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        expected: ErrorCode,
+    ) {
+        let err = self
+            .process(instructions, signers)
+            .await
+            .err()
+            .unwrap_or_else(|| panic!("expected {expected:?}, transaction succeeded"));
+        assert_eq!(
+            custom_error_code(&err),
+            Some(expected as u32),
+            "expected {expected:?}, got {err}",
+        );
+    }
+
+    pub fn assert_compute_units(&self, outcome: &Outcome, budget: u64, instruction: &str) {
+        assert!(
+            outcome.compute_units <= budget,
+            "{instruction} used {} compute units, budget is {budget}",
+            outcome.compute_units,
+        );
+    }
+
+    // ============ ACCOUNTS ============
+
+    pub async fn raw_account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .expect("banks client")
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .raw_account(address)
+            .await
+            .unwrap_or_else(|| panic!("account {address} not found"));
+        T::try_deserialize(&mut account.data.as_slice()).expect("account decode")
+    }
+
+    pub async fn zero_copy_account<T: ZeroCopy>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .raw_account(address)
+            .await
+            .unwrap_or_else(|| panic!("account {address} not found"));
+        let body = &account.data[8..8 + std::mem::size_of::<T>()];
+        bytemuck::pod_read_unaligned(body)
+    }
+
+    /// Overwrites account data in place, for states no instruction sequence can reach
+    pub async fn patch_account<T: AccountDeserialize + AccountSerialize>(
+        &mut self,
+        address: &Pubkey,
+        patch: impl FnOnce(&mut T),
+    ) {
+        let mut account = self.raw_account(address).await.expect("account to patch");
+        let mut state = T::try_deserialize(&mut account.data.as_slice()).expect("account decode");
+        patch(&mut state);
+        let mut data = Vec::with_capacity(account.data.len());
+        state.try_serialize(&mut data).expect("account encode");
+        data.resize(account.data.len(), 0);
+        account.data = data;
+        self.context
+            .set_account(address, &AccountSharedData::from(account));
+    }
+
+    pub async fn exists(&mut self, address: &Pubkey) -> bool {
+        self.raw_account(address).await.is_some()
+    }
+
+    pub async fn unix_timestamp(&mut self) -> i64 {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .expect("clock")
+            .unix_timestamp
+    }
+
+    // ============ WALLETS AND TOKENS ============
+
+    /// Funded keypair
+    pub async fn wallet(&mut self) -> Keypair {
+        let wallet = Keypair::new();
+        self.execute(
+            &[system_instruction::transfer(
+                &self.admin.pubkey(),
+                &wallet.pubkey(),
+                10 * LAMPORTS_PER_SOL,
+            )],
+            &[],
+        )
+        .await;
+        wallet
+    }
+
+    /// Extension-free mint with `DECIMALS` decimals and the admin as mint authority
+    pub async fn create_mint(
+        &mut self,
+        token_program: &Pubkey,
+        freeze_authority: Option<&Pubkey>,
+    ) -> TestMint {
+        let mint = Keypair::new();
+        let admin = self.admin.pubkey();
+        let rent = Rent::default().minimum_balance(Mint::LEN);
+
+        self.execute(
+            &[
+                system_instruction::create_account(
+                    &admin,
+                    &mint.pubkey(),
+                    rent,
+                    Mint::LEN as u64,
+                    token_program,
+                ),
+                spl_token_2022::instruction::initialize_mint2(
+                    token_program,
+                    &mint.pubkey(),
+                    &admin,
+                    freeze_authority,
+                    DECIMALS,
+                )
+                .unwrap(),
+            ],
+            &[&mint],
+        )
+        .await;
+
+        TestMint {
+            address: mint.pubkey(),
+            token_program: *token_program,
+        }
+    }
+
+    /// Token-2022 mint carrying `extension`, written straight into the bank
+    pub async fn create_mint_with_extension(&mut self, extension: MintExtension) -> TestMint {
+        let mint = Pubkey::new_unique();
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[extension.extension_type()])
+            .unwrap();
+        let mut data = vec![0; len];
+
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        match extension {
+            MintExtension::PermanentDelegate => {
+                state.init_extension::<PermanentDelegate>(true).unwrap();
+            }
+            MintExtension::NonTransferable => {
+                state.init_extension::<NonTransferable>(true).unwrap();
+            }
+            MintExtension::DefaultAccountStateFrozen => {
+                state.init_extension::<DefaultAccountState>(true).unwrap().state =
+                    AccountState::Frozen as u8;
+            }
+            MintExtension::TransferHook => {
+                state.init_extension::<TransferHook>(true).unwrap();
+            }
+            MintExtension::TransferFee => {
+                state.init_extension::<TransferFeeConfig>(true).unwrap();
+            }
+            MintExtension::Pausable => {
+                state.init_extension::<PausableConfig>(true).unwrap();
+            }
+            MintExtension::MintCloseAuthority => {
+                state.init_extension::<MintCloseAuthority>(true).unwrap();
+            }
+        }
+        state.base = Mint {
+            mint_authority: COption::Some(self.admin.pubkey()),
+            supply: 0,
+            decimals: DECIMALS,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        let account = Account {
+            lamports: Rent::default().minimum_balance(len),
+            data,
+            owner: spl_token_2022::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context
+            .set_account(&mint, &AccountSharedData::from(account));
+
+        TestMint {
+            address: mint,
+            token_program: spl_token_2022::ID,
+        }
+    }
+
+    /// Registers `mint` in the collateral registry
+    pub async fn register_mint(&mut self, mint: &TestMint, params: MintConfigParams) {
+        let admin = self.admin.pubkey();
+        self.execute(
+            &[ix::initialize_mint_config(&admin, &mint.address, params)],
+            &[],
+        )
+        .await;
+    }
+
+    /// Approved, registered, extension-free mint
+    pub async fn approved_mint(&mut self, token_program: &Pubkey) -> TestMint {
+        let mint = self.create_mint(token_program, None).await;
+        self.register_mint(&mint, approved()).await;
+        mint
+    }
+
+    /// Associated token account of `wallet`, holding `amount` freshly minted tokens
+    pub async fn fund(&mut self, wallet: &Pubkey, mint: &TestMint, amount: u64) -> Pubkey {
+        let admin = self.admin.pubkey();
+        let token_account = ix::user_token_account(wallet, &mint.address, &mint.token_program);
+
+        let mut instructions = Vec::new();
+        if !self.exists(&token_account).await {
+            instructions.push(create_associated_token_account(
+                &admin,
+                wallet,
+                &mint.address,
+                &mint.token_program,
+            ));
+        }
+        if amount > 0 {
+            instructions.push(
+                spl_token_2022::instruction::mint_to(
+                    &mint.token_program,
+                    &mint.address,
+                    &token_account,
+                    &admin,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            );
+        }
+        self.execute(&instructions, &[]).await;
+        token_account
+    }
+
+    /// Funded wallet holding `amount` of `mint`
+    pub async fn user(&mut self, mint: &TestMint, amount: u64) -> Keypair {
+        let user = self.wallet().await;
+        self.fund(&user.pubkey(), mint, amount).await;
+        user
+    }
+
+    /// Funded wallet with an open vault holding `deposit` of `mint`
+    pub async fn user_with_vault(&mut self, mint: &TestMint, deposit: u64) -> Keypair {
+        let user = self.user(mint, deposit).await;
+        let owner = user.pubkey();
+
+        let mut instructions = vec![ix::initialize_vault(
+            &owner,
+            &mint.address,
+            &mint.token_program,
+        )];
+        if deposit > 0 {
+            instructions.push(ix::deposit(
+                &owner,
+                &mint.address,
+                &mint.token_program,
+                deposit,
+            ));
+        }
+        self.execute(&instructions, &[&user]).await;
+        user
+    }
+
+    pub async fn token_balance(&mut self, token_account: &Pubkey) -> u64 {
+        let account = self
+            .raw_account(token_account)
+            .await
+            .unwrap_or_else(|| panic!("token account {token_account} not found"));
+        StateWithExtensions::<TokenAccount>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    /// Direct SPL transfer between token accounts, bypassing the vault program
+    pub async fn transfer_tokens(
+        &mut self,
+        mint: &TestMint,
+        from: &Pubkey,
+        to: &Pubkey,
+        authority: &Keypair,
+        amount: u64,
+    ) {
+        self.execute(
+            &[spl_token_2022::instruction::transfer_checked(
+                &mint.token_program,
+                from,
+                &mint.address,
+                to,
+                &authority.pubkey(),
+                &[],
+                amount,
+                DECIMALS,
+            )
+            .unwrap()],
+            &[authority],
+        )
+        .await;
+    }
+}
+
+/// Approved registry entry without extensions or caps
+pub fn approved() -> MintConfigParams {
+    MintConfigParams {
+        approved: true,
+        allowed_extensions: 0,
+        allow_freeze_authority: false,
+        max_deposit_amount: 0,
+        max_vault_balance: 0,
+        max_total_balance: 0,
+    }
+}
+
+/// Both token programs the vault accepts
+pub fn token_programs() -> [Pubkey; 2] {
+    [spl_token::ID, spl_token_2022::ID]
+}
+
+pub fn custom_error_code(err: &TransactionError) -> Option<u32> {
+    use anchor_lang::solana_program::instruction::error::InstructionError;
+
+    match err {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(*code),
+        _ => None,
+    }
+}
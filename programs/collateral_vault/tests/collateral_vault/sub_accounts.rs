@@ -0,0 +1,326 @@
+//! Isolated sub-accounts carved out of a standalone vault.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token_2022::spl_token_2022::{self, state::Account as TokenAccount};
+use collateral_vault::{
+    CollateralVault, IntegratorStats, LockPositions, MintStats, SubAccount, VaultError,
+    VIEW_VERSION,
+};
+use collateral_vault_client::{accounts::decode_return_data, instructions as ix, pda};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_system_interface::instruction as system_instruction;
+
+use crate::setup::{token_programs, TestEnv};
+
+async fn vault(env: &mut TestEnv, owner: &Keypair) -> CollateralVault {
+    env.account(&pda::vault(&owner.pubkey()).0).await
+}
+
+async fn sub_account(env: &mut TestEnv, owner: &Keypair, id: u8) -> SubAccount {
+    let vault = pda::vault(&owner.pubkey()).0;
+    env.account(&pda::sub_account(&vault, id).0).await
+}
+
+#[tokio::test]
+async fn allocate_release_and_move() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+
+    env.execute(
+        &[
+            ix::create_sub_account(&owner, 0),
+            ix::create_sub_account(&owner, 1),
+        ],
+        &[&user],
+    )
+    .await;
+
+    env.expect_anchor_error(
+        &[ix::allocate_to_sub_account(&owner, 2, 10)],
+        &[&user],
+        ErrorCode::AccountNotInitialized,
+    )
+    .await;
+    env.expect_error(
+        &[ix::allocate_to_sub_account(&owner, 0, 101)],
+        &[&user],
+        VaultError::InsufficientAvailableBalance,
+    )
+    .await;
+    env.execute(&[ix::allocate_to_sub_account(&owner, 0, 60)], &[&user])
+        .await;
+
+    let vault_state = vault(&mut env, &user).await;
+    assert_eq!(vault_state.total_balance, 40);
+    assert_eq!(vault_state.available_balance, 40);
+    assert_eq!(vault_state.sub_account_balance, 60);
+
+    env.expect_error(
+        &[ix::move_between_sub_accounts(&owner, 0, 0, 10)],
+        &[&user],
+        VaultError::SameVault,
+    )
+    .await;
+    env.execute(&[ix::move_between_sub_accounts(&owner, 0, 1, 25)], &[&user])
+        .await;
+    env.expect_error(
+        &[ix::release_from_sub_account(&owner, 1, 26)],
+        &[&user],
+        VaultError::InsufficientAvailableBalance,
+    )
+    .await;
+    env.execute(&[ix::release_from_sub_account(&owner, 1, 25)], &[&user])
+        .await;
+
+    assert_eq!(sub_account(&mut env, &user, 0).await.total_balance, 35);
+    assert_eq!(sub_account(&mut env, &user, 1).await.total_balance, 0);
+    let vault_state = vault(&mut env, &user).await;
+    assert_eq!(vault_state.total_balance, 65);
+    assert_eq!(vault_state.sub_account_balance, 35);
+
+    // Sub-account balances still sit in the vault's token account
+    let vault_token_account = ix::vault_token_account(
+        &pda::vault(&owner).0,
+        &mint.address,
+        &mint.token_program,
+    );
+    assert_eq!(env.token_balance(&vault_token_account).await, 100);
+}
+
+#[tokio::test]
+async fn sub_account_locks_and_settlement() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[0]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let counterparty = env.user_with_vault(&mint, 0).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+
+    env.execute(
+        &[
+            ix::create_sub_account(&owner, 3),
+            ix::allocate_to_sub_account(&owner, 3, 80),
+        ],
+        &[&user],
+    )
+    .await;
+
+    env.expect_error(
+        &[ix::lock_sub_account_collateral(&signer, &owner, 3, &mint.address, 81)],
+        &[&integrator],
+        VaultError::InsufficientAvailableBalance,
+    )
+    .await;
+    env.execute(
+        &[ix::lock_sub_account_collateral(&signer, &owner, 3, &mint.address, 50)],
+        &[&integrator],
+    )
+    .await;
+    env.expect_error(
+        &[ix::unlock_sub_account_collateral(&signer, &owner, 3, &mint.address, 51)],
+        &[&integrator],
+        VaultError::InsufficientLockedBalance,
+    )
+    .await;
+    env.execute(
+        &[ix::unlock_sub_account_collateral(&signer, &owner, 3, &mint.address, 20)],
+        &[&integrator],
+    )
+    .await;
+
+    let state = sub_account(&mut env, &user, 3).await;
+    assert_eq!(state.locked_balance, 30);
+    assert_eq!(state.available_balance, 50);
+    let mint_stats: MintStats = env.account(&pda::mint_stats(&mint.address).0).await;
+    assert_eq!(mint_stats.total_locked, 30);
+    let integrator_stats: IntegratorStats =
+        env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!(integrator_stats.locked_balance, 30);
+    assert_eq!(integrator_stats.total_locked, 50);
+
+    let settle = |to_owner: &Pubkey, amount| {
+        ix::transfer_sub_account_collateral(
+            &signer,
+            &owner,
+            3,
+            to_owner,
+            &mint.address,
+            &mint.token_program,
+            amount,
+        )
+    };
+    env.expect_error(&[settle(&owner, 10)], &[&integrator], VaultError::SameVault)
+        .await;
+    env.expect_error(
+        &[settle(&counterparty.pubkey(), 51)],
+        &[&integrator],
+        VaultError::InsufficientAvailableBalance,
+    )
+    .await;
+    env.execute(&[settle(&counterparty.pubkey(), 50)], &[&integrator])
+        .await;
+
+    let state = sub_account(&mut env, &user, 3).await;
+    assert_eq!(state.total_balance, 30);
+    assert_eq!(state.available_balance, 0);
+    let vault_state = vault(&mut env, &user).await;
+    assert_eq!(vault_state.sub_account_balance, 30);
+    assert_eq!(vault_state.total_transferred_out, 50);
+    let counterparty_state = vault(&mut env, &counterparty).await;
+    assert_eq!(counterparty_state.total_balance, 50);
+    assert_eq!(counterparty_state.total_transferred_in, 50);
+    let integrator_stats: IntegratorStats =
+        env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!(integrator_stats.total_transferred, 50);
+
+    // The view reports the vault's own locks plus each sub-account passed in
+    let outcome = env
+        .execute(&[ix::get_lock_positions(&owner, &[3])], &[])
+        .await;
+    let positions: LockPositions = decode_return_data(&outcome.return_data).unwrap();
+    assert_eq!(positions.version, VIEW_VERSION);
+    assert_eq!(positions.vault, pda::vault(&owner).0);
+    assert_eq!(positions.available_balance, 20);
+    assert_eq!(positions.sub_accounts.len(), 1);
+    assert_eq!(positions.sub_accounts[0].id, 3);
+    assert_eq!(positions.sub_accounts[0].locked_balance, 30);
+}
+
+#[tokio::test]
+async fn settlement_stays_within_both_vaults_custody() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[0]).await;
+    let other_mint = env.approved_mint(&token_programs()[0]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let counterparty = env.user_with_vault(&mint, 0).await;
+    let other_holder = env.user_with_vault(&other_mint, 0).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+
+    env.execute(
+        &[
+            ix::create_sub_account(&owner, 0),
+            ix::allocate_to_sub_account(&owner, 0, 50),
+        ],
+        &[&user],
+    )
+    .await;
+    let settle = |to_owner: &Pubkey| {
+        ix::transfer_sub_account_collateral(
+            &signer,
+            &owner,
+            0,
+            to_owner,
+            &mint.address,
+            &mint.token_program,
+            10,
+        )
+    };
+
+    // A vault of another mint, passed with its own token account
+    let mut cross_mint = settle(&other_holder.pubkey());
+    cross_mint.accounts[8].pubkey = ix::vault_token_account(
+        &pda::vault(&other_holder.pubkey()).0,
+        &other_mint.address,
+        &other_mint.token_program,
+    );
+    env.expect_error(&[cross_mint], &[&integrator], VaultError::InvalidTokenMint)
+        .await;
+
+    // Second token account owned by the counterparty's vault, but not its custody account
+    let stray = Keypair::new();
+    let admin = env.admin.pubkey();
+    env.execute(
+        &[
+            system_instruction::create_account(
+                &admin,
+                &stray.pubkey(),
+                Rent::default().minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                &mint.token_program,
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &mint.token_program,
+                &stray.pubkey(),
+                &mint.address,
+                &pda::vault(&counterparty.pubkey()).0,
+            )
+            .unwrap(),
+        ],
+        &[&stray],
+    )
+    .await;
+    let mut side_account = settle(&counterparty.pubkey());
+    side_account.accounts[8].pubkey = stray.pubkey();
+    env.expect_anchor_error(&[side_account], &[&integrator], ErrorCode::ConstraintAddress)
+        .await;
+
+    env.execute(&[settle(&counterparty.pubkey())], &[&integrator])
+        .await;
+    assert_eq!(vault(&mut env, &counterparty).await.total_balance, 10);
+}
+
+#[tokio::test]
+async fn lock_positions_reject_foreign_sub_accounts() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 0).await;
+    let other = env.user_with_vault(&mint, 0).await;
+    env.execute(&[ix::create_sub_account(&other.pubkey(), 0)], &[&other])
+        .await;
+
+    let foreign = pda::sub_account(&pda::vault(&other.pubkey()).0, 0).0;
+    let mut view = ix::get_lock_positions(&user.pubkey(), &[]);
+    view.accounts.push(AccountMeta::new_readonly(foreign, false));
+    env.expect_error(&[view], &[], VaultError::InvalidSubAccount)
+        .await;
+}
+
+#[tokio::test]
+async fn sub_account_balances_block_closing() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[0]).await;
+    let user = env.user_with_vault(&mint, 10).await;
+    let owner = user.pubkey();
+
+    env.execute(
+        &[
+            ix::create_sub_account(&owner, 0),
+            ix::allocate_to_sub_account(&owner, 0, 10),
+        ],
+        &[&user],
+    )
+    .await;
+
+    env.expect_error(
+        &[ix::close_sub_account(&owner, 0)],
+        &[&user],
+        VaultError::VaultNotEmpty,
+    )
+    .await;
+    env.expect_error(
+        &[ix::close_vault(&owner, &mint.address, &mint.token_program, false)],
+        &[&user],
+        VaultError::HasSubAccountBalance,
+    )
+    .await;
+
+    env.execute(
+        &[
+            ix::release_from_sub_account(&owner, 0, 10),
+            ix::close_sub_account(&owner, 0),
+        ],
+        &[&user],
+    )
+    .await;
+    let vault_address = pda::vault(&owner).0;
+    assert!(!env.exists(&pda::sub_account(&vault_address, 0).0).await);
+}
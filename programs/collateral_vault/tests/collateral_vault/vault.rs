@@ -0,0 +1,630 @@
+//! Standalone vaults: deposit, withdraw, lock, unlock, transfer and close.
+
+use anchor_lang::prelude::*;
+use collateral_vault::{
+    CollateralVault, IntegratorPosition, IntegratorStats, MintConfigParams, MintStats,
+    VaultBalances, VaultError, VIEW_VERSION,
+};
+use collateral_vault_client::{accounts::decode_return_data, instructions as ix, pda};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::setup::{approved, token_programs, TestEnv, TestMint};
+
+const INITIALIZE_VAULT_BUDGET: u64 = 80_000;
+const DEPOSIT_BUDGET: u64 = 40_000;
+const WITHDRAW_BUDGET: u64 = 40_000;
+// The first lock in a vault also creates the program's integrator position
+const LOCK_BUDGET: u64 = 30_000;
+const TRANSFER_BUDGET: u64 = 45_000;
+
+async fn vault(env: &mut TestEnv, owner: &Keypair) -> CollateralVault {
+    env.account(&pda::vault(&owner.pubkey()).0).await
+}
+
+fn vault_token_account(owner: &Keypair, mint: &TestMint) -> Pubkey {
+    ix::vault_token_account(
+        &pda::vault(&owner.pubkey()).0,
+        &mint.address,
+        &mint.token_program,
+    )
+}
+
+#[tokio::test]
+async fn deposit_and_withdraw_move_tokens() {
+    for token_program in token_programs() {
+        let mut env = TestEnv::new().await;
+        let mint = env.approved_mint(&token_program).await;
+        let user = env.user(&mint, 1_000).await;
+        let owner = user.pubkey();
+        let user_token_account = ix::user_token_account(&owner, &mint.address, &token_program);
+
+        let outcome = env
+            .execute(
+                &[ix::initialize_vault(&owner, &mint.address, &token_program)],
+                &[&user],
+            )
+            .await;
+        env.assert_compute_units(&outcome, INITIALIZE_VAULT_BUDGET, "initialize_vault");
+
+        let vault_state = vault(&mut env, &user).await;
+        assert_eq!(vault_state.owner, owner);
+        assert_eq!(vault_state.token_mint, mint.address);
+        assert_eq!(vault_state.token_account, vault_token_account(&user, &mint));
+        assert_eq!(vault_state.version, collateral_vault::VAULT_VERSION);
+        assert!(!vault_state.pooled);
+
+        let outcome = env
+            .execute(
+                &[ix::deposit(&owner, &mint.address, &token_program, 600)],
+                &[&user],
+            )
+            .await;
+        env.assert_compute_units(&outcome, DEPOSIT_BUDGET, "deposit");
+        assert!(outcome
+            .logs
+            .iter()
+            .any(|log| log == "Program log: Instruction: Deposit"));
+
+        let vault_state = vault(&mut env, &user).await;
+        assert_eq!(vault_state.total_balance, 600);
+        assert_eq!(vault_state.available_balance, 600);
+        assert_eq!(vault_state.total_deposited, 600);
+        assert_eq!(env.token_balance(&user_token_account).await, 400);
+        assert_eq!(env.token_balance(&vault_token_account(&user, &mint)).await, 600);
+
+        let outcome = env
+            .execute(
+                &[ix::withdraw(&owner, &mint.address, &token_program, 250)],
+                &[&user],
+            )
+            .await;
+        env.assert_compute_units(&outcome, WITHDRAW_BUDGET, "withdraw");
+
+        let vault_state = vault(&mut env, &user).await;
+        assert_eq!(vault_state.total_balance, 350);
+        assert_eq!(vault_state.available_balance, 350);
+        assert_eq!(vault_state.total_withdrawn, 250);
+        assert_eq!(env.token_balance(&user_token_account).await, 650);
+        assert_eq!(env.token_balance(&vault_token_account(&user, &mint)).await, 350);
+
+        let mint_stats: MintStats = env.account(&pda::mint_stats(&mint.address).0).await;
+        assert_eq!(mint_stats.total_balance, 350);
+        assert_eq!(mint_stats.total_deposited, 600);
+        assert_eq!(mint_stats.total_withdrawn, 250);
+        assert_eq!(mint_stats.vault_count, 1);
+    }
+}
+
+#[tokio::test]
+async fn deposit_rejects_invalid_amount_and_foreign_mint() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let other_mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+    env.fund(&owner, &other_mint, 100).await;
+
+    env.expect_error(
+        &[ix::deposit(&owner, &mint.address, &mint.token_program, 0)],
+        &[&user],
+        VaultError::InvalidAmount,
+    )
+    .await;
+    env.expect_error(
+        &[ix::withdraw(&owner, &mint.address, &mint.token_program, 0)],
+        &[&user],
+        VaultError::InvalidAmount,
+    )
+    .await;
+
+    // Point every mint-derived account at the other mint except the vault's own token account
+    let mut deposit = ix::deposit(&owner, &other_mint.address, &other_mint.token_program, 10);
+    deposit.accounts[6].pubkey = vault_token_account(&user, &mint);
+    env.expect_error(&[deposit], &[&user], VaultError::InvalidTokenMint)
+        .await;
+}
+
+#[tokio::test]
+async fn withdraw_is_limited_to_available_balance() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[0]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+
+    env.expect_error(
+        &[ix::withdraw(&owner, &mint.address, &mint.token_program, 101)],
+        &[&user],
+        VaultError::InsufficientAvailableBalance,
+    )
+    .await;
+
+    env.execute(
+        &[ix::lock_collateral(&integrator.pubkey(), &owner, &mint.address, 70)],
+        &[&integrator],
+    )
+    .await;
+    env.expect_error(
+        &[ix::withdraw(&owner, &mint.address, &mint.token_program, 31)],
+        &[&user],
+        VaultError::InsufficientAvailableBalance,
+    )
+    .await;
+    env.execute(
+        &[ix::withdraw(&owner, &mint.address, &mint.token_program, 30)],
+        &[&user],
+    )
+    .await;
+
+    let vault_state = vault(&mut env, &user).await;
+    assert_eq!(vault_state.total_balance, 70);
+    assert_eq!(vault_state.locked_balance, 70);
+    assert_eq!(vault_state.available_balance, 0);
+}
+
+#[tokio::test]
+async fn lock_and_unlock_collateral() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+
+    let outcome = env
+        .execute(
+            &[ix::lock_collateral(&signer, &owner, &mint.address, 60)],
+            &[&integrator],
+        )
+        .await;
+    env.assert_compute_units(&outcome, LOCK_BUDGET, "lock_collateral");
+
+    let vault_state = vault(&mut env, &user).await;
+    assert_eq!(vault_state.locked_balance, 60);
+    assert_eq!(vault_state.available_balance, 40);
+    assert_eq!(vault_state.total_balance, 100);
+
+    let mint_stats: MintStats = env.account(&pda::mint_stats(&mint.address).0).await;
+    assert_eq!(mint_stats.total_locked, 60);
+    let integrator_stats: IntegratorStats =
+        env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!(integrator_stats.locked_balance, 60);
+    assert_eq!(integrator_stats.total_locked, 60);
+
+    env.expect_error(
+        &[ix::lock_collateral(&signer, &owner, &mint.address, 0)],
+        &[&integrator],
+        VaultError::InvalidAmount,
+    )
+    .await;
+    env.expect_error(
+        &[ix::lock_collateral(&signer, &owner, &mint.address, 41)],
+        &[&integrator],
+        VaultError::InsufficientAvailableBalance,
+    )
+    .await;
+    env.expect_error(
+        &[ix::unlock_collateral(&signer, &owner, &mint.address, 0)],
+        &[&integrator],
+        VaultError::InvalidAmount,
+    )
+    .await;
+    env.expect_error(
+        &[ix::unlock_collateral(&signer, &owner, &mint.address, 61)],
+        &[&integrator],
+        VaultError::InsufficientLockedBalance,
+    )
+    .await;
+
+    let outcome = env
+        .execute(
+            &[ix::unlock_collateral(&signer, &owner, &mint.address, 60)],
+            &[&integrator],
+        )
+        .await;
+    env.assert_compute_units(&outcome, LOCK_BUDGET, "unlock_collateral");
+
+    let vault_state = vault(&mut env, &user).await;
+    assert_eq!(vault_state.locked_balance, 0);
+    assert_eq!(vault_state.available_balance, 100);
+    let integrator_stats: IntegratorStats =
+        env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!(integrator_stats.locked_balance, 0);
+    assert_eq!(integrator_stats.total_locked, 60);
+}
+
+#[tokio::test]
+async fn unlocks_are_charged_to_the_locking_program() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+    let vault_address = pda::vault(&owner).0;
+    let integrator = env.integrator.insecure_clone();
+    // Authorized without exposure counters, which its first lock creates
+    let other = env.wallet().await;
+    env.execute(&[ix::add_authorized_program(&admin, other.pubkey())], &[])
+        .await;
+
+    env.execute(
+        &[ix::lock_collateral(&integrator.pubkey(), &owner, &mint.address, 60)],
+        &[&integrator],
+    )
+    .await;
+    env.execute(
+        &[ix::lock_collateral(&other.pubkey(), &owner, &mint.address, 20)],
+        &[&other],
+    )
+    .await;
+
+    // The vault has 80 locked, but only 20 of it is the other program's to release
+    env.expect_error(
+        &[ix::unlock_collateral(&other.pubkey(), &owner, &mint.address, 21)],
+        &[&other],
+        VaultError::LockNotHeld,
+    )
+    .await;
+    env.execute(
+        &[ix::unlock_collateral(&other.pubkey(), &owner, &mint.address, 20)],
+        &[&other],
+    )
+    .await;
+
+    let position: IntegratorPosition = env
+        .account(&pda::integrator_position(&integrator.pubkey(), &vault_address).0)
+        .await;
+    assert_eq!(position.program, integrator.pubkey());
+    assert_eq!(position.holder, vault_address);
+    assert_eq!(position.locked(&mint.address), 60);
+    let position: IntegratorPosition = env
+        .account(&pda::integrator_position(&other.pubkey(), &vault_address).0)
+        .await;
+    assert!(position.locks.is_empty());
+
+    let stats: IntegratorStats = env.account(&pda::integrator_stats(&integrator.pubkey()).0).await;
+    assert_eq!(stats.locked_balance, 60);
+    let (stats_address, bump) = pda::integrator_stats(&other.pubkey());
+    let stats: IntegratorStats = env.account(&stats_address).await;
+    assert_eq!((stats.program, stats.bump, stats.max_locked), (other.pubkey(), bump, 0));
+    assert_eq!((stats.locked_balance, stats.total_locked), (0, 20));
+    assert_eq!(vault(&mut env, &user).await.locked_balance, 60);
+}
+
+#[tokio::test]
+async fn unauthorized_program_cannot_lock_or_transfer() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let other = env.user_with_vault(&mint, 0).await;
+    let rogue = Keypair::new();
+    let admin = env.admin.pubkey();
+
+    // Exposure counters exist, but the program was never authorized
+    env.execute(
+        &[ix::initialize_integrator_stats(&admin, rogue.pubkey(), 0)],
+        &[],
+    )
+    .await;
+
+    env.expect_error(
+        &[ix::lock_collateral(&rogue.pubkey(), &user.pubkey(), &mint.address, 10)],
+        &[&rogue],
+        VaultError::UnauthorizedProgram,
+    )
+    .await;
+    env.expect_error(
+        &[ix::transfer_collateral(
+            &rogue.pubkey(),
+            &user.pubkey(),
+            &other.pubkey(),
+            &mint.address,
+            &mint.token_program,
+            10,
+        )],
+        &[&rogue],
+        VaultError::UnauthorizedProgram,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn transfer_collateral_between_vaults() {
+    for token_program in token_programs() {
+        let mut env = TestEnv::new().await;
+        let mint = env.approved_mint(&token_program).await;
+        let from = env.user_with_vault(&mint, 100).await;
+        let to = env.user_with_vault(&mint, 10).await;
+        let integrator = env.integrator.insecure_clone();
+        let signer = integrator.pubkey();
+
+        let transfer = |amount| {
+            ix::transfer_collateral(
+                &signer,
+                &from.pubkey(),
+                &to.pubkey(),
+                &mint.address,
+                &token_program,
+                amount,
+            )
+        };
+
+        env.expect_error(&[transfer(0)], &[&integrator], VaultError::InvalidAmount)
+            .await;
+        env.expect_error(
+            &[transfer(101)],
+            &[&integrator],
+            VaultError::InsufficientAvailableBalance,
+        )
+        .await;
+        env.expect_error(
+            &[ix::transfer_collateral(
+                &signer,
+                &from.pubkey(),
+                &from.pubkey(),
+                &mint.address,
+                &token_program,
+                10,
+            )],
+            &[&integrator],
+            VaultError::SameVault,
+        )
+        .await;
+
+        let outcome = env.execute(&[transfer(40)], &[&integrator]).await;
+        env.assert_compute_units(&outcome, TRANSFER_BUDGET, "transfer_collateral");
+
+        let from_state = vault(&mut env, &from).await;
+        let to_state = vault(&mut env, &to).await;
+        assert_eq!(from_state.total_balance, 60);
+        assert_eq!(from_state.available_balance, 60);
+        assert_eq!(to_state.total_balance, 50);
+        assert_eq!(to_state.available_balance, 50);
+        assert_eq!(from_state.total_transferred_out, 40);
+        assert_eq!(to_state.total_transferred_in, 40);
+        assert_eq!(env.token_balance(&vault_token_account(&from, &mint)).await, 60);
+        assert_eq!(env.token_balance(&vault_token_account(&to, &mint)).await, 50);
+
+        let integrator_stats: IntegratorStats =
+            env.account(&pda::integrator_stats(&signer).0).await;
+        assert_eq!(integrator_stats.total_transferred, 40);
+    }
+}
+
+#[tokio::test]
+async fn close_vault_requires_an_empty_vault() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+    let vault_address = pda::vault(&owner).0;
+
+    env.expect_error(
+        &[ix::close_vault(&owner, &mint.address, &mint.token_program, false)],
+        &[&user],
+        VaultError::VaultNotEmpty,
+    )
+    .await;
+
+    env.execute(
+        &[ix::withdraw(&owner, &mint.address, &mint.token_program, 100)],
+        &[&user],
+    )
+    .await;
+
+    // Locked collateral without a balance can't be produced by instructions
+    env.patch_account::<CollateralVault>(&vault_address, |vault| vault.locked_balance = 5)
+        .await;
+    env.expect_error(
+        &[ix::close_vault(&owner, &mint.address, &mint.token_program, false)],
+        &[&user],
+        VaultError::HasLockedCollateral,
+    )
+    .await;
+    env.patch_account::<CollateralVault>(&vault_address, |vault| vault.locked_balance = 0)
+        .await;
+
+    let lamports_before = env.raw_account(&owner).await.unwrap().lamports;
+    env.execute(
+        &[ix::close_vault(&owner, &mint.address, &mint.token_program, false)],
+        &[&user],
+    )
+    .await;
+
+    assert!(!env.exists(&vault_address).await);
+    assert!(env.raw_account(&owner).await.unwrap().lamports > lamports_before);
+    let mint_stats: MintStats = env.account(&pda::mint_stats(&mint.address).0).await;
+    assert_eq!(mint_stats.vault_count, 0);
+}
+
+#[tokio::test]
+async fn checked_arithmetic_rejects_corrupt_balances() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+    let vault_address = pda::vault(&owner).0;
+    env.fund(&owner, &mint, 10).await;
+
+    env.patch_account::<CollateralVault>(&vault_address, |vault| {
+        vault.total_balance = u64::MAX;
+    })
+    .await;
+    env.expect_error(
+        &[ix::deposit(&owner, &mint.address, &mint.token_program, 1)],
+        &[&user],
+        VaultError::Overflow,
+    )
+    .await;
+
+    env.patch_account::<CollateralVault>(&vault_address, |vault| {
+        vault.total_balance = 10;
+        vault.available_balance = 50;
+    })
+    .await;
+    env.expect_error(
+        &[ix::withdraw(&owner, &mint.address, &mint.token_program, 20)],
+        &[&user],
+        VaultError::Underflow,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn assert_invariants_detects_inconsistent_vaults() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[0]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+    let vault_address = pda::vault(&owner).0;
+    let check = || ix::assert_invariants(&owner, &mint.address, &mint.token_program, false);
+
+    env.execute(&[check()], &[]).await;
+
+    env.patch_account::<CollateralVault>(&vault_address, |vault| {
+        vault.locked_balance = 1;
+    })
+    .await;
+    env.expect_error(&[check()], &[], VaultError::BalanceSplitMismatch)
+        .await;
+
+    // Split adds up, but the vault claims more than its token account holds
+    env.patch_account::<CollateralVault>(&vault_address, |vault| {
+        vault.locked_balance = 0;
+        vault.sub_account_balance = 1;
+    })
+    .await;
+    env.expect_error(&[check()], &[], VaultError::CustodyShortfall)
+        .await;
+
+    // Seizures and fees leave the vault like withdrawals
+    env.patch_account::<CollateralVault>(&vault_address, |vault| {
+        vault.sub_account_balance = 0;
+        vault.total_deposited = 110;
+        vault.total_seized = 6;
+        vault.total_fees_paid = 4;
+    })
+    .await;
+    env.execute(&[check()], &[]).await;
+
+    // Balances that no deposit, withdrawal, transfer, seizure or fee accounts for
+    env.patch_account::<CollateralVault>(&vault_address, |vault| {
+        vault.total_fees_paid = 0;
+    })
+    .await;
+    env.expect_error(&[check()], &[], VaultError::LifetimeBalanceMismatch)
+        .await;
+
+    // Unless the vault was migrated with transfers that were never counted
+    env.patch_account::<CollateralVault>(&vault_address, |vault| {
+        vault.partial_history = true;
+    })
+    .await;
+    env.execute(&[check()], &[]).await;
+}
+
+#[tokio::test]
+async fn deposit_limits_and_caps() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let mint = env.create_mint(&token_programs()[1], None).await;
+    env.register_mint(
+        &mint,
+        MintConfigParams {
+            max_deposit_amount: 50,
+            max_vault_balance: 80,
+            max_total_balance: 120,
+            ..approved()
+        },
+    )
+    .await;
+    let first = env.user_with_vault(&mint, 0).await;
+    let second = env.user_with_vault(&mint, 0).await;
+    env.fund(&first.pubkey(), &mint, 1_000).await;
+    env.fund(&second.pubkey(), &mint, 1_000).await;
+
+    let deposit = |user: &Keypair, amount| {
+        ix::deposit(&user.pubkey(), &mint.address, &mint.token_program, amount)
+    };
+
+    env.expect_error(&[deposit(&first, 51)], &[&first], VaultError::DepositLimitExceeded)
+        .await;
+    env.execute(&[deposit(&first, 50)], &[&first]).await;
+    env.expect_error(&[deposit(&first, 31)], &[&first], VaultError::DepositCapExceeded)
+        .await;
+    env.execute(&[deposit(&first, 30)], &[&first]).await;
+    env.execute(&[deposit(&second, 40)], &[&second]).await;
+
+    // 80 + 40 already fills the per-mint TVL cap
+    env.expect_error(&[deposit(&second, 1)], &[&second], VaultError::DepositCapExceeded)
+        .await;
+
+    env.execute(
+        &[ix::update_mint_config(&admin, &mint.address, approved())],
+        &[],
+    )
+    .await;
+    env.execute(&[deposit(&second, 500)], &[&second]).await;
+
+    let mint_stats: MintStats = env.account(&pda::mint_stats(&mint.address).0).await;
+    assert_eq!(mint_stats.total_balance, 620);
+}
+
+#[tokio::test]
+async fn integrator_cap_limits_locked_collateral() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+
+    env.execute(&[ix::set_integrator_cap(&admin, &signer, 50)], &[])
+        .await;
+    env.execute(
+        &[ix::lock_collateral(&signer, &user.pubkey(), &mint.address, 50)],
+        &[&integrator],
+    )
+    .await;
+    env.expect_error(
+        &[ix::lock_collateral(&signer, &user.pubkey(), &mint.address, 1)],
+        &[&integrator],
+        VaultError::IntegratorCapExceeded,
+    )
+    .await;
+
+    let integrator_stats: IntegratorStats = env.account(&pda::integrator_stats(&signer).0).await;
+    assert_eq!(integrator_stats.max_locked, 50);
+    assert_eq!(integrator_stats.locked_balance, 50);
+}
+
+#[tokio::test]
+async fn vault_balances_view() {
+    let mut env = TestEnv::new().await;
+    let mint = env.approved_mint(&token_programs()[0]).await;
+    let user = env.user_with_vault(&mint, 100).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+
+    env.execute(
+        &[ix::lock_collateral(&integrator.pubkey(), &owner, &mint.address, 25)],
+        &[&integrator],
+    )
+    .await;
+
+    let outcome = env.execute(&[ix::get_vault_balances(&owner)], &[]).await;
+    let balances: VaultBalances = decode_return_data(&outcome.return_data).unwrap();
+    assert_eq!(balances.version, VIEW_VERSION);
+    assert_eq!(balances.vault, pda::vault(&owner).0);
+    assert_eq!(balances.owner, owner);
+    assert_eq!(balances.token_mint, mint.address);
+    assert_eq!(balances.total_balance, 100);
+    assert_eq!(balances.locked_balance, 25);
+    assert_eq!(balances.available_balance, 75);
+    assert_eq!(balances.total_deposited, 100);
+    assert_eq!(balances.total_transferred_in, 0);
+    assert_eq!(balances.total_seized, 0);
+    assert_eq!(balances.total_fees_paid, 0);
+    assert!(!balances.pooled);
+}
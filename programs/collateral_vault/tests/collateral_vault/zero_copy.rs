@@ -0,0 +1,259 @@
+//! Zero-copy authority and vault instruction paths, an experiment measured against
+//! the Borsh accounts. Only built into the program with `zero-copy`, which these
+//! tests enable; the deployed program leaves it out.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use collateral_vault::{CollateralVaultZc, VaultAuthorityZc, VaultError, ZERO_COPY_VERSION};
+use collateral_vault_client::{instructions as ix, pda};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::setup::{token_programs, TestEnv};
+
+const DEPOSIT_ZC_BUDGET: u64 = 40_000;
+// The first lock in a vault also creates the program's integrator position
+const LOCK_ZC_BUDGET: u64 = 25_000;
+
+/// Bank with the zero-copy authority initialized and `integrator` authorized
+async fn zero_copy_env() -> TestEnv {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.pubkey();
+    let integrator = env.integrator.pubkey();
+    env.execute(&[ix::initialize_authority_zc(&admin, vec![integrator])], &[])
+        .await;
+    env
+}
+
+async fn authority(env: &mut TestEnv) -> VaultAuthorityZc {
+    env.zero_copy_account(&pda::vault_authority_zc().0).await
+}
+
+async fn vault(env: &mut TestEnv, owner: &Keypair) -> CollateralVaultZc {
+    env.zero_copy_account(&pda::vault_zc(&owner.pubkey()).0)
+        .await
+}
+
+#[tokio::test]
+async fn authority_program_list() {
+    let mut env = zero_copy_env().await;
+    let admin = env.admin.pubkey();
+    let integrator = env.integrator.pubkey();
+    let programs: Vec<Pubkey> = (0..9).map(|_| Pubkey::new_unique()).collect();
+
+    env.expect_error(
+        &[ix::add_authorized_program_zc(&admin, integrator)],
+        &[],
+        VaultError::ProgramAlreadyAuthorized,
+    )
+    .await;
+    for program in &programs {
+        env.execute(&[ix::add_authorized_program_zc(&admin, *program)], &[])
+            .await;
+    }
+    env.expect_error(
+        &[ix::add_authorized_program_zc(&admin, Pubkey::new_unique())],
+        &[],
+        VaultError::TooManyPrograms,
+    )
+    .await;
+
+    let intruder = env.wallet().await;
+    env.expect_error(
+        &[ix::remove_authorized_program_zc(&intruder.pubkey(), integrator)],
+        &[&intruder],
+        VaultError::Unauthorized,
+    )
+    .await;
+
+    // Swap-remove moves the last entry into the freed slot
+    env.execute(&[ix::remove_authorized_program_zc(&admin, integrator)], &[])
+        .await;
+    let state = authority(&mut env).await;
+    assert_eq!(state.admin, admin);
+    assert_eq!(state.program_count, 9);
+    assert_eq!(state.authorized_programs()[0], programs[8]);
+    assert!(!state.is_authorized(&integrator));
+    assert!(programs.iter().all(|program| state.is_authorized(program)));
+
+    env.expect_error(
+        &[ix::remove_authorized_program_zc(&admin, integrator)],
+        &[],
+        VaultError::ProgramNotAuthorized,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn zero_copy_vault_lifecycle() {
+    for token_program in token_programs() {
+        let mut env = zero_copy_env().await;
+        let mint = env.approved_mint(&token_program).await;
+        let user = env.user(&mint, 100).await;
+        let owner = user.pubkey();
+        let integrator = env.integrator.insecure_clone();
+        let signer = integrator.pubkey();
+
+        env.execute(
+            &[ix::initialize_vault_zc(&owner, &mint.address, &token_program)],
+            &[&user],
+        )
+        .await;
+        let outcome = env
+            .execute(
+                &[ix::deposit_zc(&owner, &mint.address, &token_program, 100)],
+                &[&user],
+            )
+            .await;
+        env.assert_compute_units(&outcome, DEPOSIT_ZC_BUDGET, "deposit_zc");
+
+        let state = vault(&mut env, &user).await;
+        assert_eq!(state.owner, owner);
+        assert_eq!(state.token_mint, mint.address);
+        assert_eq!(state.version, ZERO_COPY_VERSION);
+        assert_eq!(state.total_balance, 100);
+
+        let outcome = env
+            .execute(
+                &[ix::lock_collateral_zc(&signer, &owner, &mint.address, 30)],
+                &[&integrator],
+            )
+            .await;
+        env.assert_compute_units(&outcome, LOCK_ZC_BUDGET, "lock_collateral_zc");
+
+        env.expect_error(
+            &[ix::withdraw_zc(&owner, &mint.address, &token_program, 71)],
+            &[&user],
+            VaultError::InsufficientAvailableBalance,
+        )
+        .await;
+        env.execute(
+            &[ix::withdraw_zc(&owner, &mint.address, &token_program, 70)],
+            &[&user],
+        )
+        .await;
+        env.expect_error(
+            &[ix::close_vault_zc(&owner, &mint.address)],
+            &[&user],
+            VaultError::VaultNotEmpty,
+        )
+        .await;
+        env.expect_error(
+            &[ix::unlock_collateral_zc(&signer, &owner, &mint.address, 31)],
+            &[&integrator],
+            VaultError::InsufficientLockedBalance,
+        )
+        .await;
+
+        env.execute(
+            &[
+                ix::unlock_collateral_zc(&signer, &owner, &mint.address, 30),
+                ix::withdraw_zc(&owner, &mint.address, &token_program, 30),
+                ix::close_vault_zc(&owner, &mint.address),
+            ],
+            &[&user, &integrator],
+        )
+        .await;
+        assert!(!env.exists(&pda::vault_zc(&owner).0).await);
+        let user_token_account = ix::user_token_account(&owner, &mint.address, &token_program);
+        assert_eq!(env.token_balance(&user_token_account).await, 100);
+    }
+}
+
+/// Runs each operation on a Borsh vault and a zero-copy vault of the same user. Zero-copy
+/// is only worth keeping if it beats Borsh where the authority is read: the lock path
+#[tokio::test]
+async fn compute_units_against_borsh() {
+    let mut env = zero_copy_env().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user(&mint, 200).await;
+    let owner = user.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let signer = integrator.pubkey();
+    let (token_mint, token_program) = (mint.address, mint.token_program);
+
+    env.execute(
+        &[
+            ix::initialize_vault(&owner, &token_mint, &token_program),
+            ix::initialize_vault_zc(&owner, &token_mint, &token_program),
+        ],
+        &[&user],
+    )
+    .await;
+
+    let steps: [(&str, Instruction, Instruction, &Keypair); 4] = [
+        (
+            "deposit",
+            ix::deposit(&owner, &token_mint, &token_program, 100),
+            ix::deposit_zc(&owner, &token_mint, &token_program, 100),
+            &user,
+        ),
+        (
+            "lock_collateral",
+            ix::lock_collateral(&signer, &owner, &token_mint, 30),
+            ix::lock_collateral_zc(&signer, &owner, &token_mint, 30),
+            &integrator,
+        ),
+        (
+            "unlock_collateral",
+            ix::unlock_collateral(&signer, &owner, &token_mint, 30),
+            ix::unlock_collateral_zc(&signer, &owner, &token_mint, 30),
+            &integrator,
+        ),
+        (
+            "withdraw",
+            ix::withdraw(&owner, &token_mint, &token_program, 100),
+            ix::withdraw_zc(&owner, &token_mint, &token_program, 100),
+            &user,
+        ),
+    ];
+    for (name, borsh, zero_copy, step_signer) in steps {
+        let borsh = env.execute(&[borsh], &[step_signer]).await.compute_units;
+        let zero_copy = env.execute(&[zero_copy], &[step_signer]).await.compute_units;
+        eprintln!("{name}: Borsh {borsh} CU, zero-copy {zero_copy} CU");
+        if name.ends_with("lock_collateral") {
+            assert!(
+                zero_copy < borsh,
+                "{name}: zero-copy used {zero_copy} compute units, Borsh {borsh}"
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn zero_copy_locks_require_authorization() {
+    let mut env = zero_copy_env().await;
+    let mint = env.approved_mint(&token_programs()[1]).await;
+    let user = env.user(&mint, 10).await;
+    let owner = user.pubkey();
+    let admin = env.admin.pubkey();
+    let integrator = env.integrator.insecure_clone();
+    let rogue = Keypair::new();
+
+    env.execute(
+        &[
+            ix::initialize_vault_zc(&owner, &mint.address, &mint.token_program),
+            ix::deposit_zc(&owner, &mint.address, &mint.token_program, 10),
+        ],
+        &[&user],
+    )
+    .await;
+    env.execute(
+        &[ix::initialize_integrator_stats(&admin, rogue.pubkey(), 0)],
+        &[],
+    )
+    .await;
+
+    env.expect_error(
+        &[ix::lock_collateral_zc(&rogue.pubkey(), &owner, &mint.address, 1)],
+        &[&rogue],
+        VaultError::UnauthorizedProgram,
+    )
+    .await;
+    env.expect_error(
+        &[ix::lock_collateral_zc(&integrator.pubkey(), &owner, &mint.address, 0)],
+        &[&integrator],
+        VaultError::InvalidAmount,
+    )
+    .await;
+}
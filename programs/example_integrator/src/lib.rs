@@ -0,0 +1,186 @@
+use anchor_lang::prelude::*;
+use collateral_vault_interface::{cpi as vault_cpi, CollateralVaultProgram};
+
+declare_id!("H9eMBzCMbnwfy6SzwmpcHY9ZBqFsTPPLsCUpPgdBXheZ");
+
+/// Seed of the PDA this program signs vault CPIs with; that PDA is the key
+/// registered in `VaultAuthority::authorized_programs`.
+pub const INTEGRATOR_SEED: &[u8] = b"integrator";
+
+/// Minimal margin desk used by the tests: it posts and releases margin and
+/// settles PnL between vaults using only `collateral_vault_interface`.
+#[program]
+pub mod example_integrator {
+    use super::*;
+
+    /// 1. Lock `amount` of a user's collateral as margin for a new position
+    pub fn open_position(ctx: Context<Position>, amount: u64) -> Result<()> {
+        // Read balances through the view instead of deserializing the vault
+        let balances = vault_cpi::get_vault_balances(
+            ctx.accounts.vault_program.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+        )?;
+        require!(balances.available_balance >= amount, IntegratorError::InsufficientMargin);
+        
+        vault_cpi::lock_collateral(
+            ctx.accounts.lock_accounts(),
+            amount,
+            &[&[INTEGRATOR_SEED, &[ctx.bumps.integrator]]],
+        )
+    }
+
+    /// 2. Release `amount` of margin when a position is closed
+    pub fn close_position(ctx: Context<Position>, amount: u64) -> Result<()> {
+        vault_cpi::unlock_collateral(
+            ctx.accounts.lock_accounts(),
+            amount,
+            &[&[INTEGRATOR_SEED, &[ctx.bumps.integrator]]],
+        )
+    }
+
+    /// 3. Pay `amount` of realized loss from one vault to another
+    pub fn settle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Settle<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let accounts = vault_cpi::TransferCollateral {
+            vault_program: ctx.accounts.vault_program.to_account_info(),
+            signer: ctx.accounts.integrator.to_account_info(),
+            from_vault: ctx.accounts.from_vault.to_account_info(),
+            to_vault: ctx.accounts.to_vault.to_account_info(),
+            vault_authority: ctx.accounts.vault_authority.to_account_info(),
+            integrator_stats: ctx.accounts.integrator_stats.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            token_mint: ctx.accounts.token_mint.to_account_info(),
+            from_vault_token_account: ctx.accounts.from_vault_token_account.to_account_info(),
+            to_vault_token_account: ctx.accounts.to_vault_token_account.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            #[cfg(feature = "event-cpi")]
+            event_authority: ctx.accounts.vault_event_authority.to_account_info(),
+        };
+        
+        vault_cpi::transfer_collateral(
+            accounts,
+            amount,
+            ctx.remaining_accounts.to_vec(),
+            &[&[INTEGRATOR_SEED, &[ctx.bumps.integrator]]],
+        )
+    }
+}
+
+// ============ CONTEXTS ============
+
+// Vault-owned accounts are validated by the collateral vault program itself.
+
+#[derive(Accounts)]
+pub struct Position<'info> {
+    /// CHECK: PDA signer of this program, never read. Funded by the tests, it
+    /// pays for its integrator stats and its position in each vault on first use
+    #[account(
+        mut,
+        seeds = [INTEGRATOR_SEED],
+        bump,
+    )]
+    pub integrator: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    pub vault_authority: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    #[account(mut)]
+    pub mint_stats: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    #[account(mut)]
+    pub integrator_stats: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    #[account(mut)]
+    pub integrator_position: UncheckedAccount<'info>,
+    
+    pub vault_program: Program<'info, CollateralVaultProgram>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: the vault program's event authority, checked by the CPI wrapper
+    #[cfg(feature = "event-cpi")]
+    pub vault_event_authority: UncheckedAccount<'info>,
+}
+
+impl<'info> Position<'info> {
+    fn lock_accounts(&self) -> vault_cpi::LockCollateral<'info> {
+        vault_cpi::LockCollateral {
+            vault_program: self.vault_program.to_account_info(),
+            signer: self.integrator.to_account_info(),
+            vault: self.vault.to_account_info(),
+            vault_authority: self.vault_authority.to_account_info(),
+            mint_stats: self.mint_stats.to_account_info(),
+            integrator_stats: self.integrator_stats.to_account_info(),
+            integrator_position: self.integrator_position.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            #[cfg(feature = "event-cpi")]
+            event_authority: self.vault_event_authority.to_account_info(),
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    /// CHECK: PDA signer of this program, never read. Pays for its integrator
+    /// stats if this is its first call
+    #[account(
+        mut,
+        seeds = [INTEGRATOR_SEED],
+        bump,
+    )]
+    pub integrator: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    #[account(mut)]
+    pub from_vault: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    #[account(mut)]
+    pub to_vault: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    pub vault_authority: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    #[account(mut)]
+    pub integrator_stats: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    pub token_mint: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    #[account(mut)]
+    pub from_vault_token_account: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    #[account(mut)]
+    pub to_vault_token_account: UncheckedAccount<'info>,
+    
+    /// CHECK: validated by the collateral vault program
+    pub token_program: UncheckedAccount<'info>,
+    
+    pub vault_program: Program<'info, CollateralVaultProgram>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: the vault program's event authority, checked by the CPI wrapper
+    #[cfg(feature = "event-cpi")]
+    pub vault_event_authority: UncheckedAccount<'info>,
+}
+
+// ============ ERROR CODES ============
+
+#[error_code]
+pub enum IntegratorError {
+    #[msg("Vault has insufficient available collateral for this position")]
+    InsufficientMargin,
+}
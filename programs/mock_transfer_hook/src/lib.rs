@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta,
+    seeds::Seed,
+    state::ExtraAccountMetaList,
+};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+declare_id!("DXrHkPpj9D41zzbWUJoyqMLcubDiRPpm95VRBboxeU7E");
+
+/// Minimal transfer hook for tests: every transfer of a hooked mint bumps a
+/// per-mint counter that is only reachable through the `ExtraAccountMetaList`.
+#[program]
+pub mod mock_transfer_hook {
+    use super::*;
+
+    /// 1. Write the extra account metas for `mint` (the counter PDA)
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        let extra_account_metas = extra_account_metas()?;
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &extra_account_metas,
+        )?;
+        
+        let counter = &mut ctx.accounts.counter;
+        counter.mint = ctx.accounts.mint.key();
+        counter.transfers = 0;
+        counter.bump = ctx.bumps.counter;
+        
+        Ok(())
+    }
+
+    /// 2. Transfer hook `Execute`, invoked by Token-2022 on every transfer
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        let counter = &mut ctx.accounts.counter;
+        counter.transfers = counter.transfers
+            .checked_add(1)
+            .ok_or(HookError::Overflow)?;
+        counter.last_amount = amount;
+        
+        Ok(())
+    }
+}
+
+/// Counter PDA at `[b"counter", mint]`, i.e. account index 1 of `Execute`
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    Ok(vec![ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal { bytes: b"counter".to_vec() },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )?])
+}
+
+// ============ ACCOUNT STRUCTURES ============
+
+#[account]
+#[derive(InitSpace)]
+pub struct HookCounter {
+    pub mint: Pubkey,              // 32 - Hooked mint
+    pub transfers: u64,            // 8  - Number of hooked transfers
+    pub last_amount: u64,          // 8  - Amount of the latest transfer
+    pub bump: u8,                  // 1  - PDA bump
+}
+
+// ============ CONTEXTS ============
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: TLV account written by `ExtraAccountMetaList::init`
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(extra_account_metas()?.len())?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + HookCounter::INIT_SPACE,
+        seeds = [b"counter", mint.key().as_ref()],
+        bump
+    )]
+    pub counter: Account<'info, HookCounter>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Account order fixed by the transfer hook interface
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Source owner or delegate, validated by the token program
+    pub owner: UncheckedAccount<'info>,
+    
+    /// CHECK: Validation account for this mint
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"counter", mint.key().as_ref()],
+        bump = counter.bump
+    )]
+    pub counter: Account<'info, HookCounter>,
+}
+
+// ============ ERROR CODES ============
+
+#[error_code]
+pub enum HookError {
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
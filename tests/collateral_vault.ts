@@ -1,13 +1,23 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { CollateralVault } from "../target/types/collateral_vault";
+import { MockTransferHook } from "../target/types/mock_transfer_hook";
+import { ExampleIntegrator } from "../target/types/example_integrator";
 import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
 import { 
   TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
+  ExtensionType,
   getAssociatedTokenAddressSync,
   getAccount,
   getMint,
+  getMintLen,
+  createInitializeMintInstruction,
+  createInitializePermanentDelegateInstruction,
+  createInitializeTransferHookInstruction,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createMintToInstruction,
+  getExtraAccountMetaAddress,
 } from "@solana/spl-token";
 import { expect } from "chai";
 
@@ -16,6 +26,8 @@ describe("collateral_vault", () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.CollateralVault as Program<CollateralVault>;
+  const hookProgram = anchor.workspace.MockTransferHook as Program<MockTransferHook>;
+  const integratorProgram = anchor.workspace.ExampleIntegrator as Program<ExampleIntegrator>;
   
   let tokenMint: Keypair;
   let admin: Keypair;
@@ -24,6 +36,9 @@ describe("collateral_vault", () => {
   let authorizedProgram: Keypair;
   
   let vaultAuthorityPda: PublicKey;
+  let mintConfigPda: PublicKey;
+  let mintStatsPda: PublicKey;
+  let integratorStatsPda: PublicKey;
   let user1VaultPda: PublicKey;
   let user2VaultPda: PublicKey;
   
@@ -41,6 +56,8 @@ describe("collateral_vault", () => {
     
     console.log("\n🏦 PDA Addresses:");
     console.log(`   Vault Authority PDA: ${vaultAuthorityPda.toString()}`);
+    console.log(`   Mint Config PDA: ${mintConfigPda.toString()}`);
+    console.log(`   Mint Stats PDA: ${mintStatsPda.toString()}`);
     console.log(`   User1 Vault PDA: ${user1VaultPda.toString()}`);
     console.log(`   User2 Vault PDA: ${user2VaultPda.toString()}`);
     
@@ -173,6 +190,11 @@ describe("collateral_vault", () => {
     const user2Airdrop = await provider.connection.requestAirdrop(user2.publicKey, airdropAmount);
     await provider.connection.confirmTransaction(user2Airdrop);
     console.log(`   User2 airdropped: ${airdropAmount / LAMPORTS_PER_SOL} SOL`);
+    
+    // The authorized program pays for its integrator positions on first lock
+    const programAirdrop = await provider.connection.requestAirdrop(authorizedProgram.publicKey, airdropAmount);
+    await provider.connection.confirmTransaction(programAirdrop);
+    console.log(`   Authorized Program airdropped: ${airdropAmount / LAMPORTS_PER_SOL} SOL`);
 
     // Create token mint using Anchor
     console.log("🏗️  Creating Token Mint...");
@@ -308,6 +330,24 @@ describe("collateral_vault", () => {
     );
     console.log(`   Vault Authority PDA: ${vaultAuthorityPda.toString()}`);
 
+    [mintConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_config"), tokenMint.publicKey.toBuffer()],
+      program.programId
+    );
+    console.log(`   Mint Config PDA: ${mintConfigPda.toString()}`);
+
+    [mintStatsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_stats"), tokenMint.publicKey.toBuffer()],
+      program.programId
+    );
+    console.log(`   Mint Stats PDA: ${mintStatsPda.toString()}`);
+
+    [integratorStatsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("integrator_stats"), authorizedProgram.publicKey.toBuffer()],
+      program.programId
+    );
+    console.log(`   Integrator Stats PDA: ${integratorStatsPda.toString()}`);
+
     [user1VaultPda] = PublicKey.findProgramAddressSync(
       [Buffer.from("vault"), user1.publicKey.toBuffer()],
       program.programId
@@ -347,10 +387,53 @@ describe("collateral_vault", () => {
       console.log("✅ Vault Authority initialized successfully!");
       await logAccountDetails("After Initialize Authority");
     });
+
+    it("should initialize integrator stats for the authorized program", async () => {
+      await program.methods
+        .initializeIntegratorStats(authorizedProgram.publicKey, new anchor.BN(0))
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          integratorStats: integratorStatsPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const stats = await program.account.integratorStats.fetch(integratorStatsPda);
+      expect(stats.program.toString()).to.equal(authorizedProgram.publicKey.toString());
+      expect(stats.lockedBalance.toString()).to.equal("0");
+
+      console.log("✅ Integrator stats initialized!");
+    });
   });
 
   // Test 2: Initialize Vault
   describe("2. initialize_vault", () => {
+    before(async () => {
+      // The test mint keeps its freeze authority, so the registry must allow it
+      await program.methods
+        .initializeMintConfig({
+          approved: true,
+          allowedExtensions: new anchor.BN(0),
+          allowFreezeAuthority: true,
+          maxDepositAmount: new anchor.BN(0),
+          maxVaultBalance: new anchor.BN(0),
+          maxTotalBalance: new anchor.BN(0),
+        })
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          tokenMint: tokenMint.publicKey,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      console.log("✅ Token mint registered as collateral");
+    });
+
     it("should initialize user vault", async () => {
       console.log("🧪 Starting Test 2: Initialize Vault");
       
@@ -367,6 +450,9 @@ describe("collateral_vault", () => {
         .accounts({
           user: user1.publicKey,
           tokenMint: tokenMint.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
           vault: user1VaultPda,
           userTokenAccount: user1TokenAccount,
           vaultTokenAccount: user1VaultTokenAccount,
@@ -409,6 +495,8 @@ describe("collateral_vault", () => {
           user: user1.publicKey,
           vault: user1VaultPda,
           tokenMint: tokenMint.publicKey,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
           userTokenAccount: user1TokenAccount,
           vaultTokenAccount: user1VaultTokenAccount,
           tokenProgram: TOKEN_2022_PROGRAM_ID,
@@ -440,6 +528,8 @@ describe("collateral_vault", () => {
           signer: authorizedProgram.publicKey,
           vault: user1VaultPda,
           vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
         })
         .signers([authorizedProgram])
         .rpc();
@@ -466,6 +556,8 @@ describe("collateral_vault", () => {
             signer: unauthorizedSigner.publicKey,
             vault: user1VaultPda,
             vaultAuthority: vaultAuthorityPda,
+            mintStats: mintStatsPda,
+            integratorStats: integratorStatsPda,
           })
           .signers([unauthorizedSigner])
           .rpc();
@@ -493,6 +585,8 @@ describe("collateral_vault", () => {
           signer: authorizedProgram.publicKey,
           vault: user1VaultPda,
           vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
         })
         .signers([authorizedProgram])
         .rpc();
@@ -527,6 +621,7 @@ describe("collateral_vault", () => {
           user: user1.publicKey,
           vault: user1VaultPda,
           tokenMint: tokenMint.publicKey,
+          mintStats: mintStatsPda,
           userTokenAccount: user1TokenAccount,
           vaultTokenAccount: user1VaultTokenAccount,
           tokenProgram: TOKEN_2022_PROGRAM_ID,
@@ -562,6 +657,9 @@ describe("collateral_vault", () => {
         .accounts({
           user: user2.publicKey,
           tokenMint: tokenMint.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
           vault: user2VaultPda,
           userTokenAccount: user2TokenAccount,
           vaultTokenAccount: user2VaultTokenAccount,
@@ -604,6 +702,7 @@ describe("collateral_vault", () => {
           fromVault: user1VaultPda,
           toVault: user2VaultPda,
           vaultAuthority: vaultAuthorityPda,
+          integratorStats: integratorStatsPda,
           tokenMint: tokenMint.publicKey,
           fromVaultTokenAccount: user1VaultTokenAccount,
           toVaultTokenAccount: user2VaultTokenAccount,
@@ -617,10 +716,48 @@ describe("collateral_vault", () => {
       
       expect(fromVault.totalBalance.toString()).to.equal(new anchor.BN(700 * 1e6).toString());
       expect(toVault.totalBalance.toString()).to.equal(transferAmount.toString());
+      expect(fromVault.totalTransferredOut.toString()).to.equal(transferAmount.toString());
+      expect(toVault.totalTransferredIn.toString()).to.equal(transferAmount.toString());
       
       console.log("✅ Transfer successful!");
       await logAccountDetails("After Transfer Collateral");
     });
+
+    it("should fail when transferring into the same vault", async () => {
+      console.log("🧪 Testing transfer into the source vault");
+
+      const user1VaultTokenAccount = getAssociatedTokenAddressSync(
+        tokenMint.publicKey,
+        user1VaultPda,
+        true,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      try {
+        await program.methods
+          .transferCollateral(new anchor.BN(1 * 1e6))
+          .accounts({
+            signer: authorizedProgram.publicKey,
+            fromVault: user1VaultPda,
+            toVault: user1VaultPda,
+            vaultAuthority: vaultAuthorityPda,
+            integratorStats: integratorStatsPda,
+            tokenMint: tokenMint.publicKey,
+            fromVaultTokenAccount: user1VaultTokenAccount,
+            toVaultTokenAccount: user1VaultTokenAccount,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .signers([authorizedProgram])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("SameVault");
+        console.log("✅ Correctly rejected transfer into the same vault");
+      }
+
+      const vault = await program.account.collateralVault.fetch(user1VaultPda);
+      expect(vault.totalBalance.toString()).to.equal(new anchor.BN(700 * 1e6).toString());
+    });
   });
 
   // Test 8: Close Vault
@@ -636,6 +773,8 @@ describe("collateral_vault", () => {
           signer: authorizedProgram.publicKey,
           vault: user1VaultPda,
           vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
         })
         .signers([authorizedProgram])
         .rpc();
@@ -658,6 +797,7 @@ describe("collateral_vault", () => {
           user: user1.publicKey,
           vault: user1VaultPda,
           tokenMint: tokenMint.publicKey,
+          mintStats: mintStatsPda,
           userTokenAccount: user1TokenAccount,
           vaultTokenAccount: user1VaultTokenAccount,
           tokenProgram: TOKEN_2022_PROGRAM_ID,
@@ -676,6 +816,7 @@ describe("collateral_vault", () => {
           user: user1.publicKey,
           vault: user1VaultPda,
           vaultTokenAccount: user1VaultTokenAccount,
+          mintStats: mintStatsPda,
         })
         .signers([user1])
         .rpc();
@@ -767,6 +908,2296 @@ describe("collateral_vault", () => {
       console.log("✅ Program removed successfully!");
       await logAccountDetails("After Remove Authorized Program");
     });
+
+    it("should fail to remove a program that is not authorized", async () => {
+      try {
+        await program.methods
+          .removeAuthorizedProgram(authorizedProgram.publicKey)
+          .accounts({
+            admin: admin.publicKey,
+            vaultAuthority: vaultAuthorityPda,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("ProgramNotAuthorized");
+        console.log("✅ Correctly rejected removal of an unlisted program");
+      }
+    });
+  });
+
+  // Test 11: Pooled Custody
+  describe("11. pooled custody", () => {
+    let user3: Keypair;
+    let user3TokenAccount: PublicKey;
+    let user3VaultPda: PublicKey;
+    let custodyPoolPda: PublicKey;
+    let custodyTokenAccount: PublicKey;
+
+    before(async () => {
+      console.log("🔧 Setting up for Test 11: Pooled Custody");
+
+      // Re-authorize the program removed in Test 10
+      await program.methods
+        .addAuthorizedProgram(authorizedProgram.publicKey)
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+        })
+        .signers([admin])
+        .rpc();
+
+      user3 = Keypair.generate();
+      const user3Airdrop = await provider.connection.requestAirdrop(user3.publicKey, 10 * LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(user3Airdrop);
+
+      user3TokenAccount = getAssociatedTokenAddressSync(
+        tokenMint.publicKey,
+        user3.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const createUser3AtaIx = {
+        keys: [
+          { pubkey: user3.publicKey, isSigner: true, isWritable: true },
+          { pubkey: user3TokenAccount, isSigner: false, isWritable: true },
+          { pubkey: user3.publicKey, isSigner: false, isWritable: false },
+          { pubkey: tokenMint.publicKey, isSigner: false, isWritable: false },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        ],
+        programId: ASSOCIATED_TOKEN_PROGRAM_ID,
+        data: Buffer.from([]),
+      };
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(createUser3AtaIx), [user3]);
+
+      const mintToUser3Ix = {
+        keys: [
+          { pubkey: tokenMint.publicKey, isSigner: false, isWritable: true },
+          { pubkey: user3TokenAccount, isSigner: false, isWritable: true },
+          { pubkey: admin.publicKey, isSigner: true, isWritable: false },
+        ],
+        programId: TOKEN_2022_PROGRAM_ID,
+        data: Buffer.from([
+          7, // MintTo instruction
+          ...new anchor.BN(1000 * 1e6).toArrayLike(Buffer, "le", 8),
+        ]),
+      };
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(mintToUser3Ix), [admin]);
+
+      [user3VaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), user3.publicKey.toBuffer()],
+        program.programId
+      );
+      [custodyPoolPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("custody_pool"), tokenMint.publicKey.toBuffer()],
+        program.programId
+      );
+      custodyTokenAccount = getAssociatedTokenAddressSync(
+        tokenMint.publicKey,
+        custodyPoolPda,
+        true,
+        TOKEN_2022_PROGRAM_ID
+      );
+      console.log(`   Custody Pool PDA: ${custodyPoolPda.toString()}`);
+      console.log(`   Custody Token Account: ${custodyTokenAccount.toString()}`);
+    });
+
+    it("should initialize custody pool and pooled vaults", async () => {
+      console.log("🧪 Starting Test 11: Pooled Custody");
+
+      await program.methods
+        .initializeCustodyPool()
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          tokenMint: tokenMint.publicKey,
+          custodyPool: custodyPoolPda,
+          custodyTokenAccount,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      // User1's regular vault was closed in Test 8, so the PDA is free again
+      for (const [user, vaultPda] of [[user1, user1VaultPda], [user3, user3VaultPda]] as [Keypair, PublicKey][]) {
+        await program.methods
+          .initializePooledVault()
+          .accounts({
+            user: user.publicKey,
+            tokenMint: tokenMint.publicKey,
+            vaultAuthority: vaultAuthorityPda,
+            mintConfig: mintConfigPda,
+            mintStats: mintStatsPda,
+            custodyPool: custodyPoolPda,
+            vault: vaultPda,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc();
+      }
+
+      const pool = await program.account.custodyPool.fetch(custodyPoolPda);
+      expect(pool.vaultCount).to.equal(2);
+      expect(pool.totalBalance.toNumber()).to.equal(0);
+
+      const vault = await program.account.collateralVault.fetch(user3VaultPda);
+      expect(vault.pooled).to.equal(true);
+      expect(vault.tokenAccount.toString()).to.equal(custodyTokenAccount.toString());
+
+      console.log("✅ Custody pool and pooled vaults initialized!");
+    });
+
+    it("should deposit, transfer internally and withdraw from the pool", async () => {
+      await program.methods
+        .depositPooled(new anchor.BN(500 * 1e6))
+        .accounts({
+          user: user3.publicKey,
+          vault: user3VaultPda,
+          custodyPool: custodyPoolPda,
+          tokenMint: tokenMint.publicKey,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
+          userTokenAccount: user3TokenAccount,
+          custodyTokenAccount,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user3])
+        .rpc();
+
+      await program.methods
+        .transferCollateralInternal(new anchor.BN(200 * 1e6))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          fromVault: user3VaultPda,
+          toVault: user1VaultPda,
+          vaultAuthority: vaultAuthorityPda,
+          integratorStats: integratorStatsPda,
+        })
+        .signers([authorizedProgram])
+        .rpc();
+
+      // Custody token balance is untouched by the internal transfer
+      let custody = await getAccount(provider.connection, custodyTokenAccount, "confirmed", TOKEN_2022_PROGRAM_ID);
+      expect(custody.amount.toString()).to.equal(new anchor.BN(500 * 1e6).toString());
+
+      await program.methods
+        .withdrawPooled(new anchor.BN(200 * 1e6))
+        .accounts({
+          user: user1.publicKey,
+          vault: user1VaultPda,
+          custodyPool: custodyPoolPda,
+          tokenMint: tokenMint.publicKey,
+          mintStats: mintStatsPda,
+          userTokenAccount: user1TokenAccount,
+          custodyTokenAccount,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+
+      const user1Vault = await program.account.collateralVault.fetch(user1VaultPda);
+      const user3Vault = await program.account.collateralVault.fetch(user3VaultPda);
+      const pool = await program.account.custodyPool.fetch(custodyPoolPda);
+      custody = await getAccount(provider.connection, custodyTokenAccount, "confirmed", TOKEN_2022_PROGRAM_ID);
+
+      expect(user1Vault.totalBalance.toNumber()).to.equal(0);
+      expect(user3Vault.totalBalance.toString()).to.equal(new anchor.BN(300 * 1e6).toString());
+      expect(pool.totalBalance.toString()).to.equal(new anchor.BN(300 * 1e6).toString());
+      expect(custody.amount.toString()).to.equal(new anchor.BN(300 * 1e6).toString());
+
+      console.log("✅ Pooled deposit, internal transfer and withdrawal successful!");
+    });
+
+    it("should verify custody pool invariants", async () => {
+      await program.methods
+        .verifyCustodyPool()
+        .accounts({
+          custodyPool: custodyPoolPda,
+          custodyTokenAccount,
+        })
+        .remainingAccounts([
+          { pubkey: user1VaultPda, isSigner: false, isWritable: false },
+          { pubkey: user3VaultPda, isSigner: false, isWritable: false },
+        ])
+        .rpc();
+
+      try {
+        await program.methods
+          .verifyCustodyPool()
+          .accounts({
+            custodyPool: custodyPoolPda,
+            custodyTokenAccount,
+          })
+          .remainingAccounts([
+            { pubkey: user3VaultPda, isSigner: false, isWritable: false },
+          ])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("PoolVaultSetMismatch");
+        console.log("✅ Correctly rejected incomplete vault set");
+      }
+    });
+
+    it("should reject regular deposits into a pooled vault", async () => {
+      try {
+        await program.methods
+          .deposit(new anchor.BN(1 * 1e6))
+          .accounts({
+            user: user3.publicKey,
+            vault: user3VaultPda,
+            tokenMint: tokenMint.publicKey,
+            mintConfig: mintConfigPda,
+            mintStats: mintStatsPda,
+            userTokenAccount: user3TokenAccount,
+            vaultTokenAccount: custodyTokenAccount,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .signers([user3])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("PooledVault");
+        console.log("✅ Correctly rejected regular deposit into pooled vault");
+      }
+    });
+
+    it("should reject sub-accounts under a pooled vault", async () => {
+      const [pooledSubAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sub_account"), user3VaultPda.toBuffer(), Buffer.from([0])],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .createSubAccount(0)
+          .accounts({
+            user: user3.publicKey,
+            vault: user3VaultPda,
+            subAccount: pooledSubAccount,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user3])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("PooledVault");
+        console.log("✅ Correctly rejected sub-account under pooled vault");
+      }
+    });
+  });
+
+  // Test 12: Sub-Accounts
+  describe("12. sub-accounts", () => {
+    let subAccount0: PublicKey;
+    let subAccount1: PublicKey;
+
+    before(async () => {
+      [subAccount0] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sub_account"), user2VaultPda.toBuffer(), Buffer.from([0])],
+        program.programId
+      );
+      [subAccount1] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sub_account"), user2VaultPda.toBuffer(), Buffer.from([1])],
+        program.programId
+      );
+      console.log(`   Sub-Account 0: ${subAccount0.toString()}`);
+      console.log(`   Sub-Account 1: ${subAccount1.toString()}`);
+    });
+
+    it("should create sub-accounts and allocate funds", async () => {
+      console.log("🧪 Starting Test 12: Sub-Accounts");
+
+      for (const [id, subAccount] of [[0, subAccount0], [1, subAccount1]] as [number, PublicKey][]) {
+        await program.methods
+          .createSubAccount(id)
+          .accounts({
+            user: user2.publicKey,
+            vault: user2VaultPda,
+            subAccount,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user2])
+          .rpc();
+      }
+
+      await program.methods
+        .allocateToSubAccount(new anchor.BN(60 * 1e6))
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultPda,
+          subAccount: subAccount0,
+        })
+        .signers([user2])
+        .rpc();
+
+      await program.methods
+        .moveBetweenSubAccounts(new anchor.BN(10 * 1e6))
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultPda,
+          fromSubAccount: subAccount0,
+          toSubAccount: subAccount1,
+        })
+        .signers([user2])
+        .rpc();
+
+      const vault = await program.account.collateralVault.fetch(user2VaultPda);
+      const sub0 = await program.account.subAccount.fetch(subAccount0);
+      const sub1 = await program.account.subAccount.fetch(subAccount1);
+
+      expect(vault.totalBalance.toString()).to.equal(new anchor.BN(40 * 1e6).toString());
+      expect(vault.subAccountBalance.toString()).to.equal(new anchor.BN(60 * 1e6).toString());
+      expect(sub0.totalBalance.toString()).to.equal(new anchor.BN(50 * 1e6).toString());
+      expect(sub1.totalBalance.toString()).to.equal(new anchor.BN(10 * 1e6).toString());
+
+      console.log("✅ Sub-accounts created and funded!");
+    });
+
+    it("should isolate locks per sub-account", async () => {
+      await program.methods
+        .lockSubAccountCollateral(new anchor.BN(50 * 1e6))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          vault: user2VaultPda,
+          subAccount: subAccount0,
+          vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
+        })
+        .signers([authorizedProgram])
+        .rpc();
+
+      // Sub-account 0 is fully locked, so it cannot release anything
+      try {
+        await program.methods
+          .releaseFromSubAccount(new anchor.BN(1 * 1e6))
+          .accounts({
+            user: user2.publicKey,
+            vault: user2VaultPda,
+            subAccount: subAccount0,
+          })
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("InsufficientAvailableBalance");
+        console.log("✅ Correctly rejected release of locked sub-account funds");
+      }
+
+      // Sub-account 1 is unaffected
+      await program.methods
+        .releaseFromSubAccount(new anchor.BN(10 * 1e6))
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultPda,
+          subAccount: subAccount1,
+        })
+        .signers([user2])
+        .rpc();
+
+      await program.methods
+        .unlockSubAccountCollateral(new anchor.BN(50 * 1e6))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          vault: user2VaultPda,
+          subAccount: subAccount0,
+          vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
+        })
+        .signers([authorizedProgram])
+        .rpc();
+
+      const vault = await program.account.collateralVault.fetch(user2VaultPda);
+      const sub0 = await program.account.subAccount.fetch(subAccount0);
+      expect(vault.availableBalance.toString()).to.equal(new anchor.BN(50 * 1e6).toString());
+      expect(sub0.lockedBalance.toNumber()).to.equal(0);
+      expect(sub0.availableBalance.toString()).to.equal(new anchor.BN(50 * 1e6).toString());
+
+      console.log("✅ Sub-account locks are isolated!");
+    });
+
+    it("should close an empty sub-account", async () => {
+      await program.methods
+        .closeSubAccount()
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultPda,
+          subAccount: subAccount1,
+        })
+        .signers([user2])
+        .rpc();
+
+      try {
+        await program.account.subAccount.fetch(subAccount1);
+        expect.fail("Sub-account should be closed");
+      } catch (err: any) {
+        expect(err.message).to.include("Account does not exist");
+        console.log("✅ Sub-account successfully closed");
+      }
+    });
+  });
+
+  // Test 13: Portfolio Vault
+  describe("13. portfolio vault", () => {
+    let secondMint: Keypair;
+    let user2SecondTokenAccount: PublicKey;
+    let portfolioPda: PublicKey;
+
+    const portfolioTokenAccount = (mint: PublicKey) =>
+      getAssociatedTokenAddressSync(mint, portfolioPda, true, TOKEN_2022_PROGRAM_ID);
+
+    const mintConfigFor = (mint: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("mint_config"), mint.toBuffer()], program.programId)[0];
+
+    const mintStatsFor = (mint: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("mint_stats"), mint.toBuffer()], program.programId)[0];
+
+    // Portfolio locks take each leg's mint stats as remaining accounts
+    const legStats = (...mints: PublicKey[]) =>
+      mints.map((mint) => ({ pubkey: mintStatsFor(mint), isSigner: false, isWritable: true }));
+
+    before(async () => {
+      console.log("🔧 Setting up for Test 13: Portfolio Vault");
+
+      secondMint = Keypair.generate();
+      const mintRent = await provider.connection.getMinimumBalanceForRentExemption(82);
+
+      const createMintIx = SystemProgram.createAccount({
+        fromPubkey: admin.publicKey,
+        newAccountPubkey: secondMint.publicKey,
+        space: 82,
+        lamports: mintRent,
+        programId: TOKEN_2022_PROGRAM_ID,
+      });
+
+      const initMintIx = {
+        keys: [
+          { pubkey: secondMint.publicKey, isSigner: false, isWritable: true },
+          { pubkey: anchor.web3.SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        ],
+        programId: TOKEN_2022_PROGRAM_ID,
+        data: Buffer.from([
+          0, // InitializeMint instruction
+          9,
+          ...admin.publicKey.toBytes(),
+          0, // Option: None
+          ...new Uint8Array(32),
+        ]),
+      };
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(createMintIx, initMintIx), [admin, secondMint]);
+
+      user2SecondTokenAccount = getAssociatedTokenAddressSync(
+        secondMint.publicKey,
+        user2.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const createAtaIx = {
+        keys: [
+          { pubkey: user2.publicKey, isSigner: true, isWritable: true },
+          { pubkey: user2SecondTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: user2.publicKey, isSigner: false, isWritable: false },
+          { pubkey: secondMint.publicKey, isSigner: false, isWritable: false },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        ],
+        programId: ASSOCIATED_TOKEN_PROGRAM_ID,
+        data: Buffer.from([]),
+      };
+
+      const mintToIx = {
+        keys: [
+          { pubkey: secondMint.publicKey, isSigner: false, isWritable: true },
+          { pubkey: user2SecondTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: admin.publicKey, isSigner: true, isWritable: false },
+        ],
+        programId: TOKEN_2022_PROGRAM_ID,
+        data: Buffer.from([
+          7, // MintTo instruction
+          ...new anchor.BN(10 * 1e9).toArrayLike(Buffer, "le", 8),
+        ]),
+      };
+      await provider.sendAndConfirm(new anchor.web3.Transaction().add(createAtaIx, mintToIx), [user2, admin]);
+
+      await program.methods
+        .initializeMintConfig({
+          approved: true,
+          allowedExtensions: new anchor.BN(0),
+          allowFreezeAuthority: false,
+          maxDepositAmount: new anchor.BN(0),
+          maxVaultBalance: new anchor.BN(0),
+          maxTotalBalance: new anchor.BN(0),
+        })
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          tokenMint: secondMint.publicKey,
+          mintConfig: mintConfigFor(secondMint.publicKey),
+          mintStats: mintStatsFor(secondMint.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      [portfolioPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("portfolio"), user2.publicKey.toBuffer()],
+        program.programId
+      );
+      console.log(`   Second Mint: ${secondMint.publicKey.toString()}`);
+      console.log(`   Portfolio PDA: ${portfolioPda.toString()}`);
+    });
+
+    it("should hold balances for several mints", async () => {
+      console.log("🧪 Starting Test 13: Portfolio Vault");
+
+      await program.methods
+        .initializePortfolioVault()
+        .accounts({
+          user: user2.publicKey,
+          portfolioVault: portfolioPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+      const deposits: [PublicKey, PublicKey, anchor.BN][] = [
+        [tokenMint.publicKey, user2TokenAccount, new anchor.BN(100 * 1e6)],
+        [secondMint.publicKey, user2SecondTokenAccount, new anchor.BN(2 * 1e9)],
+      ];
+
+      for (const [mint, userTokenAccount, amount] of deposits) {
+        await program.methods
+          .addPortfolioMint()
+          .accounts({
+            user: user2.publicKey,
+            portfolioVault: portfolioPda,
+            tokenMint: mint,
+            vaultAuthority: vaultAuthorityPda,
+            mintConfig: mintConfigFor(mint),
+            vaultTokenAccount: portfolioTokenAccount(mint),
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user2])
+          .rpc();
+
+        await program.methods
+          .depositPortfolio(amount)
+          .accounts({
+            user: user2.publicKey,
+            portfolioVault: portfolioPda,
+            tokenMint: mint,
+            mintConfig: mintConfigFor(mint),
+            mintStats: mintStatsFor(mint),
+            userTokenAccount,
+            vaultTokenAccount: portfolioTokenAccount(mint),
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .signers([user2])
+          .rpc();
+      }
+
+      const portfolio = await program.account.portfolioVault.fetch(portfolioPda);
+      expect(portfolio.balances.length).to.equal(2);
+      expect(portfolio.balances[1].mint.toString()).to.equal(secondMint.publicKey.toString());
+      expect(portfolio.balances[1].totalBalance.toString()).to.equal(new anchor.BN(2 * 1e9).toString());
+
+      console.log("✅ Portfolio vault funded with two mints!");
+    });
+
+    it("should lock a basket atomically", async () => {
+      await program.methods
+        .lockPortfolioBasket([
+          { mint: tokenMint.publicKey, amount: new anchor.BN(40 * 1e6) },
+          { mint: secondMint.publicKey, amount: new anchor.BN(1 * 1e9) },
+        ])
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          portfolioVault: portfolioPda,
+          vaultAuthority: vaultAuthorityPda,
+          integratorStats: integratorStatsPda,
+        })
+        .remainingAccounts(legStats(tokenMint.publicKey, secondMint.publicKey))
+        .signers([authorizedProgram])
+        .rpc();
+
+      // Second leg exceeds the available balance, so nothing is locked
+      try {
+        await program.methods
+          .lockPortfolioBasket([
+            { mint: tokenMint.publicKey, amount: new anchor.BN(10 * 1e6) },
+            { mint: secondMint.publicKey, amount: new anchor.BN(5 * 1e9) },
+          ])
+          .accounts({
+            signer: authorizedProgram.publicKey,
+            portfolioVault: portfolioPda,
+            vaultAuthority: vaultAuthorityPda,
+            integratorStats: integratorStatsPda,
+          })
+          .remainingAccounts(legStats(tokenMint.publicKey, secondMint.publicKey))
+          .signers([authorizedProgram])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("InsufficientAvailableBalance");
+      }
+
+      const portfolio = await program.account.portfolioVault.fetch(portfolioPda);
+      expect(portfolio.balances[0].lockedBalance.toString()).to.equal(new anchor.BN(40 * 1e6).toString());
+      expect(portfolio.balances[1].lockedBalance.toString()).to.equal(new anchor.BN(1 * 1e9).toString());
+
+      await program.methods
+        .unlockPortfolioCollateral(secondMint.publicKey, new anchor.BN(1 * 1e9))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          portfolioVault: portfolioPda,
+          vaultAuthority: vaultAuthorityPda,
+          integratorStats: integratorStatsPda,
+        })
+        .remainingAccounts(legStats(secondMint.publicKey))
+        .signers([authorizedProgram])
+        .rpc();
+
+      await program.methods
+        .withdrawPortfolio(new anchor.BN(2 * 1e9))
+        .accounts({
+          user: user2.publicKey,
+          portfolioVault: portfolioPda,
+          tokenMint: secondMint.publicKey,
+          mintStats: mintStatsFor(secondMint.publicKey),
+          userTokenAccount: user2SecondTokenAccount,
+          vaultTokenAccount: portfolioTokenAccount(secondMint.publicKey),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc();
+
+      const after = await program.account.portfolioVault.fetch(portfolioPda);
+      expect(after.balances[1].totalBalance.toNumber()).to.equal(0);
+
+      console.log("✅ Basket lock, unlock and withdrawal successful!");
+    });
+
+    it("should only close an empty portfolio", async () => {
+      try {
+        await program.methods
+          .closePortfolioVault()
+          .accounts({
+            user: user2.publicKey,
+            portfolioVault: portfolioPda,
+          })
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("VaultNotEmpty");
+        console.log("✅ Correctly rejected closing a funded portfolio");
+      }
+    });
+  });
+
+  // Test 14: Oracle Valuation (requires the program built with `--features mock-oracle`)
+  describe("14. oracle valuation", () => {
+    let priceFeed: Keypair;
+    let oracleConfigPda: PublicKey;
+
+    before(async function () {
+      if (!program.idl.instructions.some((ix) => ix.name === "set_mock_price")) {
+        console.log("⏭️  Skipping oracle tests: program built without mock-oracle");
+        this.skip();
+      }
+
+      priceFeed = Keypair.generate();
+      [oracleConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("oracle_config"), tokenMint.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await (program.methods as any)
+        .initializeMockPriceFeed()
+        .accounts({
+          authority: admin.publicKey,
+          priceFeed: priceFeed.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin, priceFeed])
+        .rpc();
+
+      // $1.00 with a 0.01% confidence interval
+      await (program.methods as any)
+        .setMockPrice(new anchor.BN(100_000_000), new anchor.BN(10_000), -8, null)
+        .accounts({
+          authority: admin.publicKey,
+          priceFeed: priceFeed.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      console.log(`   Mock Price Feed: ${priceFeed.publicKey.toString()}`);
+      console.log(`   Oracle Config PDA: ${oracleConfigPda.toString()}`);
+    });
+
+    it("should value a vault with a haircut", async () => {
+      console.log("🧪 Starting Test 14: Oracle Valuation");
+
+      await program.methods
+        .initializeOracleConfig({
+          oracle: priceFeed.publicKey,
+          maxStaleness: new anchor.BN(60),
+          maxConfidenceBps: 100,
+          haircutBps: 1000,
+        })
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          tokenMint: tokenMint.publicKey,
+          oracleConfig: oracleConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const value = await program.methods
+        .getCollateralValue()
+        .accounts({
+          vault: user2VaultPda,
+          tokenMint: tokenMint.publicKey,
+          oracleConfig: oracleConfigPda,
+          oracle: priceFeed.publicKey,
+        })
+        .view();
+
+      // 50 tokens at $1.00 less a 10% haircut
+      expect(value.totalBalance.toString()).to.equal(new anchor.BN(50 * 1e6).toString());
+      expect(value.totalValue.toString()).to.equal(new anchor.BN(45 * 1e6).toString());
+      expect(value.quoteDecimals).to.equal(6);
+
+      console.log(`   Risk-adjusted value: ${value.totalValue.toString()}`);
+      console.log("✅ Collateral valued successfully!");
+    });
+
+    it("should reject a stale price", async () => {
+      const stale = Math.floor(Date.now() / 1000) - 3600;
+      await (program.methods as any)
+        .setMockPrice(new anchor.BN(100_000_000), new anchor.BN(10_000), -8, new anchor.BN(stale))
+        .accounts({
+          authority: admin.publicKey,
+          priceFeed: priceFeed.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      try {
+        await program.methods
+          .getCollateralValue()
+          .accounts({
+            vault: user2VaultPda,
+            tokenMint: tokenMint.publicKey,
+            oracleConfig: oracleConfigPda,
+            oracle: priceFeed.publicKey,
+          })
+          .view();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("StaleOraclePrice");
+        console.log("✅ Correctly rejected stale price");
+      }
+
+      // Restore a fresh price for later tests
+      await (program.methods as any)
+        .setMockPrice(new anchor.BN(100_000_000), new anchor.BN(10_000), -8, null)
+        .accounts({
+          authority: admin.publicKey,
+          priceFeed: priceFeed.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+    });
+  });
+
+  // Test 15: Lock Value (requires the program built with `--features mock-oracle`)
+  describe("15. lock_value", () => {
+    before(function () {
+      if (!program.idl.instructions.some((ix) => ix.name === "set_mock_price")) {
+        this.skip();
+      }
+    });
+
+    it("should lock collateral covering a quote value", async () => {
+      console.log("🧪 Starting Test 15: Lock Value");
+
+      const [oracleConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("oracle_config"), tokenMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const oracleConfig = await program.account.oracleConfig.fetch(oracleConfigPda);
+      const before = await program.account.collateralVault.fetch(user2VaultPda);
+
+      // $9.00 at $1.00 with a 10% haircut needs 10 tokens
+      await program.methods
+        .lockValue(new anchor.BN(9 * 1e6))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          vault: user2VaultPda,
+          vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
+          tokenMint: tokenMint.publicKey,
+          oracleConfig: oracleConfigPda,
+          oracle: oracleConfig.oracle,
+        })
+        .signers([authorizedProgram])
+        .rpc();
+
+      const after = await program.account.collateralVault.fetch(user2VaultPda);
+      expect(after.lockedBalance.sub(before.lockedBalance).toString()).to.equal(new anchor.BN(10 * 1e6).toString());
+
+      await program.methods
+        .unlockCollateral(new anchor.BN(10 * 1e6))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          vault: user2VaultPda,
+          vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
+        })
+        .signers([authorizedProgram])
+        .rpc();
+
+      console.log("✅ Value-based lock successful!");
+    });
+
+    it("should lock a value-weighted portfolio basket", async () => {
+      const [oracleConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("oracle_config"), tokenMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const [portfolioPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("portfolio"), user2.publicKey.toBuffer()],
+        program.programId
+      );
+      const oracleConfig = await program.account.oracleConfig.fetch(oracleConfigPda);
+      const before = await program.account.portfolioVault.fetch(portfolioPda);
+      const accounts = {
+        signer: authorizedProgram.publicKey,
+        portfolioVault: portfolioPda,
+        vaultAuthority: vaultAuthorityPda,
+        integratorStats: integratorStatsPda,
+      };
+
+      // The whole $9.00 on one mint at $1.00 with a 10% haircut needs 10 tokens
+      await program.methods
+        .lockPortfolioValue(new anchor.BN(9 * 1e6), [{ mint: tokenMint.publicKey, weightBps: 10_000 }])
+        .accounts(accounts)
+        .remainingAccounts([
+          { pubkey: tokenMint.publicKey, isSigner: false, isWritable: false },
+          { pubkey: oracleConfigPda, isSigner: false, isWritable: false },
+          { pubkey: oracleConfig.oracle, isSigner: false, isWritable: false },
+          { pubkey: mintStatsPda, isSigner: false, isWritable: true },
+        ])
+        .signers([authorizedProgram])
+        .rpc();
+      const legs = [{ mint: tokenMint.publicKey, amount: new anchor.BN(10 * 1e6) }];
+
+      const after = await program.account.portfolioVault.fetch(portfolioPda);
+      expect(after.balances[0].lockedBalance.sub(before.balances[0].lockedBalance).toString()).to.equal(
+        legs[0].amount.toString()
+      );
+
+      await program.methods
+        .unlockPortfolioBasket(legs)
+        .accounts(accounts)
+        .remainingAccounts([{ pubkey: mintStatsPda, isSigner: false, isWritable: true }])
+        .signers([authorizedProgram])
+        .rpc();
+
+      console.log("✅ Value-weighted basket lock successful!");
+    });
+  });
+
+  // Test 16: Mint Registry
+  describe("16. mint registry", () => {
+    it("should enforce the per-mint deposit limit", async () => {
+      console.log("🧪 Starting Test 16: Mint Registry");
+
+      await program.methods
+        .updateMintConfig({
+          approved: true,
+          allowedExtensions: new anchor.BN(0),
+          allowFreezeAuthority: true,
+          maxDepositAmount: new anchor.BN(10 * 1e6),
+          maxVaultBalance: new anchor.BN(0),
+          maxTotalBalance: new anchor.BN(0),
+        })
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          mintConfig: mintConfigPda,
+        })
+        .signers([admin])
+        .rpc();
+
+      const user2VaultTokenAccount = getAssociatedTokenAddressSync(
+        tokenMint.publicKey,
+        user2VaultPda,
+        true,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      try {
+        await program.methods
+          .deposit(new anchor.BN(11 * 1e6))
+          .accounts({
+            user: user2.publicKey,
+            vault: user2VaultPda,
+            tokenMint: tokenMint.publicKey,
+            mintConfig: mintConfigPda,
+            mintStats: mintStatsPda,
+            userTokenAccount: user2TokenAccount,
+            vaultTokenAccount: user2VaultTokenAccount,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("DepositLimitExceeded");
+        console.log("✅ Correctly rejected oversized deposit");
+      }
+    });
+
+    it("should reject deposits once a mint is revoked", async () => {
+      await program.methods
+        .updateMintConfig({
+          approved: false,
+          allowedExtensions: new anchor.BN(0),
+          allowFreezeAuthority: true,
+          maxDepositAmount: new anchor.BN(0),
+          maxVaultBalance: new anchor.BN(0),
+          maxTotalBalance: new anchor.BN(0),
+        })
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          mintConfig: mintConfigPda,
+        })
+        .signers([admin])
+        .rpc();
+
+      const user2VaultTokenAccount = getAssociatedTokenAddressSync(
+        tokenMint.publicKey,
+        user2VaultPda,
+        true,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      try {
+        await program.methods
+          .deposit(new anchor.BN(1 * 1e6))
+          .accounts({
+            user: user2.publicKey,
+            vault: user2VaultPda,
+            tokenMint: tokenMint.publicKey,
+            mintConfig: mintConfigPda,
+            mintStats: mintStatsPda,
+            userTokenAccount: user2TokenAccount,
+            vaultTokenAccount: user2VaultTokenAccount,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .signers([user2])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("MintNotApproved");
+        console.log("✅ Correctly rejected deposit of revoked mint");
+      }
+
+      // Restore approval for later tests
+      await program.methods
+        .updateMintConfig({
+          approved: true,
+          allowedExtensions: new anchor.BN(0),
+          allowFreezeAuthority: true,
+          maxDepositAmount: new anchor.BN(0),
+          maxVaultBalance: new anchor.BN(0),
+          maxTotalBalance: new anchor.BN(0),
+        })
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          mintConfig: mintConfigPda,
+        })
+        .signers([admin])
+        .rpc();
+    });
+  });
+
+  // Test 17: Token-2022 Extension Deny-List
+  describe("17. extension deny-list", () => {
+    it("should reject a mint with a permanent delegate", async () => {
+      console.log("🧪 Starting Test 17: Extension Deny-List");
+
+      const delegateMint = Keypair.generate();
+      const user = Keypair.generate();
+      const userAirdrop = await provider.connection.requestAirdrop(user.publicKey, 2 * LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(userAirdrop);
+
+      const mintLen = getMintLen([ExtensionType.PermanentDelegate]);
+      const mintRent = await provider.connection.getMinimumBalanceForRentExemption(mintLen);
+      const userTokenAccount = getAssociatedTokenAddressSync(
+        delegateMint.publicKey,
+        user.publicKey,
+        false,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const createMintTx = new anchor.web3.Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: admin.publicKey,
+          newAccountPubkey: delegateMint.publicKey,
+          space: mintLen,
+          lamports: mintRent,
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializePermanentDelegateInstruction(delegateMint.publicKey, admin.publicKey, TOKEN_2022_PROGRAM_ID),
+        createInitializeMintInstruction(delegateMint.publicKey, 6, admin.publicKey, null, TOKEN_2022_PROGRAM_ID),
+        createAssociatedTokenAccountIdempotentInstruction(
+          admin.publicKey,
+          userTokenAccount,
+          user.publicKey,
+          delegateMint.publicKey,
+          TOKEN_2022_PROGRAM_ID
+        )
+      );
+      await provider.sendAndConfirm(createMintTx, [admin, delegateMint]);
+
+      // Registry allows every extension, so only the deny-list can reject it
+      const [delegateMintConfig] = PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_config"), delegateMint.publicKey.toBuffer()],
+        program.programId
+      );
+      const [delegateMintStats] = PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_stats"), delegateMint.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .initializeMintConfig({
+          approved: true,
+          allowedExtensions: new anchor.BN("ffffffffffffffff", 16),
+          allowFreezeAuthority: false,
+          maxDepositAmount: new anchor.BN(0),
+          maxVaultBalance: new anchor.BN(0),
+          maxTotalBalance: new anchor.BN(0),
+        })
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          tokenMint: delegateMint.publicKey,
+          mintConfig: delegateMintConfig,
+          mintStats: delegateMintStats,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const [userVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), user.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .initializeVault()
+          .accounts({
+            user: user.publicKey,
+            tokenMint: delegateMint.publicKey,
+            vaultAuthority: vaultAuthorityPda,
+            mintConfig: delegateMintConfig,
+            mintStats: delegateMintStats,
+            vault: userVaultPda,
+            userTokenAccount,
+            vaultTokenAccount: getAssociatedTokenAddressSync(
+              delegateMint.publicKey,
+              userVaultPda,
+              true,
+              TOKEN_2022_PROGRAM_ID
+            ),
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("PermanentDelegateNotAllowed");
+        console.log("✅ Correctly rejected permanent delegate mint");
+      }
+    });
+  });
+
+  // Test 18: Transfer-Hook Mints
+  describe("18. transfer-hook mints", () => {
+    const hookMint = Keypair.generate();
+    const userA = Keypair.generate();
+    const userB = Keypair.generate();
+    let userATokenAccount: PublicKey;
+    let userBTokenAccount: PublicKey;
+    let userAVaultPda: PublicKey;
+    let userBVaultPda: PublicKey;
+    let hookMintConfig: PublicKey;
+    let hookMintStats: PublicKey;
+    let hookAccounts: anchor.web3.AccountMeta[];
+    let hookCounterPda: PublicKey;
+    let previousDeniedExtensions: anchor.BN;
+
+    const vaultTokenAccountFor = (vault: PublicKey) =>
+      getAssociatedTokenAddressSync(hookMint.publicKey, vault, true, TOKEN_2022_PROGRAM_ID);
+
+    before(async () => {
+      console.log("🔧 Setting up for Test 18: Transfer-Hook Mints");
+
+      for (const user of [userA, userB]) {
+        const airdrop = await provider.connection.requestAirdrop(user.publicKey, 2 * LAMPORTS_PER_SOL);
+        await provider.connection.confirmTransaction(airdrop);
+      }
+
+      userATokenAccount = getAssociatedTokenAddressSync(hookMint.publicKey, userA.publicKey, false, TOKEN_2022_PROGRAM_ID);
+      userBTokenAccount = getAssociatedTokenAddressSync(hookMint.publicKey, userB.publicKey, false, TOKEN_2022_PROGRAM_ID);
+
+      const mintLen = getMintLen([ExtensionType.TransferHook]);
+      const mintRent = await provider.connection.getMinimumBalanceForRentExemption(mintLen);
+      const createMintTx = new anchor.web3.Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: admin.publicKey,
+          newAccountPubkey: hookMint.publicKey,
+          space: mintLen,
+          lamports: mintRent,
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferHookInstruction(hookMint.publicKey, admin.publicKey, hookProgram.programId, TOKEN_2022_PROGRAM_ID),
+        createInitializeMintInstruction(hookMint.publicKey, 6, admin.publicKey, null, TOKEN_2022_PROGRAM_ID),
+        createAssociatedTokenAccountIdempotentInstruction(admin.publicKey, userATokenAccount, userA.publicKey, hookMint.publicKey, TOKEN_2022_PROGRAM_ID),
+        createAssociatedTokenAccountIdempotentInstruction(admin.publicKey, userBTokenAccount, userB.publicKey, hookMint.publicKey, TOKEN_2022_PROGRAM_ID),
+        createMintToInstruction(hookMint.publicKey, userATokenAccount, admin.publicKey, 1000 * 1e6, [], TOKEN_2022_PROGRAM_ID)
+      );
+      await provider.sendAndConfirm(createMintTx, [admin, hookMint]);
+
+      // Hook program writes its ExtraAccountMetaList (a per-mint counter PDA)
+      const extraAccountMetaList = getExtraAccountMetaAddress(hookMint.publicKey, hookProgram.programId);
+      [hookCounterPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("counter"), hookMint.publicKey.toBuffer()],
+        hookProgram.programId
+      );
+      await hookProgram.methods
+        .initializeExtraAccountMetaList()
+        .accounts({
+          payer: admin.publicKey,
+          extraAccountMetaList,
+          mint: hookMint.publicKey,
+          counter: hookCounterPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      hookAccounts = [
+        { pubkey: hookProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: extraAccountMetaList, isSigner: false, isWritable: false },
+        { pubkey: hookCounterPda, isSigner: false, isWritable: true },
+      ];
+
+      // TransferHook is deny-listed by default
+      const authority = await program.account.vaultAuthority.fetch(vaultAuthorityPda);
+      previousDeniedExtensions = authority.deniedExtensions;
+      const transferHookBit = new anchor.BN(1).shln(ExtensionType.TransferHook);
+      await program.methods
+        .setDeniedExtensions(previousDeniedExtensions.and(transferHookBit.notn(64)))
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+        })
+        .signers([admin])
+        .rpc();
+
+      [hookMintConfig] = PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_config"), hookMint.publicKey.toBuffer()],
+        program.programId
+      );
+      [hookMintStats] = PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_stats"), hookMint.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .initializeMintConfig({
+          approved: true,
+          allowedExtensions: transferHookBit,
+          allowFreezeAuthority: false,
+          maxDepositAmount: new anchor.BN(0),
+          maxVaultBalance: new anchor.BN(0),
+          maxTotalBalance: new anchor.BN(0),
+        })
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          tokenMint: hookMint.publicKey,
+          mintConfig: hookMintConfig,
+          mintStats: hookMintStats,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      [userAVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), userA.publicKey.toBuffer()],
+        program.programId
+      );
+      [userBVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), userB.publicKey.toBuffer()],
+        program.programId
+      );
+      for (const [user, vault, userTokenAccount] of [
+        [userA, userAVaultPda, userATokenAccount],
+        [userB, userBVaultPda, userBTokenAccount],
+      ] as [Keypair, PublicKey, PublicKey][]) {
+        await program.methods
+          .initializeVault()
+          .accounts({
+            user: user.publicKey,
+            tokenMint: hookMint.publicKey,
+            vaultAuthority: vaultAuthorityPda,
+            mintConfig: hookMintConfig,
+            mintStats: hookMintStats,
+            vault,
+            userTokenAccount,
+            vaultTokenAccount: vaultTokenAccountFor(vault),
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc();
+      }
+
+      console.log(`   Hook Mint: ${hookMint.publicKey.toString()}`);
+      console.log(`   Hook Program: ${hookProgram.programId.toString()}`);
+    });
+
+    after(async () => {
+      await program.methods
+        .setDeniedExtensions(previousDeniedExtensions)
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    const depositAccounts = () => ({
+      user: userA.publicKey,
+      vault: userAVaultPda,
+      tokenMint: hookMint.publicKey,
+      mintConfig: hookMintConfig,
+      mintStats: hookMintStats,
+      userTokenAccount: userATokenAccount,
+      vaultTokenAccount: vaultTokenAccountFor(userAVaultPda),
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    });
+
+    it("should fail to deposit without the hook accounts", async () => {
+      console.log("🧪 Starting Test 18: Transfer-Hook Mints");
+
+      try {
+        await program.methods
+          .deposit(new anchor.BN(100 * 1e6))
+          .accounts(depositAccounts())
+          .signers([userA])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.not.include("Should have thrown an error");
+        console.log("✅ Correctly rejected deposit without hook accounts");
+      }
+    });
+
+    it("should deposit, transfer and withdraw with the hook accounts", async () => {
+      const amount = new anchor.BN(100 * 1e6);
+
+      await program.methods
+        .deposit(amount)
+        .accounts(depositAccounts())
+        .remainingAccounts(hookAccounts)
+        .signers([userA])
+        .rpc();
+
+      await program.methods
+        .transferCollateral(new anchor.BN(40 * 1e6))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          fromVault: userAVaultPda,
+          toVault: userBVaultPda,
+          vaultAuthority: vaultAuthorityPda,
+          integratorStats: integratorStatsPda,
+          tokenMint: hookMint.publicKey,
+          fromVaultTokenAccount: vaultTokenAccountFor(userAVaultPda),
+          toVaultTokenAccount: vaultTokenAccountFor(userBVaultPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts(hookAccounts)
+        .signers([authorizedProgram])
+        .rpc();
+
+      await program.methods
+        .withdraw(new anchor.BN(40 * 1e6))
+        .accounts({
+          user: userB.publicKey,
+          vault: userBVaultPda,
+          tokenMint: hookMint.publicKey,
+          mintStats: hookMintStats,
+          userTokenAccount: userBTokenAccount,
+          vaultTokenAccount: vaultTokenAccountFor(userBVaultPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts(hookAccounts)
+        .signers([userB])
+        .rpc();
+
+      const vaultA = await program.account.collateralVault.fetch(userAVaultPda);
+      const vaultB = await program.account.collateralVault.fetch(userBVaultPda);
+      expect(vaultA.totalBalance.toString()).to.equal(new anchor.BN(60 * 1e6).toString());
+      expect(vaultB.totalBalance.toString()).to.equal("0");
+
+      const userBToken = await getAccount(provider.connection, userBTokenAccount, "confirmed", TOKEN_2022_PROGRAM_ID);
+      expect(userBToken.amount.toString()).to.equal(new anchor.BN(40 * 1e6).toString());
+
+      // Hook ran once per token movement
+      const counter = await hookProgram.account.hookCounter.fetch(hookCounterPda);
+      expect(counter.transfers.toNumber()).to.equal(3);
+      expect(counter.lastAmount.toString()).to.equal(new anchor.BN(40 * 1e6).toString());
+
+      console.log("✅ Hooked deposit, transfer and withdraw successful!");
+    });
+  });
+
+  // Test 19: Deposit Caps
+  describe("19. deposit caps", () => {
+    const setCaps = async (maxVaultBalance: anchor.BN, maxTotalBalance: anchor.BN) => {
+      await program.methods
+        .updateMintConfig({
+          approved: true,
+          allowedExtensions: new anchor.BN(0),
+          allowFreezeAuthority: true,
+          maxDepositAmount: new anchor.BN(0),
+          maxVaultBalance,
+          maxTotalBalance,
+        })
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          mintConfig: mintConfigPda,
+        })
+        .signers([admin])
+        .rpc();
+    };
+
+    const depositToUser2 = (amount: anchor.BN) =>
+      program.methods
+        .deposit(amount)
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultPda,
+          tokenMint: tokenMint.publicKey,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
+          userTokenAccount: user2TokenAccount,
+          vaultTokenAccount: getAssociatedTokenAddressSync(
+            tokenMint.publicKey,
+            user2VaultPda,
+            true,
+            TOKEN_2022_PROGRAM_ID
+          ),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc();
+
+    after(async () => {
+      await setCaps(new anchor.BN(0), new anchor.BN(0));
+    });
+
+    it("should track aggregate deposits per mint", async () => {
+      console.log("🧪 Starting Test 19: Deposit Caps");
+
+      const before = await program.account.mintStats.fetch(mintStatsPda);
+      await depositToUser2(new anchor.BN(1 * 1e6));
+      const after = await program.account.mintStats.fetch(mintStatsPda);
+
+      expect(after.totalBalance.sub(before.totalBalance).toString()).to.equal(new anchor.BN(1 * 1e6).toString());
+      expect(after.totalDeposited.sub(before.totalDeposited).toString()).to.equal(new anchor.BN(1 * 1e6).toString());
+      console.log(`   Mint TVL: ${after.totalBalance.toString()}`);
+    });
+
+    it("should enforce the per-vault balance cap", async () => {
+      const vault = await program.account.collateralVault.fetch(user2VaultPda);
+      await setCaps(vault.totalBalance, new anchor.BN(0));
+
+      try {
+        await depositToUser2(new anchor.BN(1));
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("DepositCapExceeded");
+        console.log("✅ Correctly rejected deposit above the vault cap");
+      }
+    });
+
+    it("should enforce the per-mint TVL cap", async () => {
+      const stats = await program.account.mintStats.fetch(mintStatsPda);
+      await setCaps(new anchor.BN(0), stats.totalBalance);
+
+      try {
+        await depositToUser2(new anchor.BN(1));
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("DepositCapExceeded");
+        console.log("✅ Correctly rejected deposit above the TVL cap");
+      }
+    });
+  });
+
+  // Test 20: Mint Statistics
+  describe("20. mint statistics", () => {
+    it("should track locks and report stats via return data", async () => {
+      console.log("🧪 Starting Test 20: Mint Statistics");
+
+      const before = await program.account.mintStats.fetch(mintStatsPda);
+
+      await program.methods
+        .lockCollateral(new anchor.BN(5 * 1e6))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          vault: user2VaultPda,
+          vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
+        })
+        .signers([authorizedProgram])
+        .rpc();
+
+      const stats = await program.methods
+        .getMintStats()
+        .accounts({
+          mintStats: mintStatsPda,
+        })
+        .view();
+
+      expect(stats.tokenMint.toString()).to.equal(tokenMint.publicKey.toString());
+      expect(stats.totalLocked.sub(before.totalLocked).toString()).to.equal(new anchor.BN(5 * 1e6).toString());
+      expect(stats.totalBalance.toString()).to.equal(before.totalBalance.toString());
+      expect(stats.totalDeposited.sub(stats.totalWithdrawn).toString()).to.equal(stats.totalBalance.toString());
+      expect(stats.vaultCount).to.equal(before.vaultCount);
+      expect(stats.unreconciled).to.equal(false);
+      console.log(`   Vaults: ${stats.vaultCount}, TVL: ${stats.totalBalance.toString()}, Locked: ${stats.totalLocked.toString()}`);
+
+      await program.methods
+        .unlockCollateral(new anchor.BN(5 * 1e6))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          vault: user2VaultPda,
+          vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
+        })
+        .signers([authorizedProgram])
+        .rpc();
+
+      const after = await program.account.mintStats.fetch(mintStatsPda);
+      expect(after.totalLocked.toString()).to.equal(before.totalLocked.toString());
+
+      console.log("✅ Mint statistics tracked!");
+    });
+  });
+
+  // Test 21: Integrator Exposure
+  describe("21. integrator exposure", () => {
+    const setCap = async (maxLocked: anchor.BN) => {
+      await program.methods
+        .setIntegratorCap(maxLocked)
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          integratorStats: integratorStatsPda,
+        })
+        .signers([admin])
+        .rpc();
+    };
+
+    const lockUser2 = (amount: anchor.BN) =>
+      program.methods
+        .lockCollateral(amount)
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          vault: user2VaultPda,
+          vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
+        })
+        .signers([authorizedProgram])
+        .rpc();
+
+    after(async () => {
+      await setCap(new anchor.BN(0));
+    });
+
+    it("should track the program's locked and transferred collateral", async () => {
+      console.log("🧪 Starting Test 21: Integrator Exposure");
+
+      const before = await program.account.integratorStats.fetch(integratorStatsPda);
+      // Test 7 and Test 18 transferred 100 and 40 tokens
+      expect(before.totalTransferred.toString()).to.equal(new anchor.BN(140 * 1e6).toString());
+
+      await lockUser2(new anchor.BN(5 * 1e6));
+
+      const after = await program.account.integratorStats.fetch(integratorStatsPda);
+      expect(after.lockedBalance.sub(before.lockedBalance).toString()).to.equal(new anchor.BN(5 * 1e6).toString());
+      expect(after.totalLocked.sub(before.totalLocked).toString()).to.equal(new anchor.BN(5 * 1e6).toString());
+      console.log(`   Locked: ${after.lockedBalance.toString()}, Lifetime: ${after.totalLocked.toString()}`);
+    });
+
+    it("should enforce the integrator's lock cap", async () => {
+      const stats = await program.account.integratorStats.fetch(integratorStatsPda);
+      await setCap(stats.lockedBalance);
+
+      try {
+        await lockUser2(new anchor.BN(1));
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("IntegratorCapExceeded");
+        console.log("✅ Correctly rejected lock above the integrator cap");
+      }
+
+      await program.methods
+        .unlockCollateral(new anchor.BN(5 * 1e6))
+        .accounts({
+          signer: authorizedProgram.publicKey,
+          vault: user2VaultPda,
+          vaultAuthority: vaultAuthorityPda,
+          mintStats: mintStatsPda,
+          integratorStats: integratorStatsPda,
+        })
+        .signers([authorizedProgram])
+        .rpc();
+
+      const after = await program.account.integratorStats.fetch(integratorStatsPda);
+      expect(after.lockedBalance.toString()).to.equal(stats.lockedBalance.sub(new anchor.BN(5 * 1e6)).toString());
+    });
+  });
+
+  // Test 22: Vault Migration
+  describe("22. migrate_vault", () => {
+    // Version-0 vault loaded from tests/fixtures/legacy_vault.json
+    const legacyOwner = new PublicKey("49mWUrG3VMfcbjVnxhQVgjHAnxdoc3RyMni7iV88MT6g");
+    const legacyVaultPda = new PublicKey("7wE3MkKQRD2wdB8CLPShsWbRfxe6UaQB5G4GAZUDWSjL");
+
+    const migrate = (owner: PublicKey, vault: PublicKey) =>
+      program.methods
+        .migrateVault()
+        .accounts({
+          payer: admin.publicKey,
+          owner,
+          vault,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+    it("should migrate a legacy vault to the current layout", async () => {
+      console.log("🧪 Starting Test 22: Vault Migration");
+
+      const legacy = await provider.connection.getAccountInfo(legacyVaultPda);
+      expect(legacy.data.length).to.equal(153);
+
+      await migrate(legacyOwner, legacyVaultPda);
+
+      const migrated = await provider.connection.getAccountInfo(legacyVaultPda);
+      expect(migrated.data.length).to.equal(program.account.collateralVault.size);
+      expect(migrated.data.subarray(0, 153).equals(legacy.data)).to.be.true;
+
+      const vault = await program.account.collateralVault.fetch(legacyVaultPda);
+      expect(vault.version).to.equal(2);
+      expect(vault.owner.toString()).to.equal(legacyOwner.toString());
+      expect(vault.totalBalance.toString()).to.equal(new anchor.BN(500 * 1e6).toString());
+      expect(vault.lockedBalance.toString()).to.equal(new anchor.BN(200 * 1e6).toString());
+      expect(vault.availableBalance.toString()).to.equal(new anchor.BN(300 * 1e6).toString());
+      expect(vault.reserved.every((b: number) => b === 0)).to.be.true;
+
+      // Transfers weren't counted before version 2, so the counters start at zero
+      expect(vault.totalTransferredIn.toString()).to.equal("0");
+      expect(vault.totalTransferredOut.toString()).to.equal("0");
+      expect(vault.totalSeized.toString()).to.equal("0");
+      expect(vault.totalFeesPaid.toString()).to.equal("0");
+      expect(vault.partialHistory).to.be.true;
+      // Its locks predate integrator positions, so no program is charged for them
+      expect(vault.unattributedLocked.toString()).to.equal(new anchor.BN(200 * 1e6).toString());
+
+      console.log("✅ Legacy vault migrated!");
+    });
+
+    it("should be idempotent", async () => {
+      const before = await provider.connection.getAccountInfo(legacyVaultPda);
+      await migrate(legacyOwner, legacyVaultPda);
+      const after = await provider.connection.getAccountInfo(legacyVaultPda);
+
+      expect(after.data.equals(before.data)).to.be.true;
+      expect(after.lamports).to.equal(before.lamports);
+
+      // Vaults created at the current version are left untouched
+      const current = await provider.connection.getAccountInfo(user2VaultPda);
+      await migrate(user2.publicKey, user2VaultPda);
+      const unchanged = await provider.connection.getAccountInfo(user2VaultPda);
+      expect(unchanged.data.equals(current.data)).to.be.true;
+
+      console.log("✅ Repeated migration is a no-op");
+    });
+  });
+
+  // Test 23: Zero-Copy Accounts (requires the program built with `--features zero-copy`)
+  describe("23. zero-copy accounts", () => {
+    let vaultAuthorityZcPda: PublicKey;
+    let user2VaultZcPda: PublicKey;
+    const computeUnits: Record<string, { borsh: number; zeroCopy: number }> = {};
+
+    const unitsConsumed = async (signature: string) => {
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return tx.meta.computeUnitsConsumed;
+    };
+
+    const vaultTokenAccountFor = (vault: PublicKey) =>
+      getAssociatedTokenAddressSync(tokenMint.publicKey, vault, true, TOKEN_2022_PROGRAM_ID);
+
+    before(async function () {
+      if (!program.idl.instructions.some((ix) => ix.name === "initialize_authority_zc")) {
+        console.log("⏭️  Skipping zero-copy tests: program built without zero-copy");
+        this.skip();
+      }
+
+      console.log("🔧 Setting up for Test 23: Zero-Copy Accounts");
+
+      [vaultAuthorityZcPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_authority_zc")],
+        program.programId
+      );
+      [user2VaultZcPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_zc"), user2.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeAuthorityZc([authorizedProgram.publicKey])
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityZcPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .initializeVaultZc()
+        .accounts({
+          user: user2.publicKey,
+          tokenMint: tokenMint.publicKey,
+          vaultAuthority: vaultAuthorityZcPda,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
+          vault: user2VaultZcPda,
+          vaultTokenAccount: vaultTokenAccountFor(user2VaultZcPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+      const authority = await program.account.vaultAuthorityZc.fetch(vaultAuthorityZcPda);
+      expect(authority.programCount).to.equal(1);
+      expect(authority.authorizedPrograms[0].toString()).to.equal(authorizedProgram.publicKey.toString());
+    });
+
+    after(async () => {
+      if (Object.keys(computeUnits).length === 0) return;
+      console.log("\n⛽ Compute units (Borsh vs zero-copy):");
+      for (const [name, units] of Object.entries(computeUnits)) {
+        console.log(`   ${name.padEnd(18)} ${String(units.borsh).padStart(7)} ${String(units.zeroCopy).padStart(7)}`);
+      }
+    });
+
+    it("should compare deposit compute units", async () => {
+      console.log("🧪 Starting Test 23: Zero-Copy Accounts");
+
+      const amount = new anchor.BN(10 * 1e6);
+      const borsh = await program.methods
+        .deposit(amount)
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultPda,
+          tokenMint: tokenMint.publicKey,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
+          userTokenAccount: user2TokenAccount,
+          vaultTokenAccount: vaultTokenAccountFor(user2VaultPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc({ commitment: "confirmed" });
+      const zeroCopy = await program.methods
+        .depositZc(amount)
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultZcPda,
+          tokenMint: tokenMint.publicKey,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
+          userTokenAccount: user2TokenAccount,
+          vaultTokenAccount: vaultTokenAccountFor(user2VaultZcPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc({ commitment: "confirmed" });
+      computeUnits.deposit = { borsh: await unitsConsumed(borsh), zeroCopy: await unitsConsumed(zeroCopy) };
+
+      const vault = await program.account.collateralVaultZc.fetch(user2VaultZcPda);
+      expect(vault.totalBalance.toString()).to.equal(amount.toString());
+      expect(vault.availableBalance.toString()).to.equal(amount.toString());
+    });
+
+    it("should compare lock and unlock compute units", async () => {
+      const amount = new anchor.BN(4 * 1e6);
+      const lockAccounts = {
+        signer: authorizedProgram.publicKey,
+        mintStats: mintStatsPda,
+        integratorStats: integratorStatsPda,
+      };
+
+      const borshLock = await program.methods
+        .lockCollateral(amount)
+        .accounts({ ...lockAccounts, vault: user2VaultPda, vaultAuthority: vaultAuthorityPda })
+        .signers([authorizedProgram])
+        .rpc({ commitment: "confirmed" });
+      const zeroCopyLock = await program.methods
+        .lockCollateralZc(amount)
+        .accounts({ ...lockAccounts, vault: user2VaultZcPda, vaultAuthority: vaultAuthorityZcPda })
+        .signers([authorizedProgram])
+        .rpc({ commitment: "confirmed" });
+      computeUnits.lock_collateral = { borsh: await unitsConsumed(borshLock), zeroCopy: await unitsConsumed(zeroCopyLock) };
+
+      const locked = await program.account.collateralVaultZc.fetch(user2VaultZcPda);
+      expect(locked.lockedBalance.toString()).to.equal(amount.toString());
+
+      const borshUnlock = await program.methods
+        .unlockCollateral(amount)
+        .accounts({ ...lockAccounts, vault: user2VaultPda, vaultAuthority: vaultAuthorityPda })
+        .signers([authorizedProgram])
+        .rpc({ commitment: "confirmed" });
+      const zeroCopyUnlock = await program.methods
+        .unlockCollateralZc(amount)
+        .accounts({ ...lockAccounts, vault: user2VaultZcPda, vaultAuthority: vaultAuthorityZcPda })
+        .signers([authorizedProgram])
+        .rpc({ commitment: "confirmed" });
+      computeUnits.unlock_collateral = { borsh: await unitsConsumed(borshUnlock), zeroCopy: await unitsConsumed(zeroCopyUnlock) };
+
+      // Skipping Borsh deserialization of the authority is the point of the zero-copy path
+      expect(computeUnits.lock_collateral.zeroCopy).to.be.lessThan(computeUnits.lock_collateral.borsh);
+      expect(computeUnits.unlock_collateral.zeroCopy).to.be.lessThan(computeUnits.unlock_collateral.borsh);
+    });
+
+    it("should reject zero-copy locks from unauthorized programs", async () => {
+      const unauthorizedSigner = Keypair.generate();
+
+      try {
+        await program.methods
+          .lockCollateralZc(new anchor.BN(1))
+          .accounts({
+            signer: unauthorizedSigner.publicKey,
+            vault: user2VaultZcPda,
+            vaultAuthority: vaultAuthorityZcPda,
+            mintStats: mintStatsPda,
+            integratorStats: integratorStatsPda,
+          })
+          .signers([unauthorizedSigner])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("UnauthorizedProgram");
+        console.log("✅ Correctly rejected unauthorized zero-copy lock");
+      }
+    });
+
+    it("should compare withdraw compute units and close the zero-copy vault", async () => {
+      const amount = new anchor.BN(10 * 1e6);
+
+      const borsh = await program.methods
+        .withdraw(amount)
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultPda,
+          tokenMint: tokenMint.publicKey,
+          mintStats: mintStatsPda,
+          userTokenAccount: user2TokenAccount,
+          vaultTokenAccount: vaultTokenAccountFor(user2VaultPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc({ commitment: "confirmed" });
+      const zeroCopy = await program.methods
+        .withdrawZc(amount)
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultZcPda,
+          tokenMint: tokenMint.publicKey,
+          mintStats: mintStatsPda,
+          userTokenAccount: user2TokenAccount,
+          vaultTokenAccount: vaultTokenAccountFor(user2VaultZcPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc({ commitment: "confirmed" });
+      computeUnits.withdraw = { borsh: await unitsConsumed(borsh), zeroCopy: await unitsConsumed(zeroCopy) };
+
+      const statsBefore = await program.account.mintStats.fetch(mintStatsPda);
+      await program.methods
+        .closeVaultZc()
+        .accounts({
+          user: user2.publicKey,
+          vault: user2VaultZcPda,
+          mintStats: mintStatsPda,
+        })
+        .signers([user2])
+        .rpc();
+      const statsAfter = await program.account.mintStats.fetch(mintStatsPda);
+      expect(statsAfter.vaultCount).to.equal(statsBefore.vaultCount - 1);
+
+      console.log("✅ Zero-copy vault lifecycle complete!");
+    });
+  });
+
+  // Test 24: View Instructions
+  describe("24. view instructions", () => {
+    it("should return vault balances", async () => {
+      console.log("🧪 Starting Test 24: View Instructions");
+
+      const balances = await program.methods
+        .getVaultBalances()
+        .accounts({
+          vault: user2VaultPda,
+        })
+        .view();
+      const vault = await program.account.collateralVault.fetch(user2VaultPda);
+
+      expect(balances.version).to.equal(3);
+      expect(balances.owner.toString()).to.equal(user2.publicKey.toString());
+      expect(balances.totalBalance.toString()).to.equal(vault.totalBalance.toString());
+      expect(balances.lockedBalance.toString()).to.equal(vault.lockedBalance.toString());
+      expect(balances.availableBalance.toString()).to.equal(vault.availableBalance.toString());
+      expect(balances.subAccountBalance.toString()).to.equal(vault.subAccountBalance.toString());
+      expect(balances.totalTransferredIn.toString()).to.equal(vault.totalTransferredIn.toString());
+      expect(balances.totalTransferredOut.toString()).to.equal(vault.totalTransferredOut.toString());
+      expect(balances.totalSeized.toString()).to.equal(vault.totalSeized.toString());
+      expect(balances.totalFeesPaid.toString()).to.equal(vault.totalFeesPaid.toString());
+      expect(balances.pooled).to.equal(false);
+    });
+
+    it("should return lock positions including sub-accounts", async () => {
+      const [subAccount0] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sub_account"), user2VaultPda.toBuffer(), Buffer.from([0])],
+        program.programId
+      );
+
+      const positions = await program.methods
+        .getLockPositions()
+        .accounts({
+          vault: user2VaultPda,
+        })
+        .remainingAccounts([{ pubkey: subAccount0, isSigner: false, isWritable: false }])
+        .view();
+      const sub0 = await program.account.subAccount.fetch(subAccount0);
+
+      expect(positions.version).to.equal(2);
+      expect(positions.subAccounts.length).to.equal(1);
+      expect(positions.subAccounts[0].id).to.equal(0);
+      expect(positions.subAccounts[0].lockedBalance.toString()).to.equal(sub0.lockedBalance.toString());
+
+      // Sub-accounts of another vault are rejected
+      try {
+        await program.methods
+          .getLockPositions()
+          .accounts({
+            vault: user1VaultPda,
+          })
+          .remainingAccounts([{ pubkey: subAccount0, isSigner: false, isWritable: false }])
+          .view();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("InvalidSubAccount");
+        console.log("✅ Correctly rejected foreign sub-account");
+      }
+    });
+
+    it("should return the authority config", async () => {
+      const config = await program.methods
+        .getAuthorityConfig()
+        .accounts({
+          vaultAuthority: vaultAuthorityPda,
+        })
+        .view();
+      const authority = await program.account.vaultAuthority.fetch(vaultAuthorityPda);
+
+      expect(config.version).to.equal(2);
+      expect(config.admin.toString()).to.equal(admin.publicKey.toString());
+      expect(config.authorizedPrograms.map((p: PublicKey) => p.toString()))
+        .to.deep.equal(authority.authorizedPrograms.map((p: PublicKey) => p.toString()));
+      expect(config.deniedExtensions.toString()).to.equal(authority.deniedExtensions.toString());
+
+      console.log("✅ View instructions return versioned data!");
+    });
+  });
+
+  // Test 25: Example Integrator
+  describe("25. example integrator via collateral_vault_interface", () => {
+    const user4 = Keypair.generate();
+    let integratorPda: PublicKey;
+    let exampleStatsPda: PublicKey;
+    let examplePositionPda: PublicKey;
+    let user4VaultPda: PublicKey;
+    let user4TokenAccount: PublicKey;
+
+    const vaultTokenAccountFor = (vault: PublicKey) =>
+      getAssociatedTokenAddressSync(tokenMint.publicKey, vault, true, TOKEN_2022_PROGRAM_ID);
+
+    const positionAccounts = () => ({
+      integrator: integratorPda,
+      vault: user4VaultPda,
+      vaultAuthority: vaultAuthorityPda,
+      mintStats: mintStatsPda,
+      integratorStats: exampleStatsPda,
+      integratorPosition: examplePositionPda,
+      vaultProgram: program.programId,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      console.log("🔧 Setting up for Test 25: Example Integrator");
+
+      // The integrator signs its CPIs with this PDA, so the PDA is what gets authorized
+      [integratorPda] = PublicKey.findProgramAddressSync([Buffer.from("integrator")], integratorProgram.programId);
+      [exampleStatsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("integrator_stats"), integratorPda.toBuffer()],
+        program.programId
+      );
+      [user4VaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), user4.publicKey.toBuffer()],
+        program.programId
+      );
+      [examplePositionPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("integrator_position"), integratorPda.toBuffer(), user4VaultPda.toBuffer()],
+        program.programId
+      );
+      user4TokenAccount = getAssociatedTokenAddressSync(tokenMint.publicKey, user4.publicKey, false, TOKEN_2022_PROGRAM_ID);
+
+      const airdrop = await provider.connection.requestAirdrop(user4.publicKey, 2 * LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(airdrop);
+      // The integrator PDA pays for its stats and its position in the vault on first use
+      const integratorAirdrop = await provider.connection.requestAirdrop(integratorPda, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(integratorAirdrop);
+      await provider.sendAndConfirm(
+        new anchor.web3.Transaction().add(
+          createAssociatedTokenAccountIdempotentInstruction(admin.publicKey, user4TokenAccount, user4.publicKey, tokenMint.publicKey, TOKEN_2022_PROGRAM_ID),
+          createMintToInstruction(tokenMint.publicKey, user4TokenAccount, admin.publicKey, 100 * 1e6, [], TOKEN_2022_PROGRAM_ID)
+        ),
+        [admin]
+      );
+
+      await program.methods
+        .initializeVault()
+        .accounts({
+          user: user4.publicKey,
+          tokenMint: tokenMint.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
+          vault: user4VaultPda,
+          userTokenAccount: user4TokenAccount,
+          vaultTokenAccount: vaultTokenAccountFor(user4VaultPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user4])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(100 * 1e6))
+        .accounts({
+          user: user4.publicKey,
+          vault: user4VaultPda,
+          tokenMint: tokenMint.publicKey,
+          mintConfig: mintConfigPda,
+          mintStats: mintStatsPda,
+          userTokenAccount: user4TokenAccount,
+          vaultTokenAccount: vaultTokenAccountFor(user4VaultPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user4])
+        .rpc();
+
+      await program.methods
+        .addAuthorizedProgram(integratorPda)
+        .accounts({
+          admin: admin.publicKey,
+          vaultAuthority: vaultAuthorityPda,
+        })
+        .signers([admin])
+        .rpc();
+
+      // No initializeIntegratorStats: the first lock creates the integrator's stats
+      console.log(`   Integrator PDA: ${integratorPda.toString()}`);
+    });
+
+    it("should lock and unlock margin through the interface crate", async () => {
+      console.log("🧪 Starting Test 25: Example Integrator");
+
+      await integratorProgram.methods
+        .openPosition(new anchor.BN(60 * 1e6))
+        .accounts(positionAccounts())
+        .rpc();
+
+      let vault = await program.account.collateralVault.fetch(user4VaultPda);
+      expect(vault.lockedBalance.toString()).to.equal(new anchor.BN(60 * 1e6).toString());
+      expect(vault.availableBalance.toString()).to.equal(new anchor.BN(40 * 1e6).toString());
+
+      const stats = await program.account.integratorStats.fetch(exampleStatsPda);
+      expect(stats.lockedBalance.toString()).to.equal(new anchor.BN(60 * 1e6).toString());
+
+      await integratorProgram.methods
+        .closePosition(new anchor.BN(60 * 1e6))
+        .accounts(positionAccounts())
+        .rpc();
+
+      vault = await program.account.collateralVault.fetch(user4VaultPda);
+      expect(vault.lockedBalance.toNumber()).to.equal(0);
+
+      console.log("✅ Margin posted and released via CPI!");
+    });
+
+    it("should reject positions above the available margin", async () => {
+      try {
+        await integratorProgram.methods
+          .openPosition(new anchor.BN(101 * 1e6))
+          .accounts(positionAccounts())
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("InsufficientMargin");
+        console.log("✅ Correctly rejected position read from get_vault_balances");
+      }
+    });
+
+    it("should settle collateral between vaults", async () => {
+      const settleAmount = new anchor.BN(25 * 1e6);
+      const before = await program.account.collateralVault.fetch(user2VaultPda);
+
+      await integratorProgram.methods
+        .settle(settleAmount)
+        .accounts({
+          integrator: integratorPda,
+          fromVault: user4VaultPda,
+          toVault: user2VaultPda,
+          vaultAuthority: vaultAuthorityPda,
+          integratorStats: exampleStatsPda,
+          tokenMint: tokenMint.publicKey,
+          fromVaultTokenAccount: vaultTokenAccountFor(user4VaultPda),
+          toVaultTokenAccount: vaultTokenAccountFor(user2VaultPda),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          vaultProgram: program.programId,
+        })
+        .rpc();
+
+      const from = await program.account.collateralVault.fetch(user4VaultPda);
+      const to = await program.account.collateralVault.fetch(user2VaultPda);
+      const stats = await program.account.integratorStats.fetch(exampleStatsPda);
+
+      expect(from.totalBalance.toString()).to.equal(new anchor.BN(75 * 1e6).toString());
+      expect(to.totalBalance.sub(before.totalBalance).toString()).to.equal(settleAmount.toString());
+      expect(stats.totalTransferred.toString()).to.equal(settleAmount.toString());
+
+      console.log("✅ Settlement via interface crate successful!");
+    });
+  });
+
+  // Test 26: Invariant Check
+  describe("26. assert_invariants", () => {
+    const vaultTokenAccountFor = (vault: PublicKey) =>
+      getAssociatedTokenAddressSync(tokenMint.publicKey, vault, true, TOKEN_2022_PROGRAM_ID);
+
+    it("should pass for consistent vaults", async () => {
+      console.log("🧪 Starting Test 26: Assert Invariants");
+
+      for (const vault of [user2VaultPda, user4VaultPda]) {
+        await program.methods
+          .assertInvariants()
+          .accounts({
+            vault,
+            vaultTokenAccount: vaultTokenAccountFor(vault),
+          })
+          .rpc();
+        console.log(`   ${vault.toString()}: ok`);
+      }
+
+      console.log("✅ Invariants hold after settlement!");
+    });
+
+    it("should fail for a token account that isn't the vault's", async () => {
+      console.log("🧪 Testing invariant check against another vault's token account");
+
+      try {
+        await program.methods
+          .assertInvariants()
+          .accounts({
+            vault: user2VaultPda,
+            vaultTokenAccount: vaultTokenAccountFor(user4VaultPda),
+          })
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.message).to.include("ConstraintAddress");
+        console.log("✅ Correctly rejected mismatched token account");
+      }
+    });
+  });
+
+  // Test 27: Event Decoding
+  describe("27. event decoding", () => {
+    // anchor_lang::event::EVENT_IX_TAG, little-endian
+    const EVENT_IX_TAG = Buffer.from("e445a52e51cb9a1d", "hex");
+
+    // Event self-CPIs when built with `event-cpi`, `Program data:` log lines otherwise
+    const eventsOf = async (signature: string) => {
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const accountKeys = tx.transaction.message.getAccountKeys({
+        accountKeysFromLookups: tx.meta.loadedAddresses,
+      });
+
+      const cpiEvents = (tx.meta.innerInstructions ?? [])
+        .flatMap((inner) => inner.instructions)
+        .filter((ix) => accountKeys.get(ix.programIdIndex).equals(program.programId))
+        .map((ix) => Buffer.from(anchor.utils.bytes.bs58.decode(ix.data)))
+        .filter((data) => data.subarray(0, 8).equals(EVENT_IX_TAG))
+        .map((data) => program.coder.events.decode(data.subarray(8).toString("base64")));
+      if (cpiEvents.length > 0) {
+        return { channel: "event CPI", events: cpiEvents };
+      }
+
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      return { channel: "logs", events: [...parser.parseLogs(tx.meta.logMessages)] };
+    };
+
+    it("should decode lock and unlock events", async () => {
+      console.log("🧪 Starting Test 27: Event Decoding");
+
+      const amount = new anchor.BN(1);
+      const accounts = {
+        signer: authorizedProgram.publicKey,
+        vault: user2VaultPda,
+        vaultAuthority: vaultAuthorityPda,
+        mintStats: mintStatsPda,
+        integratorStats: integratorStatsPda,
+      };
+
+      const lockSignature = await program.methods
+        .lockCollateral(amount)
+        .accounts(accounts)
+        .signers([authorizedProgram])
+        .rpc({ commitment: "confirmed" });
+      const locked = await eventsOf(lockSignature);
+      console.log(`   Lock events via ${locked.channel}`);
+
+      expect(locked.events.map((event) => event.name)).to.deep.equal(["lockEvent"]);
+      const lockEvent = locked.events[0].data;
+      const afterLock = await program.account.collateralVault.fetch(user2VaultPda);
+      expect(lockEvent.vault.toString()).to.equal(user2VaultPda.toString());
+      expect(lockEvent.tokenMint.toString()).to.equal(tokenMint.publicKey.toString());
+      expect(lockEvent.amount.toString()).to.equal("1");
+      expect(lockEvent.totalBalance.toString()).to.equal(afterLock.totalBalance.toString());
+      expect(lockEvent.lockedBalance.toString()).to.equal(afterLock.lockedBalance.toString());
+      expect(lockEvent.availableBalance.toString()).to.equal(afterLock.availableBalance.toString());
+
+      const unlockSignature = await program.methods
+        .unlockCollateral(amount)
+        .accounts(accounts)
+        .signers([authorizedProgram])
+        .rpc({ commitment: "confirmed" });
+      const unlocked = await eventsOf(unlockSignature);
+
+      expect(unlocked.channel).to.equal(locked.channel);
+      expect(unlocked.events.map((event) => event.name)).to.deep.equal(["unlockEvent"]);
+      const unlockEvent = unlocked.events[0].data;
+      const afterUnlock = await program.account.collateralVault.fetch(user2VaultPda);
+      expect(unlockEvent.totalBalance.toString()).to.equal(afterUnlock.totalBalance.toString());
+      expect(unlockEvent.lockedBalance.toString()).to.equal(afterUnlock.lockedBalance.toString());
+      expect(unlockEvent.availableBalance.toString()).to.equal(afterUnlock.availableBalance.toString());
+      expect(unlockEvent.caller.toString()).to.equal(authorizedProgram.publicKey.toString());
+
+      console.log("✅ Events decoded and match on-chain state!");
+    });
+
+    it("should number each vault's events in sequence", async () => {
+      const amount = new anchor.BN(1);
+      const accounts = {
+        signer: authorizedProgram.publicKey,
+        vault: user2VaultPda,
+        vaultAuthority: vaultAuthorityPda,
+        mintStats: mintStatsPda,
+        integratorStats: integratorStatsPda,
+      };
+      const before = await program.account.collateralVault.fetch(user2VaultPda);
+
+      const lockSignature = await program.methods
+        .lockCollateral(amount)
+        .accounts(accounts)
+        .signers([authorizedProgram])
+        .rpc({ commitment: "confirmed" });
+      const unlockSignature = await program.methods
+        .unlockCollateral(amount)
+        .accounts(accounts)
+        .signers([authorizedProgram])
+        .rpc({ commitment: "confirmed" });
+
+      const lockEvent = (await eventsOf(lockSignature)).events[0].data;
+      const unlockEvent = (await eventsOf(unlockSignature)).events[0].data;
+      expect(lockEvent.sequence.toString()).to.equal(before.sequence.addn(1).toString());
+      expect(unlockEvent.sequence.toString()).to.equal(before.sequence.addn(2).toString());
+
+      const after = await program.account.collateralVault.fetch(user2VaultPda);
+      expect(after.sequence.toString()).to.equal(unlockEvent.sequence.toString());
+
+      console.log(`✅ Events numbered ${lockEvent.sequence} and ${unlockEvent.sequence}!`);
+    });
   });
 
   // Final summary